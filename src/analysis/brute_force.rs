@@ -0,0 +1,91 @@
+use crate::{analysis::scoring::Scorer, traits::CryptographicIter};
+
+/// A key that was tried, along with the plaintext it gives and how well that scored
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate<K> {
+    key: K,
+    score: f64,
+    plaintext: Vec<u8>,
+}
+
+impl<K> Candidate<K> {
+    #[inline]
+    pub fn new(key: K, score: f64, plaintext: Vec<u8>) -> Self {
+        Candidate {
+            key,
+            score,
+            plaintext,
+        }
+    }
+
+    #[inline]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    #[inline]
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    #[inline]
+    pub fn plaintext(&self) -> &[u8] {
+        &self.plaintext
+    }
+}
+
+/// Tries every key and keeps the `top` best scoring ones, best first. Keys that score the same stay in the order
+/// they were tried.
+pub fn rank<K, S: Scorer + ?Sized, F: FnMut(&K) -> Vec<u8>>(
+    keys: impl IntoIterator<Item = K>,
+    mut decrypt: F,
+    scorer: &S,
+    top: usize,
+) -> Vec<Candidate<K>> {
+    let mut candidates: Vec<Candidate<K>> = keys
+        .into_iter()
+        .map(|key| {
+            let plaintext = decrypt(&key);
+            Candidate::new(key, scorer.score_bytes(&plaintext), plaintext)
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates.truncate(top);
+    candidates
+}
+
+/// Tries every `caesar_shift` amount. The key of a candidate is the shift the message was encrypted with.
+pub fn break_caesar<I: CryptographicIter, S: Scorer + ?Sized>(
+    ciphertext: I,
+    scorer: &S,
+    top: usize,
+) -> Vec<Candidate<u8>> {
+    let ciphertext: Vec<u8> = ciphertext.collect();
+    rank(
+        0..=u8::MAX,
+        |&shift| {
+            ciphertext
+                .clone()
+                .into_iter()
+                .caesar_unshift(shift)
+                .collect()
+        },
+        scorer,
+        top,
+    )
+}
+
+/// Tries every single-byte XOR key
+pub fn break_single_byte_xor<I: CryptographicIter, S: Scorer + ?Sized>(
+    ciphertext: I,
+    scorer: &S,
+    top: usize,
+) -> Vec<Candidate<u8>> {
+    let ciphertext: Vec<u8> = ciphertext.collect();
+    rank(
+        0..=u8::MAX,
+        |&key| ciphertext.iter().map(|b| b ^ key).collect(),
+        scorer,
+        top,
+    )
+}
@@ -0,0 +1,233 @@
+use crate::{
+    analysis::{brute_force::Candidate, scoring::Scorer},
+    traits::{CryptographicIter, InspectableState},
+};
+
+/// Slides `crib` across the XOR of two ciphertexts encrypted with the same keystream, e.g.
+/// `c1.xor_truncating(c2)`, which is also the XOR of their plaintexts.
+///
+/// The key of a candidate is the offset of the crib in one of the messages, and its plaintext is what that reveals
+/// of the other message at the same offset. The `top` best scoring offsets are kept, best first.
+pub fn drag_crib<I: CryptographicIter, S: Scorer + ?Sized>(
+    xored: I,
+    crib: &[u8],
+    scorer: &S,
+    top: usize,
+) -> Vec<Candidate<usize>> {
+    let xored: Vec<u8> = xored.collect();
+    if crib.is_empty() || crib.len() > xored.len() {
+        return Vec::new();
+    }
+
+    let mut candidates: Vec<Candidate<usize>> = (0..=xored.len() - crib.len())
+        .map(|offset| {
+            let revealed = reveal(&xored[offset..], crib);
+            Candidate::new(offset, scorer.score_bytes(&revealed), revealed)
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.score().total_cmp(&a.score()));
+    candidates.truncate(top);
+    candidates
+}
+
+#[inline]
+fn reveal(xored: &[u8], crib: &[u8]) -> Vec<u8> {
+    xored.iter().zip(crib).map(|(x, c)| x ^ c).collect()
+}
+
+// a guess at the plaintext, along with the keystream it replaced, to undo it with
+#[derive(Clone, Debug, PartialEq)]
+struct Placement {
+    offset: usize,
+    previous: Vec<Option<u8>>,
+}
+
+/// A guided attack on several ciphertexts encrypted with the same keystream, e.g. a reused one-time pad.
+///
+/// Every guess at a piece of the plaintext of one message reveals the keystream under it, and with it the other
+/// messages at the same offsets, which in turn suggest what to guess next.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CribSession {
+    ciphertexts: Vec<Vec<u8>>,
+    // as long as the longest ciphertext
+    keystream: Vec<Option<u8>>,
+    history: Vec<Placement>,
+}
+
+impl CribSession {
+    /// At least two ciphertexts are needed
+    pub fn new(ciphertexts: Vec<Vec<u8>>) -> Result<Self, std::io::Error> {
+        if ciphertexts.len() < 2 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "At least two ciphertexts encrypted with the same keystream are needed",
+            ));
+        }
+
+        let len = ciphertexts.iter().map(Vec::len).max().unwrap_or(0);
+        Ok(CribSession {
+            ciphertexts,
+            keystream: vec![None; len],
+            history: Vec::new(),
+        })
+    }
+
+    #[inline]
+    pub fn ciphertexts(&self) -> &[Vec<u8>] {
+        &self.ciphertexts
+    }
+
+    /// The keystream recovered so far, byte by byte
+    #[inline]
+    pub fn keystream(&self) -> &[Option<u8>] {
+        &self.keystream
+    }
+
+    /// The number of keystream bytes recovered so far
+    pub fn known(&self) -> usize {
+        self.keystream.iter().filter(|k| k.is_some()).count()
+    }
+
+    /// Decrypts any ciphertext encrypted with the same keystream, as far as it is known
+    pub fn decrypt<I: CryptographicIter>(&self, ciphertext: I) -> Vec<Option<u8>> {
+        ciphertext
+            .zip(self.keystream.iter().chain(std::iter::repeat(&None)))
+            .map(|(c, k)| k.map(|k| c ^ k))
+            .collect()
+    }
+
+    /// The plaintext of one of the messages, as far as it is known
+    pub fn plaintext(&self, message: usize) -> Result<Vec<Option<u8>>, std::io::Error> {
+        Ok(self.decrypt(Vec::from(self.ciphertext(message)?).into_iter()))
+    }
+
+    fn ciphertext(&self, message: usize) -> Result<&[u8], std::io::Error> {
+        self.ciphertexts
+            .get(message)
+            .map(Vec::as_slice)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "There is no message {}, only {}",
+                        message,
+                        self.ciphertexts.len()
+                    ),
+                )
+            })
+    }
+
+    /// Slides `crib` across `message`, scoring every offset by what it reveals of the other messages. The
+    /// plaintext of a candidate is those revealed pieces, one line for every other message that reaches that far.
+    pub fn drag<S: Scorer + ?Sized>(
+        &self,
+        message: usize,
+        crib: &[u8],
+        scorer: &S,
+        top: usize,
+    ) -> Result<Vec<Candidate<usize>>, std::io::Error> {
+        let ciphertext = self.ciphertext(message)?;
+        if crib.is_empty() || crib.len() > ciphertext.len() {
+            return Ok(Vec::new());
+        }
+
+        let mut candidates: Vec<Candidate<usize>> = (0..=ciphertext.len() - crib.len())
+            .map(|offset| {
+                let keystream = reveal(&ciphertext[offset..], crib);
+                let pieces: Vec<Vec<u8>> = self
+                    .ciphertexts
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, c)| i != message && c.len() > offset)
+                    .map(|(_, c)| reveal(&c[offset..], &keystream))
+                    .collect();
+
+                // NOTE: every piece is scored on its own, so that no n-gram spans two messages
+                let score = if pieces.is_empty() {
+                    f64::NEG_INFINITY
+                } else {
+                    pieces.iter().map(|p| scorer.score_bytes(p)).sum::<f64>() / pieces.len() as f64
+                };
+                Candidate::new(offset, score, pieces.join(&b'\n'))
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.score().total_cmp(&a.score()));
+        candidates.truncate(top);
+        Ok(candidates)
+    }
+
+    /// Takes `plaintext` to be the plaintext of `message` at `offset`, and recovers the keystream under it. A later
+    /// guess overwrites an earlier one where they overlap.
+    pub fn place(
+        &mut self,
+        message: usize,
+        offset: usize,
+        plaintext: &[u8],
+    ) -> Result<(), std::io::Error> {
+        let ciphertext = self.ciphertext(message)?;
+        let end = offset.saturating_add(plaintext.len());
+        if end > ciphertext.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "The plaintext reaches byte {}, past the end of message {}, which is {} bytes long",
+                    end,
+                    message,
+                    ciphertext.len()
+                ),
+            ));
+        }
+
+        let keystream = reveal(&ciphertext[offset..], plaintext);
+        let previous = self.keystream[offset..end].to_vec();
+        self.keystream[offset..end]
+            .iter_mut()
+            .zip(keystream)
+            .for_each(|(k, b)| *k = Some(b));
+        self.history.push(Placement { offset, previous });
+        Ok(())
+    }
+
+    /// Takes back the last guess. Returns false if there was nothing to take back.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(placement) => {
+                let end = placement.offset + placement.previous.len();
+                self.keystream[placement.offset..end].copy_from_slice(&placement.previous);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl InspectableState for CribSession {
+    // every message on its own line, with `_` for bytes that are not known yet, and `.` for ones that can not be
+    // printed
+    fn inspect_state(&self) -> String {
+        let mut lines = vec![format!(
+            "{} of {} keystream bytes known, {} guesses",
+            self.known(),
+            self.keystream.len(),
+            self.history.len()
+        )];
+        lines.extend(
+            self.ciphertexts
+                .iter()
+                .enumerate()
+                .map(|(message, ciphertext)| {
+                    let plaintext: String = self
+                        .decrypt(ciphertext.clone().into_iter())
+                        .into_iter()
+                        .map(|b| match b {
+                            Some(b) if b == b' ' || b.is_ascii_graphic() => b as char,
+                            Some(_) => '.',
+                            None => '_',
+                        })
+                        .collect();
+                    format!("{}: {}", message + 1, plaintext)
+                }),
+        );
+        lines.join("\n")
+    }
+}
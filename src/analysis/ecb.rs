@@ -0,0 +1,311 @@
+use std::{cell::Cell, collections::HashMap};
+
+use crate::traits::{CryptographicIter, InspectableState};
+
+// NOTE: blocks shorter than this repeat by chance too often in random ciphertext to tell anything
+const MIN_BLOCK_SIZE: usize = 8;
+// the longest block size tried against an oracle
+const MAX_BLOCK_SIZE: usize = 255;
+// the heatmap is drawn with this many blocks on every row
+const HEATMAP_WIDTH: usize = 16;
+
+/// How often the blocks of a ciphertext repeat, for one block size
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockRepetition {
+    block_size: usize,
+    // the number of times every block occurs in the whole ciphertext
+    heatmap: Vec<usize>,
+    // the index of the first block that every block is the same as
+    first: Vec<usize>,
+    // the blocks that are the same as an earlier one
+    repeated: usize,
+}
+
+impl BlockRepetition {
+    /// Cuts the ciphertext into blocks of `block_size` bytes, leaving out a shorter last block
+    pub fn new(ciphertext: &[u8], block_size: usize) -> Self {
+        assert!(
+            block_size > 0,
+            "The blocks have to be at least one byte long"
+        );
+
+        let blocks: Vec<&[u8]> = ciphertext.chunks_exact(block_size).collect();
+        // the first occurrence and the count of every distinct block
+        let mut seen: HashMap<&[u8], (usize, usize)> = HashMap::new();
+        blocks.iter().enumerate().for_each(|(i, b)| {
+            seen.entry(b).or_insert((i, 0)).1 += 1;
+        });
+
+        BlockRepetition {
+            block_size,
+            heatmap: blocks.iter().map(|b| seen[b].1).collect(),
+            first: blocks.iter().map(|b| seen[b].0).collect(),
+            repeated: blocks.len() - seen.len(),
+        }
+    }
+
+    #[inline]
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    #[inline]
+    pub fn blocks(&self) -> usize {
+        self.heatmap.len()
+    }
+
+    /// The number of blocks that are the same as an earlier one
+    #[inline]
+    pub fn repeated(&self) -> usize {
+        self.repeated
+    }
+
+    /// The fraction of the blocks that are the same as an earlier one
+    pub fn fraction(&self) -> f64 {
+        if self.heatmap.is_empty() {
+            0.0
+        } else {
+            self.repeated as f64 / self.heatmap.len() as f64
+        }
+    }
+
+    /// The number of times every block occurs in the whole ciphertext
+    #[inline]
+    pub fn heatmap(&self) -> &[usize] {
+        &self.heatmap
+    }
+}
+
+/// The block sizes that could have been used to encrypt a ciphertext in the ECB mode, along with how often the
+/// blocks repeat for each of them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EcbDetection {
+    // the most repeated first
+    guesses: Vec<BlockRepetition>,
+}
+
+impl EcbDetection {
+    #[inline]
+    pub fn guesses(&self) -> &[BlockRepetition] {
+        &self.guesses
+    }
+
+    #[inline]
+    pub fn best(&self) -> Option<&BlockRepetition> {
+        self.guesses.first()
+    }
+
+    /// Whether any block repeats, which almost never happens by chance in any other mode
+    pub fn is_ecb(&self) -> bool {
+        self.best().is_some_and(|b| b.repeated > 0)
+    }
+}
+
+impl InspectableState for EcbDetection {
+    // every guess, followed by the heatmap of the best one, with a `.` for every block that occurs once and the same
+    // letter for every occurrence of a repeated block
+    fn inspect_state(&self) -> String {
+        let mut lines: Vec<String> = self
+            .guesses
+            .iter()
+            .map(|g| {
+                format!(
+                    "Block size {}: {} of {} blocks repeated",
+                    g.block_size,
+                    g.repeated,
+                    g.blocks()
+                )
+            })
+            .collect();
+
+        if let Some(best) = self.best().filter(|_| self.is_ecb()) {
+            lines.push(format!("Heatmap for block size {}", best.block_size));
+
+            let mut labels: HashMap<usize, char> = HashMap::new();
+            let mut next = b'A';
+            let cells: Vec<char> = best
+                .heatmap
+                .iter()
+                .enumerate()
+                .map(|(i, &count)| {
+                    if count == 1 {
+                        return '.';
+                    }
+                    *labels.entry(best.first[i]).or_insert_with(|| {
+                        let label = if next <= b'Z' { next as char } else { '#' };
+                        next = next.saturating_add(1);
+                        label
+                    })
+                })
+                .collect();
+            lines.extend(cells.chunks(HEATMAP_WIDTH).enumerate().map(|(row, c)| {
+                format!(
+                    "{:6} {}",
+                    row * HEATMAP_WIDTH * best.block_size,
+                    c.iter().collect::<String>()
+                )
+            }));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Looks for repeated blocks for every block size from 8 bytes up to `max_block_size` that the length of the
+/// ciphertext is a multiple of
+pub fn detect_ecb<I: CryptographicIter>(ciphertext: I, max_block_size: usize) -> EcbDetection {
+    let ciphertext: Vec<u8> = ciphertext.collect();
+    let mut guesses: Vec<BlockRepetition> = (MIN_BLOCK_SIZE..=max_block_size)
+        .filter(|&size| size <= ciphertext.len() && ciphertext.len().is_multiple_of(size))
+        .map(|size| BlockRepetition::new(&ciphertext, size))
+        .collect();
+
+    // NOTE: the halves of a repeated block repeat as well, so of two block sizes that repeat as much, the longer
+    // one is more likely
+    guesses.sort_by(|a, b| {
+        b.fraction()
+            .total_cmp(&a.fraction())
+            .then(b.block_size.cmp(&a.block_size))
+    });
+    EcbDetection { guesses }
+}
+
+/// The outcome of a byte-at-a-time attack on an ECB oracle, along with every step that led to it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EcbAttack {
+    block_size: usize,
+    secret: Vec<u8>,
+    queries: usize,
+    trace: Vec<String>,
+}
+
+impl EcbAttack {
+    #[inline]
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// What the oracle appends to every input before encrypting it
+    #[inline]
+    pub fn secret(&self) -> &[u8] {
+        &self.secret
+    }
+
+    /// The number of times the oracle was asked
+    #[inline]
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    #[inline]
+    pub fn trace(&self) -> &[String] {
+        &self.trace
+    }
+}
+
+impl InspectableState for EcbAttack {
+    fn inspect_state(&self) -> String {
+        self.trace.join("\n")
+    }
+}
+
+/// Recovers the secret that an oracle appends to its input before encrypting both in the ECB mode, with PKCS#7
+/// padding, one byte at a time.
+///
+/// Every byte of the secret is lined up as the last byte of a block whose other bytes are known, and compared with
+/// every possible last byte, so it costs up to 256 queries.
+pub fn break_ecb_suffix<O: Fn(&[u8]) -> Vec<u8>>(oracle: O) -> Result<EcbAttack, std::io::Error> {
+    let queries = Cell::new(0);
+    let ask = |input: &[u8]| {
+        queries.set(queries.get() + 1);
+        oracle(input)
+    };
+    let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+    let mut trace = Vec::new();
+
+    // the ciphertext grows by a whole block once the input fills up the padding
+    let empty = ask(&[]).len();
+    let (block_size, secret_len) = (1..=MAX_BLOCK_SIZE)
+        .find_map(|i| {
+            let len = ask(&vec![b'A'; i]).len();
+            (len > empty).then(|| (len - empty, empty.saturating_sub(i)))
+        })
+        .ok_or_else(|| {
+            invalid(format!(
+                "The ciphertext did not grow for up to {} bytes of input, so it is not a block cypher",
+                MAX_BLOCK_SIZE
+            ))
+        })?;
+    if block_size < 2 {
+        return Err(invalid(String::from(
+            "The ciphertext grows with every byte of input, so it is not a block cypher",
+        )));
+    }
+    trace.push(format!(
+        "Block size {}, and a secret of {} bytes, from the ciphertext growing from {} bytes to {}",
+        block_size,
+        secret_len,
+        empty,
+        empty + block_size
+    ));
+
+    let repetition = BlockRepetition::new(&ask(&vec![b'A'; 3 * block_size]), block_size);
+    if repetition.heatmap.first().is_none_or(|&c| c < 2) {
+        return Err(invalid(String::from(
+            "Blocks of the same input are encrypted differently, so the oracle does not use the ECB mode",
+        )));
+    }
+    trace.push(format!(
+        "Three blocks of the same input give {} repeated blocks, so the mode is ECB",
+        repetition.repeated
+    ));
+
+    // the ciphertext of every amount of input that lines up the next byte of the secret at the end of a block
+    let targets: Vec<Vec<u8>> = (0..block_size)
+        .map(|padding| ask(&vec![b'A'; padding]))
+        .collect();
+
+    let mut secret: Vec<u8> = Vec::with_capacity(secret_len);
+    while secret.len() < secret_len {
+        let padding = block_size - 1 - secret.len() % block_size;
+        let block =
+            secret.len() / block_size * block_size..(secret.len() / block_size + 1) * block_size;
+        let target = &targets[padding][block.clone()];
+
+        let mut input = vec![b'A'; padding];
+        input.extend_from_slice(&secret);
+        input.push(0);
+        let last = input.len() - 1;
+        let byte = (0..=u8::MAX)
+            .find(|&b| {
+                input[last] = b;
+                ask(&input).get(block.clone()) == Some(target)
+            })
+            .ok_or_else(|| {
+                invalid(format!(
+                    "No byte gives the same block at byte {} of the secret, so the oracle is not deterministic",
+                    secret.len()
+                ))
+            })?;
+        secret.push(byte);
+
+        if secret.len().is_multiple_of(block_size) || secret.len() == secret_len {
+            trace.push(format!(
+                "Block {}: {:?}",
+                block.start / block_size + 1,
+                String::from_utf8_lossy(&secret[block.start..])
+            ));
+        }
+    }
+    trace.push(format!(
+        "Recovered {} bytes with {} queries",
+        secret.len(),
+        queries.get()
+    ));
+
+    Ok(EcbAttack {
+        block_size,
+        secret,
+        queries: queries.get(),
+        trace,
+    })
+}
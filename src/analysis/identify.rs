@@ -0,0 +1,301 @@
+use crate::{
+    analysis::{
+        scoring::{ChiSquaredScorer, NGramScorer, Scorer},
+        statistics::{byte_counts, entropy, index_of_coincidence, periodic_index_of_coincidence},
+    },
+    traits::{CryptographicIter, InspectableState},
+};
+
+// the index of coincidence of English letters, and of uniformly random ones
+const ENGLISH_IC: f64 = 0.066;
+const RANDOM_IC: f64 = 0.0385;
+
+// the longest period tried, and the fewest letters every column has to have for it
+const MAX_PERIOD: usize = 20;
+const MIN_COLUMN: usize = 5;
+
+/// A guess at the family of cypher a text was encrypted with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hypothesis {
+    /// English that was not encrypted at all
+    Plaintext,
+    /// The letters of English in another order
+    Transposition,
+    /// Every letter always replaced by the same other letter, e.g. Caesar, Atbash or affine
+    Monoalphabetic,
+    /// A Vigenère-like cypher with a repeating key of `period` letters
+    Periodic {
+        period: usize,
+    },
+    /// A polyalphabetic cypher whose key does not repeat, e.g. autokey or running key
+    Aperiodic,
+    /// Pairs of letters encrypted together, e.g. Playfair
+    Digraphic,
+    /// Only a handful of letters, e.g. ADFGX or ADFGVX
+    Fractionated,
+    /// Coordinates in a Polybius square, digits 1 to 5 only
+    Polybius,
+    /// Polybius coordinates added to a key, as in the Nihilist cypher
+    Nihilist,
+    /// Mostly bytes that are not letters, e.g. XOR, a stream cypher or a block cypher
+    Binary,
+}
+
+impl std::fmt::Display for Hypothesis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Hypothesis::Plaintext => write!(f, "plaintext"),
+            Hypothesis::Transposition => write!(f, "transposition"),
+            Hypothesis::Monoalphabetic => write!(f, "monoalphabetic"),
+            Hypothesis::Periodic { period } => write!(f, "Vigenère period {}", period),
+            Hypothesis::Aperiodic => write!(f, "autokey or running key"),
+            Hypothesis::Digraphic => write!(f, "digraphic"),
+            Hypothesis::Fractionated => write!(f, "ADFGX or ADFGVX"),
+            Hypothesis::Polybius => write!(f, "Polybius square"),
+            Hypothesis::Nihilist => write!(f, "Nihilist"),
+            Hypothesis::Binary => write!(f, "XOR, stream or block cypher"),
+        }
+    }
+}
+
+/// The statistics of a ciphertext, and the hypotheses they support, from 0 to 1
+#[derive(Clone, Debug, PartialEq)]
+pub struct Identification {
+    // whitespace is left out of all the statistics
+    symbols: usize,
+    letters: usize,
+    digits: usize,
+    alphabet_size: usize,
+    entropy: f64,
+    index_of_coincidence: f64,
+    // by period, from 1
+    periodic_index_of_coincidence: Vec<f64>,
+    // the chi-squared statistic against English, per letter
+    chi_squared: f64,
+    // pairs of letters, counted from the start, that are the same letter twice
+    doubled_pairs: usize,
+    // best first
+    hypotheses: Vec<(Hypothesis, f64)>,
+}
+
+impl Identification {
+    /// The number of symbols other than whitespace
+    #[inline]
+    pub fn symbols(&self) -> usize {
+        self.symbols
+    }
+
+    #[inline]
+    pub fn letters(&self) -> usize {
+        self.letters
+    }
+
+    /// The number of distinct symbols, ignoring the case of letters
+    #[inline]
+    pub fn alphabet_size(&self) -> usize {
+        self.alphabet_size
+    }
+
+    /// In bits per symbol
+    #[inline]
+    pub fn entropy(&self) -> f64 {
+        self.entropy
+    }
+
+    /// Of the letters
+    #[inline]
+    pub fn index_of_coincidence(&self) -> f64 {
+        self.index_of_coincidence
+    }
+
+    /// Of the letters, for every period from 1 on
+    #[inline]
+    pub fn periodic_index_of_coincidence(&self) -> &[f64] {
+        &self.periodic_index_of_coincidence
+    }
+
+    #[inline]
+    pub fn even_length(&self) -> bool {
+        self.letters.is_multiple_of(2)
+    }
+
+    #[inline]
+    pub fn doubled_pairs(&self) -> usize {
+        self.doubled_pairs
+    }
+
+    /// Every hypothesis that has some support, best first
+    #[inline]
+    pub fn hypotheses(&self) -> &[(Hypothesis, f64)] {
+        &self.hypotheses
+    }
+
+    #[inline]
+    pub fn best(&self) -> Option<Hypothesis> {
+        self.hypotheses.first().map(|(h, _)| *h)
+    }
+}
+
+impl InspectableState for Identification {
+    fn inspect_state(&self) -> String {
+        let periods: Vec<String> = self
+            .periodic_index_of_coincidence
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, ic)| format!("{}: {:.4}", i + 1, ic))
+            .collect();
+        let mut lines = vec![
+            format!(
+                "{} symbols, {} letters, {} digits, {} distinct symbols",
+                self.symbols, self.letters, self.digits, self.alphabet_size
+            ),
+            format!("Entropy {:.2} bits per symbol", self.entropy),
+            format!(
+                "Index of coincidence {:.4}, English {:.4}, random {:.4}",
+                self.index_of_coincidence, ENGLISH_IC, RANDOM_IC
+            ),
+            format!("Periodic index of coincidence {}", periods.join(", ")),
+            format!(
+                "Chi-squared against English {:.3} per letter",
+                self.chi_squared
+            ),
+            format!(
+                "{} length, {} doubled pairs",
+                if self.even_length() { "Even" } else { "Odd" },
+                self.doubled_pairs
+            ),
+        ];
+        lines.extend(
+            self.hypotheses
+                .iter()
+                .map(|(h, score)| format!("{:.2} {}", score, h)),
+        );
+        lines.join("\n")
+    }
+}
+
+// 1 at `target`, falling to 0 at `spread` away from it
+#[inline]
+fn closeness(value: f64, target: f64, spread: f64) -> f64 {
+    (1.0 - (value - target).abs() / spread).clamp(0.0, 1.0)
+}
+
+/// Works out the statistics of the ciphertext, and ranks the families of cyphers they point to
+pub fn identify<I: CryptographicIter>(ciphertext: I) -> Identification {
+    let symbols: Vec<u8> = ciphertext
+        .filter(|b| !b.is_ascii_whitespace())
+        .map(|b| b.to_ascii_uppercase())
+        .collect();
+    let letters: Vec<u8> = symbols
+        .iter()
+        .copied()
+        .filter(u8::is_ascii_uppercase)
+        .collect();
+    let digits: Vec<u8> = symbols.iter().copied().filter(u8::is_ascii_digit).collect();
+
+    let max_period = (letters.len() / MIN_COLUMN).clamp(1, MAX_PERIOD);
+    let mut identification = Identification {
+        symbols: symbols.len(),
+        letters: letters.len(),
+        digits: digits.len(),
+        alphabet_size: byte_counts(&symbols).iter().filter(|&&c| c > 0).count(),
+        entropy: entropy(&symbols),
+        index_of_coincidence: index_of_coincidence(&letters),
+        periodic_index_of_coincidence: (1..=max_period)
+            .map(|p| periodic_index_of_coincidence(&letters, p))
+            .collect(),
+        chi_squared: -ChiSquaredScorer::english().score_bytes(&letters)
+            / letters.len().max(1) as f64,
+        doubled_pairs: letters.chunks_exact(2).filter(|p| p[0] == p[1]).count(),
+        hypotheses: Vec::new(),
+    };
+    if symbols.is_empty() {
+        return identification;
+    }
+
+    let mut hypotheses = Vec::new();
+    let fraction = |count: usize| count as f64 / symbols.len() as f64;
+
+    hypotheses.push((
+        Hypothesis::Binary,
+        1.0 - fraction(letters.len() + digits.len()),
+    ));
+
+    // numbers
+    let digit_fraction = fraction(digits.len());
+    let coordinates_only = digits.iter().all(|d| (b'1'..=b'5').contains(d));
+    hypotheses.push((
+        Hypothesis::Polybius,
+        digit_fraction * if coordinates_only { 1.0 } else { 0.1 },
+    ));
+    hypotheses.push((
+        Hypothesis::Nihilist,
+        digit_fraction * if coordinates_only { 0.2 } else { 0.9 },
+    ));
+
+    // letters
+    let letter_fraction = fraction(letters.len());
+    let distinct_letters = byte_counts(&letters).iter().filter(|&&c| c > 0).count();
+    if distinct_letters <= 6 {
+        let labels_only = letters.iter().all(|l| b"ADFGVX".contains(l));
+        hypotheses.push((
+            Hypothesis::Fractionated,
+            letter_fraction * if labels_only { 1.0 } else { 0.6 },
+        ));
+    } else {
+        let ic = identification.index_of_coincidence;
+        let english_like = closeness(ic, ENGLISH_IC, ENGLISH_IC - RANDOM_IC);
+        // English letter frequencies survive a transposition, but not a substitution
+        let frequencies_match = 1.0 / (1.0 + identification.chi_squared);
+        let readable = closeness(
+            NGramScorer::english_quadgrams().score_bytes(&letters),
+            -4.8,
+            1.0,
+        );
+
+        let plaintext = english_like * frequencies_match * readable;
+        hypotheses.push((Hypothesis::Plaintext, letter_fraction * plaintext));
+        hypotheses.push((
+            Hypothesis::Transposition,
+            letter_fraction * english_like * frequencies_match * (1.0 - readable),
+        ));
+        hypotheses.push((
+            Hypothesis::Monoalphabetic,
+            letter_fraction * english_like * (1.0 - frequencies_match),
+        ));
+
+        // NOTE: every multiple of the period looks as good as the period itself, so the shortest close one wins
+        let periodic = &identification.periodic_index_of_coincidence;
+        let best = periodic.iter().skip(1).cloned().fold(0.0, f64::max);
+        let period = (2..=periodic.len()).find(|&p| periodic[p - 1] >= 0.9 * best);
+        let periodic_score = period.map_or(0.0, |p| {
+            closeness(periodic[p - 1], ENGLISH_IC, ENGLISH_IC - RANDOM_IC) * (1.0 - english_like)
+        });
+        if let Some(period) = period {
+            hypotheses.push((
+                Hypothesis::Periodic { period },
+                letter_fraction * periodic_score,
+            ));
+        }
+        hypotheses.push((
+            Hypothesis::Aperiodic,
+            letter_fraction * 0.7 * (1.0 - english_like) * (1.0 - periodic_score),
+        ));
+
+        // Playfair never encrypts a pair of the same letter, and usually has no J
+        if identification.even_length() && identification.doubled_pairs == 0 {
+            let no_j = !letters.contains(&b'J');
+            hypotheses.push((
+                Hypothesis::Digraphic,
+                letter_fraction * (1.0 - english_like) * if no_j { 0.9 } else { 0.8 },
+            ));
+        }
+    }
+
+    hypotheses.retain(|(_, score)| *score > 0.0);
+    // NOTE: the sort is stable, so ties keep the order above
+    hypotheses.sort_by(|a, b| b.1.total_cmp(&a.1));
+    identification.hypotheses = hypotheses;
+    identification
+}
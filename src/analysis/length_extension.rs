@@ -0,0 +1,139 @@
+use std::marker::PhantomData;
+
+use crate::{
+    common::to_hex,
+    hashing::{hmac, md_padding, Hasher, MerkleDamgard},
+    traits::InspectableState,
+};
+
+/// A message with something appended to it, along with a MAC for it that was worked out without the secret
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LengthExtension {
+    secret_len: usize,
+    // the padding the hash added after the secret and the original message
+    glue: Vec<u8>,
+    // the original message, the glue and the extension
+    message: Vec<u8>,
+    mac: Vec<u8>,
+}
+
+impl LengthExtension {
+    /// The length of the secret the forgery was worked out for
+    #[inline]
+    pub fn secret_len(&self) -> usize {
+        self.secret_len
+    }
+
+    #[inline]
+    pub fn glue(&self) -> &[u8] {
+        &self.glue
+    }
+
+    /// The message to send along with the forged MAC
+    #[inline]
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    #[inline]
+    pub fn mac(&self) -> &[u8] {
+        &self.mac
+    }
+}
+
+impl InspectableState for LengthExtension {
+    fn inspect_state(&self) -> String {
+        [
+            format!("Secret of {} bytes", self.secret_len),
+            format!("Glue padding {}", to_hex(&self.glue)),
+            format!(
+                "Forged message {:?}",
+                String::from_utf8_lossy(&self.message)
+            ),
+            format!("Forged MAC {}", to_hex(&self.mac)),
+        ]
+        .join("\n")
+    }
+}
+
+/// Given `mac = H(secret || message)`, works out `H(secret || message || glue || extension)` without the secret,
+/// only its length, by carrying on hashing from where the MAC left off
+pub fn extend_length<H: MerkleDamgard>(
+    mac: &[u8],
+    secret_len: usize,
+    message: &[u8],
+    extension: &[u8],
+) -> Result<LengthExtension, std::io::Error> {
+    let hashed = (secret_len + message.len()) as u64;
+    let glue = md_padding(hashed, H::BIG_ENDIAN);
+
+    let mut hasher = Hasher::<H>::resume(mac, hashed + glue.len() as u64)?;
+    hasher.update(extension);
+
+    let mut forged = message.to_vec();
+    forged.extend_from_slice(&glue);
+    forged.extend_from_slice(extension);
+    Ok(LengthExtension {
+        secret_len,
+        glue,
+        message: forged,
+        mac: hasher.finalise(),
+    })
+}
+
+/// Tries every length of the secret up to `max_secret_len` until `verify` accepts the forged message and MAC
+pub fn extend_length_guessing<H: MerkleDamgard, V: Fn(&[u8], &[u8]) -> bool>(
+    verify: V,
+    mac: &[u8],
+    message: &[u8],
+    extension: &[u8],
+    max_secret_len: usize,
+) -> Option<LengthExtension> {
+    (0..=max_secret_len)
+        .filter_map(|len| extend_length::<H>(mac, len, message, extension).ok())
+        .find(|forgery| verify(&forgery.message, &forgery.mac))
+}
+
+/// How a server authenticates its messages with a secret key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MacScheme {
+    /// `H(key || message)`, which can be extended
+    SecretPrefix,
+    /// `HMAC(key, message)`, which hashes the inner digest again under the key, so it can not be
+    Hmac,
+}
+
+/// A server that signs and verifies messages with a key that is kept from the attacker
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MacVerifier<H: MerkleDamgard> {
+    key: Vec<u8>,
+    scheme: MacScheme,
+    hash: PhantomData<H>,
+}
+
+impl<H: MerkleDamgard> MacVerifier<H> {
+    #[inline]
+    pub fn new(key: &[u8], scheme: MacScheme) -> Self {
+        MacVerifier {
+            key: key.to_vec(),
+            scheme,
+            hash: PhantomData,
+        }
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match self.scheme {
+            MacScheme::SecretPrefix => {
+                let mut hasher = Hasher::<H>::new();
+                hasher.update(&self.key);
+                hasher.update(message);
+                hasher.finalise()
+            }
+            MacScheme::Hmac => hmac::<H, _>(&self.key, Vec::from(message).into_iter()),
+        }
+    }
+
+    pub fn verify(&self, message: &[u8], mac: &[u8]) -> bool {
+        self.sign(message) == mac
+    }
+}
@@ -0,0 +1,107 @@
+use std::ops::RangeInclusive;
+
+use crate::random::Mt19937;
+
+// the number of words in the state of MT19937, and so the number of outputs that give it away
+const STATE_LEN: usize = 624;
+
+// undoes `y ^= y >> shift`: every step recovers `shift` more of the bits, from the top
+#[inline]
+fn undo_right_shift(y: u32, shift: u32) -> u32 {
+    let mut x = y;
+    for _ in 0..32 / shift {
+        x = y ^ (x >> shift);
+    }
+    x
+}
+
+// undoes `y ^= (y << shift) & mask`: every step recovers `shift` more of the bits, from the bottom
+#[inline]
+fn undo_left_shift(y: u32, shift: u32, mask: u32) -> u32 {
+    let mut x = y;
+    for _ in 0..32 / shift {
+        x = y ^ ((x << shift) & mask);
+    }
+    x
+}
+
+/// The word of the state that `Mt19937::temper` turned into the output
+pub fn untemper(y: u32) -> u32 {
+    let y = undo_right_shift(y, 18);
+    let y = undo_left_shift(y, 15, 0xEFC6_0000);
+    let y = undo_left_shift(y, 7, 0x9D2C_5680);
+    undo_right_shift(y, 11)
+}
+
+/// A copy of a generator from any 624 consecutive outputs of it. The copy carries on with the outputs that come
+/// after the ones given.
+///
+/// NOTE: every word of the state only depends on the ones 1, 397 and 624 before it in the sequence, so any window of
+/// 624 untempered outputs works as a state, whether or not it lines up with a twist.
+///
+/// Any outputs after the first 624 are checked against the copy, and an error is returned if they do not match.
+pub fn clone_mt19937(outputs: &[u32]) -> Result<Mt19937, std::io::Error> {
+    if outputs.len() < STATE_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "{} outputs are needed to recover the state, but only {} were given",
+                STATE_LEN,
+                outputs.len()
+            ),
+        ));
+    }
+
+    // SAFETY: there are at least as many outputs as words of the state
+    let state: [u32; STATE_LEN] = std::array::from_fn(|i| untemper(outputs[i]));
+    let mut clone = Mt19937::from_state(state);
+    // NOTE: the copy starts from the state the outputs came from, so it gives all of them again first
+    for (i, &output) in outputs.iter().enumerate() {
+        if clone.next_u32() != output {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "Output {} does not match the recovered state, so the outputs are not consecutive outputs of an MT19937 generator",
+                    i
+                ),
+            ));
+        }
+    }
+    Ok(clone)
+}
+
+/// A copy of the generator behind at least 2496 bytes of a `Mt19937::keystream`, e.g. recovered from a known
+/// plaintext
+pub fn clone_mt19937_keystream(keystream: &[u8]) -> Result<Mt19937, std::io::Error> {
+    let outputs: Vec<u32> = keystream
+        .chunks_exact(4)
+        // SAFETY: every chunk is 4 bytes long
+        .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    clone_mt19937(&outputs)
+}
+
+/// Searches `seeds` for the one whose generator starts with `outputs`, e.g. every second of the last day for a
+/// generator seeded with the time
+pub fn recover_seed(outputs: &[u32], seeds: RangeInclusive<u32>) -> Option<u32> {
+    if outputs.is_empty() {
+        return None;
+    }
+    seeds.into_iter().find(|&seed| {
+        let mut rng = Mt19937::new(seed);
+        outputs.iter().all(|&o| rng.next_u32() == o)
+    })
+}
+
+/// Searches `seeds` for the one whose keystream starts with `keystream`
+pub fn recover_keystream_seed(keystream: &[u8], seeds: RangeInclusive<u32>) -> Option<u32> {
+    if keystream.is_empty() {
+        return None;
+    }
+    seeds.into_iter().find(|&seed| {
+        Mt19937::new(seed)
+            .keystream()
+            .zip(keystream)
+            .all(|(a, &b)| a == b)
+    })
+}
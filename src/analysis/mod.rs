@@ -0,0 +1,11 @@
+pub mod brute_force;
+pub mod crib;
+pub mod ecb;
+pub mod identify;
+pub mod length_extension;
+pub mod mersenne;
+pub mod padding_oracle;
+pub mod periodic;
+pub mod scoring;
+pub mod search;
+pub mod statistics;
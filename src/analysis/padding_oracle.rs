@@ -0,0 +1,310 @@
+use std::cell::Cell;
+
+use crate::{
+    common::to_hex,
+    cyphers::block::{pkcs7_pad, pkcs7_unpad, BlockCypher, BlockMode},
+    traits::{CryptographicIter, InspectableState},
+};
+
+/// A server that decrypts `iv || ciphertext` in the CBC mode, and gives away whether the padding was valid, as
+/// an error message or the time it takes to answer would
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaddingOracle<C: BlockCypher> {
+    cypher: C,
+}
+
+impl<C: BlockCypher> PaddingOracle<C> {
+    #[inline]
+    pub fn new(cypher: C) -> Self {
+        PaddingOracle { cypher }
+    }
+
+    /// `iv || ciphertext`, as the server would send it
+    pub fn encrypt<I: CryptographicIter>(
+        &self,
+        message: I,
+        iv: Vec<u8>,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        let mut transform = message.encrypt_blocks(&self.cypher, BlockMode::Cbc { iv: iv.clone() });
+        let ciphertext: Vec<u8> = transform.by_ref().collect();
+        if let Some(e) = transform.error() {
+            return Err(e);
+        }
+
+        let mut ret = iv;
+        ret.extend(ciphertext);
+        Ok(ret)
+    }
+
+    /// Decrypts `iv || ciphertext`
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        let size = self.cypher.block_size();
+        if ciphertext.len() < size {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The ciphertext is shorter than the IV",
+            ));
+        }
+
+        let (iv, ciphertext) = ciphertext.split_at(size);
+        let mut transform = Vec::from(ciphertext)
+            .into_iter()
+            .decrypt_blocks(&self.cypher, BlockMode::Cbc { iv: iv.to_vec() });
+        let plaintext: Vec<u8> = transform.by_ref().collect();
+        match transform.error() {
+            Some(e) => Err(e),
+            None => Ok(plaintext),
+        }
+    }
+
+    /// Whether `iv || ciphertext` decrypts to a validly padded message, which is all that the attack needs
+    #[inline]
+    pub fn check(&self, ciphertext: &[u8]) -> bool {
+        self.decrypt(ciphertext).is_ok()
+    }
+}
+
+/// The outcome of a padding oracle attack, along with the intermediate state of every block it worked out
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaddingOracleAttack {
+    block_size: usize,
+    output: Vec<u8>,
+    // the blocks of ciphertext decrypted by the block cypher, before they are XORed with the previous block
+    intermediates: Vec<Vec<u8>>,
+    queries: usize,
+    trace: Vec<String>,
+}
+
+impl PaddingOracleAttack {
+    #[inline]
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// The plaintext when decrypting, and `iv || ciphertext` when forging
+    #[inline]
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// The decryption of every block of ciphertext by the block cypher alone, in the order of the blocks
+    #[inline]
+    pub fn intermediates(&self) -> &[Vec<u8>] {
+        &self.intermediates
+    }
+
+    /// The number of times the oracle was asked
+    #[inline]
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    #[inline]
+    pub fn trace(&self) -> &[String] {
+        &self.trace
+    }
+}
+
+impl InspectableState for PaddingOracleAttack {
+    fn inspect_state(&self) -> String {
+        self.trace.join("\n")
+    }
+}
+
+// counts the queries, and works out the intermediate state of single blocks
+struct Attacker<O: Fn(&[u8]) -> bool> {
+    oracle: O,
+    block_size: usize,
+    queries: Cell<usize>,
+}
+
+impl<O: Fn(&[u8]) -> bool> Attacker<O> {
+    #[inline]
+    fn ask(&self, prefix: &[u8], block: &[u8]) -> bool {
+        self.queries.set(self.queries.get() + 1);
+        let mut ciphertext = prefix.to_vec();
+        ciphertext.extend_from_slice(block);
+        (self.oracle)(&ciphertext)
+    }
+
+    // the decryption of `block` by the block cypher, one byte at a time from the end: a prefix is chosen so that the
+    // known bytes decrypt to the padding, and every value of the next byte is tried until the padding is valid
+    fn intermediate(&self, block: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        let size = self.block_size;
+        let mut intermediate = vec![0; size];
+        for padding in 1..=size {
+            let position = size - padding;
+            let mut prefix = vec![0; size];
+            (position + 1..size).for_each(|i| prefix[i] = intermediate[i] ^ padding as u8);
+
+            let guess = (0..=u8::MAX).find(|&guess| {
+                prefix[position] = guess;
+                if !self.ask(&prefix, block) {
+                    return false;
+                }
+                // NOTE: the last byte may also give a longer valid padding by chance, e.g. `02 02`, which changing
+                // the byte before it breaks
+                if padding == 1 && position > 0 {
+                    let mut changed = prefix.clone();
+                    changed[position - 1] ^= 1;
+                    return self.ask(&changed, block);
+                }
+                true
+            });
+
+            intermediate[position] = guess.ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "No byte gives a valid padding at byte {} of the block, so the oracle does not check CBC padding",
+                        position
+                    ),
+                )
+            })? ^ padding as u8;
+        }
+        Ok(intermediate)
+    }
+}
+
+#[inline]
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(x, y)| x ^ y).collect()
+}
+
+/// Decrypts `iv || ciphertext` with nothing but an oracle that tells whether a ciphertext decrypts to a validly
+/// padded message in the CBC mode, as shown by Vaudenay
+pub fn padding_oracle_decrypt<O: Fn(&[u8]) -> bool>(
+    oracle: O,
+    ciphertext: &[u8],
+    block_size: usize,
+) -> Result<PaddingOracleAttack, std::io::Error> {
+    if !(1..=255).contains(&block_size) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The block size has to be between 1 and 255 bytes",
+        ));
+    }
+    if !ciphertext.len().is_multiple_of(block_size) || ciphertext.len() < 2 * block_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "The ciphertext has to be an IV followed by at least one block of {} bytes, but it is {} bytes long",
+                block_size,
+                ciphertext.len()
+            ),
+        ));
+    }
+
+    let attacker = Attacker {
+        oracle,
+        block_size,
+        queries: Cell::new(0),
+    };
+    let mut trace = vec![format!(
+        "Decrypting {} blocks of {} bytes",
+        ciphertext.len() / block_size - 1,
+        block_size
+    )];
+
+    let mut intermediates = Vec::new();
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    for (i, pair) in ciphertext
+        .chunks_exact(block_size)
+        .collect::<Vec<_>>()
+        .windows(2)
+        .enumerate()
+    {
+        let before = attacker.queries.get();
+        let intermediate = attacker.intermediate(pair[1])?;
+        let block = xor(&intermediate, pair[0]);
+        trace.push(format!(
+            "Block {}: intermediate {}, plaintext {:?}, {} queries",
+            i + 1,
+            to_hex(&intermediate),
+            String::from_utf8_lossy(&block),
+            attacker.queries.get() - before
+        ));
+        plaintext.extend(block);
+        intermediates.push(intermediate);
+    }
+
+    let plaintext = pkcs7_unpad(&plaintext, block_size)?;
+    trace.push(format!(
+        "Decrypted {} bytes with {} queries",
+        plaintext.len(),
+        attacker.queries.get()
+    ));
+
+    Ok(PaddingOracleAttack {
+        block_size,
+        output: plaintext,
+        intermediates,
+        queries: attacker.queries.get(),
+        trace,
+    })
+}
+
+/// Forges `iv || ciphertext` that decrypts to `plaintext`, without the key, with the same kind of oracle as
+/// `padding_oracle_decrypt`.
+///
+/// The blocks are worked out from the last one, which is all zeroes, back to the IV: every block is chosen so that
+/// the intermediate state of the block after it decrypts to the plaintext.
+pub fn padding_oracle_forge<O: Fn(&[u8]) -> bool>(
+    oracle: O,
+    plaintext: &[u8],
+    block_size: usize,
+) -> Result<PaddingOracleAttack, std::io::Error> {
+    if !(1..=255).contains(&block_size) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The block size has to be between 1 and 255 bytes",
+        ));
+    }
+
+    let attacker = Attacker {
+        oracle,
+        block_size,
+        queries: Cell::new(0),
+    };
+    let padded = pkcs7_pad(plaintext, block_size);
+    let blocks: Vec<&[u8]> = padded.chunks_exact(block_size).collect();
+    let mut trace = vec![format!(
+        "Forging {} blocks of {} bytes",
+        blocks.len(),
+        block_size
+    )];
+
+    let mut ciphertext: Vec<Vec<u8>> = vec![vec![0; block_size]];
+    let mut intermediates = Vec::new();
+    for (i, block) in blocks.iter().enumerate().rev() {
+        let before = attacker.queries.get();
+        // SAFETY: there is always at least the last block
+        let intermediate = attacker.intermediate(ciphertext.last().unwrap())?;
+        let previous = xor(&intermediate, block);
+        trace.push(format!(
+            "Block {}: intermediate {}, so the block before it is {}, {} queries",
+            i + 1,
+            to_hex(&intermediate),
+            to_hex(&previous),
+            attacker.queries.get() - before
+        ));
+        ciphertext.push(previous);
+        intermediates.push(intermediate);
+    }
+    ciphertext.reverse();
+    intermediates.reverse();
+
+    trace.push(format!(
+        "Forged {} bytes with {} queries",
+        plaintext.len(),
+        attacker.queries.get()
+    ));
+
+    Ok(PaddingOracleAttack {
+        block_size,
+        output: ciphertext.concat(),
+        intermediates,
+        queries: attacker.queries.get(),
+        trace,
+    })
+}
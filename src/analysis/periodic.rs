@@ -0,0 +1,390 @@
+use crate::{
+    analysis::{
+        brute_force::{rank, Candidate},
+        scoring::Scorer,
+        statistics::{columns, hamming_distance, periodic_index_of_coincidence, repeat_distances},
+    },
+    traits::{CryptographicIter, InspectableState},
+};
+
+// the length of the repeated runs looked for by the Kasiski examination
+const KASISKI_RUN: usize = 3;
+
+/// The evidence for one key length. Every statistic is also given as a part of the confidence, from 0 to 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyLength {
+    length: usize,
+    index_of_coincidence: f64,
+    // the fraction of the Kasiski distances that the length divides
+    kasiski: Option<f64>,
+    // the average number of differing bits per byte between consecutive blocks
+    hamming: Option<f64>,
+    confidence: f64,
+}
+
+impl KeyLength {
+    #[inline]
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// The average index of coincidence of the columns
+    #[inline]
+    pub fn index_of_coincidence(&self) -> f64 {
+        self.index_of_coincidence
+    }
+
+    /// The fraction of the distances between repeated runs that are a multiple of the length, if there are any
+    #[inline]
+    pub fn kasiski(&self) -> Option<f64> {
+        self.kasiski
+    }
+
+    /// The normalised Hamming distance between consecutive blocks, only used against XOR
+    #[inline]
+    pub fn hamming(&self) -> Option<f64> {
+        self.hamming
+    }
+
+    #[inline]
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+}
+
+/// A whole key, along with the confidence in its length
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyCandidate {
+    candidate: Candidate<Vec<u8>>,
+    confidence: f64,
+}
+
+impl KeyCandidate {
+    #[inline]
+    pub fn key(&self) -> &[u8] {
+        self.candidate.key()
+    }
+
+    #[inline]
+    pub fn score(&self) -> f64 {
+        self.candidate.score()
+    }
+
+    #[inline]
+    pub fn plaintext(&self) -> &[u8] {
+        self.candidate.plaintext()
+    }
+
+    #[inline]
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+}
+
+/// The outcome of breaking a cypher with a repeated key, along with every step that led to it
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeriodicBreak {
+    // best first
+    key_lengths: Vec<KeyLength>,
+    // best scoring first
+    candidates: Vec<KeyCandidate>,
+    trace: Vec<String>,
+}
+
+impl PeriodicBreak {
+    #[inline]
+    pub fn key_lengths(&self) -> &[KeyLength] {
+        &self.key_lengths
+    }
+
+    #[inline]
+    pub fn candidates(&self) -> &[KeyCandidate] {
+        &self.candidates
+    }
+
+    #[inline]
+    pub fn best(&self) -> Option<&KeyCandidate> {
+        self.candidates.first()
+    }
+
+    #[inline]
+    pub fn trace(&self) -> &[String] {
+        &self.trace
+    }
+}
+
+impl InspectableState for PeriodicBreak {
+    fn inspect_state(&self) -> String {
+        self.trace.join("\n")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Periodic {
+    Xor,
+    Vigenere,
+}
+
+impl Periodic {
+    #[inline]
+    fn key_count(self) -> usize {
+        match self {
+            Periodic::Xor => 256,
+            Periodic::Vigenere => 26,
+        }
+    }
+
+    // decrypts one symbol, both as they appear in `symbols`
+    #[inline]
+    fn decrypt(self, symbol: u8, key: u8) -> u8 {
+        match self {
+            Periodic::Xor => symbol ^ key,
+            Periodic::Vigenere => (symbol - b'A' + 26 - key) % 26 + b'A',
+        }
+    }
+
+    // the key as it is passed to the cypher
+    #[inline]
+    fn key_symbol(self, key: u8) -> u8 {
+        match self {
+            Periodic::Xor => key,
+            Periodic::Vigenere => key + b'A',
+        }
+    }
+}
+
+/// Breaks `xor_repeating` with an unknown key of up to `max_key_length` bytes.
+///
+/// The `top` most likely key lengths are solved, every column as a single-byte XOR, so `scorer` should work on
+/// single letters, e.g. `NGramScorer::english_unigrams().strict()`.
+pub fn break_repeating_xor<I: CryptographicIter, S: Scorer + ?Sized>(
+    ciphertext: I,
+    scorer: &S,
+    max_key_length: usize,
+    top: usize,
+) -> PeriodicBreak {
+    let ciphertext: Vec<u8> = ciphertext.collect();
+    break_periodic(
+        Periodic::Xor,
+        &ciphertext,
+        |key| {
+            ciphertext
+                .clone()
+                .into_iter()
+                .xor_repeating(key.into_iter())
+                .collect()
+        },
+        scorer,
+        max_key_length,
+        top,
+    )
+}
+
+/// Breaks `vigenere` over the letters, with an unknown key of up to `max_key_length` letters, keeping the case and
+/// everything else of the message as it is.
+///
+/// The `top` most likely key lengths are solved, every column as a Caesar shift, so `scorer` should work on single
+/// letters, e.g. `NGramScorer::english_unigrams()`.
+pub fn break_vigenere<I: CryptographicIter, S: Scorer + ?Sized>(
+    ciphertext: I,
+    scorer: &S,
+    max_key_length: usize,
+    top: usize,
+) -> PeriodicBreak {
+    let ciphertext: Vec<u8> = ciphertext.collect();
+    let letters: Vec<u8> = ciphertext
+        .iter()
+        .filter(|b| b.is_ascii_alphabetic())
+        .map(u8::to_ascii_uppercase)
+        .collect();
+    break_periodic(
+        Periodic::Vigenere,
+        &letters,
+        |key| {
+            ciphertext
+                .clone()
+                .into_iter()
+                .vigenere_decrypt(key.into_iter())
+                .letters_only()
+                .collect()
+        },
+        scorer,
+        max_key_length,
+        top,
+    )
+}
+
+fn break_periodic<S: Scorer + ?Sized, D: Fn(Vec<u8>) -> Vec<u8>>(
+    cypher: Periodic,
+    symbols: &[u8],
+    decrypt: D,
+    scorer: &S,
+    max_key_length: usize,
+    top: usize,
+) -> PeriodicBreak {
+    let mut trace = vec![format!(
+        "{} symbols of ciphertext, trying key lengths up to {}",
+        symbols.len(),
+        max_key_length
+    )];
+
+    let key_lengths = key_lengths(cypher, symbols, max_key_length, &mut trace);
+
+    let mut candidates: Vec<KeyCandidate> = Vec::new();
+    for length in key_lengths.iter().take(top) {
+        trace.push(format!("Solving key length {}", length.length));
+
+        let key: Vec<u8> = columns(symbols, length.length)
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let best = &rank(
+                    (0..cypher.key_count()).map(|k| k as u8),
+                    |&k| column.iter().map(|&c| cypher.decrypt(c, k)).collect(),
+                    scorer,
+                    1,
+                )[0];
+                let key = cypher.key_symbol(*best.key());
+                trace.push(format!(
+                    "  Column {}: key {:?} scores {:.3}",
+                    i + 1,
+                    key as char,
+                    best.score()
+                ));
+                key
+            })
+            .collect();
+
+        let key = shortest_period(&key).to_vec();
+        let plaintext = decrypt(key.clone());
+        let candidate = Candidate::new(key, scorer.score_bytes(&plaintext), plaintext);
+        trace.push(format!(
+            "  Key {:?} scores {:.3}",
+            String::from_utf8_lossy(candidate.key()),
+            candidate.score()
+        ));
+
+        // a key repeated twice gives the same plaintext as the key itself
+        if !candidates.iter().any(|c| c.key() == candidate.key()) {
+            candidates.push(KeyCandidate {
+                candidate,
+                confidence: length.confidence,
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| {
+        b.score()
+            .total_cmp(&a.score())
+            .then(b.confidence.total_cmp(&a.confidence))
+    });
+    if let Some(best) = candidates.first() {
+        trace.push(format!(
+            "Best key {:?}, with a confidence of {:.2} in its length",
+            String::from_utf8_lossy(best.key()),
+            best.confidence
+        ));
+    }
+
+    PeriodicBreak {
+        key_lengths,
+        candidates,
+        trace,
+    }
+}
+
+// every key length, the most likely first
+fn key_lengths(
+    cypher: Periodic,
+    symbols: &[u8],
+    max_key_length: usize,
+    trace: &mut Vec<String>,
+) -> Vec<KeyLength> {
+    let max_key_length = max_key_length.min(symbols.len() / 2).max(1);
+
+    let distances = repeat_distances(symbols, KASISKI_RUN);
+    let shown: Vec<String> = distances.iter().take(10).map(usize::to_string).collect();
+    trace.push(format!(
+        "Kasiski: {} repeated runs of {} symbols, at distances {}{}",
+        distances.len(),
+        KASISKI_RUN,
+        shown.join(", "),
+        if distances.len() > shown.len() {
+            ", ..."
+        } else {
+            ""
+        }
+    ));
+
+    let mut lengths: Vec<KeyLength> = (1..=max_key_length)
+        .map(|length| {
+            let kasiski = (!distances.is_empty()).then(|| {
+                distances.iter().filter(|&&d| d % length == 0).count() as f64
+                    / distances.len() as f64
+            });
+
+            // NOTE: Hamming distances only reveal a key that was XORed in
+            let blocks: Vec<&[u8]> = symbols.chunks_exact(length).collect();
+            let hamming = (cypher == Periodic::Xor && blocks.len() >= 2).then(|| {
+                let total: u32 = blocks
+                    .windows(2)
+                    .map(|w| hamming_distance(w[0], w[1]))
+                    .sum();
+                total as f64 / ((blocks.len() - 1) * length) as f64
+            });
+
+            KeyLength {
+                length,
+                index_of_coincidence: periodic_index_of_coincidence(symbols, length),
+                kasiski,
+                hamming,
+                confidence: 0.0,
+            }
+        })
+        .collect();
+
+    // every statistic is scaled against the best length for it, and the confidence is their average
+    let max_ic = lengths
+        .iter()
+        .map(|l| l.index_of_coincidence)
+        .fold(0.0, f64::max);
+    let min_hamming = lengths
+        .iter()
+        .filter_map(|l| l.hamming)
+        .fold(f64::INFINITY, f64::min);
+    for l in lengths.iter_mut() {
+        let mut parts = vec![if max_ic > 0.0 {
+            l.index_of_coincidence / max_ic
+        } else {
+            0.0
+        }];
+        parts.extend(l.kasiski);
+        parts.extend(
+            l.hamming
+                .map(|h| if h > 0.0 { min_hamming / h } else { 1.0 }),
+        );
+        l.confidence = parts.iter().sum::<f64>() / parts.len() as f64;
+    }
+
+    // NOTE: the sort is stable, so the shorter of two equally likely lengths comes first
+    lengths.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    for l in lengths.iter().take(5) {
+        trace.push(format!(
+            "Key length {}: index of coincidence {:.4}, Kasiski {}, Hamming {}, confidence {:.2}",
+            l.length,
+            l.index_of_coincidence,
+            l.kasiski.map_or(String::from("-"), |k| format!("{:.2}", k)),
+            l.hamming.map_or(String::from("-"), |h| format!("{:.3}", h)),
+            l.confidence
+        ));
+    }
+    lengths
+}
+
+// the key without its repetitions, e.g. `ABAB` is `AB`
+fn shortest_period(key: &[u8]) -> &[u8] {
+    let period = (1..=key.len())
+        .find(|&p| key.len().is_multiple_of(p) && key.chunks(p).all(|c| c == &key[..p]))
+        .unwrap_or(key.len());
+    &key[..period]
+}
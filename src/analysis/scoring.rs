@@ -0,0 +1,361 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::traits::CryptographicIter;
+
+/// The relative frequencies of the letters `A` to `Z` in English text
+pub const ENGLISH_LETTER_FREQUENCIES: [f64; 26] = [
+    0.08167, 0.01492, 0.02782, 0.04253, 0.12702, 0.02228, 0.02015, 0.06094, 0.06966, 0.00153,
+    0.00772, 0.04025, 0.02406, 0.06749, 0.07507, 0.01929, 0.00095, 0.05987, 0.06327, 0.09056,
+    0.02758, 0.00978, 0.02360, 0.00150, 0.01974, 0.00074,
+];
+
+// a sample of public domain English text, to build the default n-gram tables from. The passages are taken, in order,
+// from:
+// - Charles Dickens, A Tale of Two Cities (1859)
+// - Jane Austen, Pride and Prejudice (1813)
+// - Herman Melville, Moby-Dick (1851)
+// - Abraham Lincoln, the Gettysburg Address (1863)
+// - the Declaration of Independence (1776)
+// - Lewis Carroll, Alice's Adventures in Wonderland (1865)
+// - Arthur Conan Doyle, A Scandal in Bohemia (1891)
+// - Leo Tolstoy, Anna Karenina, translated by Constance Garnett (1901)
+// - the King James Bible, Genesis (1611)
+// - Robert Louis Stevenson, Treasure Island (1883)
+// - Sun Tzu, The Art of War, translated by Lionel Giles (1910)
+// - Mary Shelley, Frankenstein (1818)
+const ENGLISH_CORPUS: &str = include_str!("english.txt");
+
+/// Rates how much a text looks like natural language. Higher scores are better for every scorer.
+pub trait Scorer {
+    fn score_bytes(&self, text: &[u8]) -> f64;
+
+    #[inline]
+    fn score<I: CryptographicIter>(&self, text: I) -> f64
+    where
+        Self: Sized,
+    {
+        self.score_bytes(&text.collect::<Vec<u8>>())
+    }
+}
+
+/// The log10 probabilities of the n-grams of a language, over the letters `A` to `Z`
+#[derive(Clone, Debug, PartialEq)]
+pub struct NGramTable {
+    n: usize,
+    log_probabilities: HashMap<Vec<u8>, f64>,
+    // the same probabilities indexed by the n-gram read as a number in base 26, for the n-grams short enough
+    dense: Option<Vec<f64>>,
+    // the log10 probability of an n-gram that was never seen
+    floor: f64,
+}
+
+impl NGramTable {
+    /// The n-grams are case-insensitive, and have to be made of `n` letters
+    pub fn from_counts<S: AsRef<str>, C: IntoIterator<Item = (S, u64)>>(
+        counts: C,
+    ) -> Result<Self, std::io::Error> {
+        let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+
+        let mut n = 0;
+        let mut merged: HashMap<Vec<u8>, u64> = HashMap::new();
+        for (ngram, count) in counts {
+            let ngram = ngram.as_ref().to_ascii_uppercase().into_bytes();
+            if ngram.is_empty() || !ngram.iter().all(u8::is_ascii_uppercase) {
+                return Err(invalid(format!(
+                    "{:?} is not made of letters",
+                    String::from_utf8_lossy(&ngram)
+                )));
+            }
+            if n != 0 && ngram.len() != n {
+                return Err(invalid(String::from(
+                    "All the n-grams have to be of the same length",
+                )));
+            }
+            n = ngram.len();
+            *merged.entry(ngram).or_default() += count;
+        }
+
+        let total: u64 = merged.values().sum();
+        if total == 0 {
+            return Err(invalid(String::from("The table has to count some n-grams")));
+        }
+
+        let total = total as f64;
+        let floor = (0.01 / total).log10();
+        let log_probabilities: HashMap<Vec<u8>, f64> = merged
+            .into_iter()
+            .filter(|(_, c)| *c > 0)
+            .map(|(g, c)| (g, (c as f64 / total).log10()))
+            .collect();
+
+        let dense = (n <= DENSE_LEN).then(|| {
+            let mut dense = vec![floor; 26usize.pow(n as u32)];
+            log_probabilities
+                .iter()
+                .for_each(|(g, &p)| dense[dense_index(g)] = p);
+            dense
+        });
+
+        Ok(NGramTable {
+            n,
+            log_probabilities,
+            dense,
+            floor,
+        })
+    }
+
+    /// One `NGRAM COUNT` pair per line, e.g. `TION 13168375`. Empty lines and lines starting with `#` are skipped.
+    pub fn parse(table: &str) -> Result<Self, std::io::Error> {
+        let counts = table
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| {
+                let mut parts = l.split_whitespace();
+                match (
+                    parts.next(),
+                    parts.next().map(str::parse::<u64>),
+                    parts.next(),
+                ) {
+                    (Some(ngram), Some(Ok(count)), None) => Ok((ngram, count)),
+                    _ => Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("{:?} is not an n-gram followed by its count", l),
+                    )),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_counts(counts)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, std::io::Error> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Counts the n-grams of the letters of a text, skipping everything else
+    pub fn from_corpus(corpus: &[u8], n: usize) -> Result<Self, std::io::Error> {
+        if n == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The n-grams have to be at least one letter long",
+            ));
+        }
+
+        let letters = letters(corpus);
+        let mut counts: HashMap<String, u64> = HashMap::new();
+        letters.windows(n).for_each(|w| {
+            *counts
+                .entry(String::from_utf8_lossy(w).into_owned())
+                .or_default() += 1
+        });
+        Self::from_counts(counts)
+    }
+
+    /// Built from a small sample of English text that comes with the crate
+    pub fn english(n: usize) -> Self {
+        // SAFETY: the sample is long enough to have n-grams of any length used in practice
+        Self::from_corpus(ENGLISH_CORPUS.as_bytes(), n).expect("The English sample is too short")
+    }
+
+    #[inline]
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// The floor for n-grams that were never seen, including anything that is not made of uppercase letters
+    #[inline]
+    pub fn log_probability(&self, ngram: &[u8]) -> f64 {
+        match &self.dense {
+            Some(dense) if ngram.len() == self.n && ngram.iter().all(u8::is_ascii_uppercase) => {
+                dense[dense_index(ngram)]
+            }
+            _ => self
+                .log_probabilities
+                .get(ngram)
+                .copied()
+                .unwrap_or(self.floor),
+        }
+    }
+}
+
+// the longest n-grams that are kept in a flat table, of 26^4 entries
+const DENSE_LEN: usize = 4;
+
+#[inline]
+fn dense_index(ngram: &[u8]) -> usize {
+    ngram.iter().fold(0, |i, &b| i * 26 + (b - b'A') as usize)
+}
+
+// the letters of a text in uppercase
+fn letters(text: &[u8]) -> Vec<u8> {
+    text.iter()
+        .filter(|b| b.is_ascii_alphabetic())
+        .map(u8::to_ascii_uppercase)
+        .collect()
+}
+
+#[inline]
+fn is_text(b: u8) -> bool {
+    b.is_ascii_graphic() || b.is_ascii_whitespace()
+}
+
+/// The average log10 probability of the n-grams of the letters of the text.
+///
+/// Whitespace and punctuation are skipped, but every other byte counts as an n-gram that was never seen, so that
+/// binary garbage does not score well by having few letters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NGramScorer {
+    table: NGramTable,
+    // whether punctuation counts as garbage too
+    strict: bool,
+}
+
+impl NGramScorer {
+    #[inline]
+    pub fn new(table: NGramTable) -> Self {
+        NGramScorer {
+            table,
+            strict: false,
+        }
+    }
+
+    /// Also counts punctuation as n-grams that were never seen.
+    ///
+    /// NOTE: a wrong XOR key often turns letters into other letters and spaces into punctuation, which would score
+    /// as well as the right key if punctuation were skipped, so this is the scorer to break XOR with
+    #[inline]
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    pub fn english_unigrams() -> Self {
+        Self::new(NGramTable::english(1))
+    }
+
+    pub fn english_bigrams() -> Self {
+        Self::new(NGramTable::english(2))
+    }
+
+    pub fn english_quadgrams() -> Self {
+        Self::new(NGramTable::english(4))
+    }
+
+    #[inline]
+    pub fn table(&self) -> &NGramTable {
+        &self.table
+    }
+
+    #[inline]
+    fn is_garbage(&self, b: u8) -> bool {
+        if self.strict {
+            !b.is_ascii_alphabetic() && !b.is_ascii_whitespace()
+        } else {
+            !is_text(b)
+        }
+    }
+}
+
+impl Scorer for NGramScorer {
+    fn score_bytes(&self, text: &[u8]) -> f64 {
+        let n = self.table.n();
+        let mut garbage = 0;
+        let (mut sum, mut count) = (0.0, 0);
+
+        match &self.table.dense {
+            // NOTE: the index of the last n letters is rolled along, instead of gathering the letters first
+            Some(dense) => {
+                let (mut index, mut run) = (0, 0);
+                for &b in text {
+                    if b.is_ascii_alphabetic() {
+                        index =
+                            (index * 26 + (b.to_ascii_uppercase() - b'A') as usize) % dense.len();
+                        run += 1;
+                        if run >= n {
+                            sum += dense[index];
+                            count += 1;
+                        }
+                    } else if self.is_garbage(b) {
+                        garbage += 1;
+                    }
+                }
+            }
+            None => {
+                garbage = text.iter().filter(|&&b| self.is_garbage(b)).count();
+                letters(text).windows(n).for_each(|g| {
+                    sum += self.table.log_probability(g);
+                    count += 1;
+                });
+            }
+        }
+
+        if count + garbage == 0 {
+            return self.table.floor;
+        }
+        (sum + garbage as f64 * self.table.floor) / (count + garbage) as f64
+    }
+}
+
+/// The chi-squared statistic of the letter counts against the expected frequencies, negated so that higher is better.
+/// Texts without letters score negative infinity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChiSquaredScorer {
+    expected: [f64; 26],
+}
+
+impl ChiSquaredScorer {
+    /// The frequencies are normalised to add up to 1, and all of them have to be positive
+    pub fn new(expected: [f64; 26]) -> Result<Self, std::io::Error> {
+        let total: f64 = expected.iter().sum();
+        if !expected.iter().all(|&f| f > 0.0 && f.is_finite()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Every letter has to have a positive frequency",
+            ));
+        }
+        Ok(ChiSquaredScorer {
+            expected: expected.map(|f| f / total),
+        })
+    }
+
+    pub fn english() -> Self {
+        // SAFETY: all the frequencies are positive
+        Self::new(ENGLISH_LETTER_FREQUENCIES).unwrap()
+    }
+}
+
+impl Scorer for ChiSquaredScorer {
+    fn score_bytes(&self, text: &[u8]) -> f64 {
+        let mut counts = [0usize; 26];
+        letters(text)
+            .iter()
+            .for_each(|&b| counts[(b - b'A') as usize] += 1);
+
+        let total: usize = counts.iter().sum();
+        if total == 0 {
+            return f64::NEG_INFINITY;
+        }
+
+        -counts
+            .iter()
+            .zip(self.expected)
+            .map(|(&c, f)| {
+                let expected = f * total as f64;
+                (c as f64 - expected).powi(2) / expected
+            })
+            .sum::<f64>()
+    }
+}
+
+/// The fraction of the bytes that are printable ASCII or whitespace
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrintableRatio;
+
+impl Scorer for PrintableRatio {
+    fn score_bytes(&self, text: &[u8]) -> f64 {
+        if text.is_empty() {
+            return 0.0;
+        }
+        text.iter().filter(|&&b| is_text(b)).count() as f64 / text.len() as f64
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{
+    analysis::{brute_force::Candidate, scoring::Scorer},
+    cyphers::{
+        alphabet::{Alphabet, OutOfAlphabet, Token},
+        substitution::SubstitutionKey,
+        transposition::{ColumnarKey, Padding, Transposition},
+    },
+    random::SplitMix64,
+    traits::CryptographicIter,
+};
+
+/// The keys of a cypher for one ciphertext, for a `Search` to walk through
+pub trait KeySpace {
+    type Key: Clone;
+
+    fn random_key(&self, rng: &mut SplitMix64) -> Self::Key;
+
+    /// A key that differs from `key` only a little, e.g. by two swapped symbols
+    fn neighbour(&self, key: &Self::Key, rng: &mut SplitMix64) -> Self::Key;
+
+    fn decrypt(&self, key: &Self::Key) -> Vec<u8>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    /// Only ever moves to a better key
+    HillClimbing,
+    /// See `Strategy::simulated_annealing`
+    SimulatedAnnealing(Annealing),
+}
+
+impl Strategy {
+    /// Also moves to a worse key with the probability `exp(difference / temperature)`, while the temperature cools
+    /// down geometrically from `start` to `end` over the iterations. The temperatures are on the scale of the scores,
+    /// and have to be positive and finite.
+    pub fn simulated_annealing(start: f64, end: f64) -> Result<Self, std::io::Error> {
+        if !(start.is_finite() && start > 0.0 && end.is_finite() && end > 0.0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "The temperatures have to be positive and finite, not {} and {}",
+                    start, end
+                ),
+            ));
+        }
+        Ok(Strategy::SimulatedAnnealing(Annealing { start, end }))
+    }
+}
+
+/// The temperatures of a simulated annealing search, which are always positive and finite
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Annealing {
+    start: f64,
+    end: f64,
+}
+
+impl Annealing {
+    #[inline]
+    pub fn start(&self) -> f64 {
+        self.start
+    }
+
+    #[inline]
+    pub fn end(&self) -> f64 {
+        self.end
+    }
+}
+
+/// A seeded stochastic search for the best scoring key, restarted from several random keys
+#[derive(Clone, Debug, PartialEq)]
+pub struct Search {
+    strategy: Strategy,
+    seed: u64,
+    iterations: usize,
+    restarts: usize,
+}
+
+impl Search {
+    /// 5 restarts of 2000 iterations each, by default
+    #[inline]
+    pub fn new(strategy: Strategy, seed: u64) -> Self {
+        Search {
+            strategy,
+            seed,
+            iterations: 2000,
+            restarts: 5,
+        }
+    }
+
+    /// The number of neighbours tried from every starting key
+    #[inline]
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// The number of random keys to start from. At least one search is always run.
+    #[inline]
+    pub fn with_restarts(mut self, restarts: usize) -> Self {
+        self.restarts = restarts;
+        self
+    }
+
+    /// The best key found by every restart, best first
+    pub fn run<K: KeySpace, S: Scorer + ?Sized>(
+        &self,
+        space: &K,
+        scorer: &S,
+    ) -> Vec<Candidate<K::Key>> {
+        let mut rng = SplitMix64::new(self.seed);
+        let evaluate = |key: K::Key| {
+            let plaintext = space.decrypt(&key);
+            Candidate::new(key, scorer.score_bytes(&plaintext), plaintext)
+        };
+
+        let mut results: Vec<Candidate<K::Key>> = (0..self.restarts.max(1))
+            .map(|_| {
+                let mut current = evaluate(space.random_key(&mut rng));
+                let mut best = current.clone();
+
+                for i in 0..self.iterations {
+                    let next = evaluate(space.neighbour(current.key(), &mut rng));
+                    let difference = next.score() - current.score();
+                    let accept = match self.strategy {
+                        Strategy::HillClimbing => difference > 0.0,
+                        Strategy::SimulatedAnnealing(Annealing { start, end }) => {
+                            let progress = i as f64 / self.iterations as f64;
+                            let temperature = start * (end / start).powf(progress);
+                            difference > 0.0 || rng.next_f64() < (difference / temperature).exp()
+                        }
+                    };
+
+                    if accept {
+                        current = next;
+                        if current.score() > best.score() {
+                            best = current.clone();
+                        }
+                    }
+                }
+                best
+            })
+            .collect();
+
+        results.sort_by(|a, b| b.score().total_cmp(&a.score()));
+        results
+    }
+}
+
+/// The keys of a monoalphabetic substitution. The keys found are the ones the message was encrypted with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubstitutionSpace {
+    // NOTE: the ciphertext is split into symbols once, so that every key only has to map their indices
+    tokens: Vec<Token>,
+    alphabet: Alphabet,
+}
+
+impl SubstitutionSpace {
+    pub fn new<I: CryptographicIter>(ciphertext: I, alphabet: &Alphabet) -> Self {
+        SubstitutionSpace {
+            tokens: alphabet.tokenise(ciphertext).collect(),
+            alphabet: alphabet.clone(),
+        }
+    }
+}
+
+impl KeySpace for SubstitutionSpace {
+    type Key = SubstitutionKey;
+
+    #[inline]
+    fn random_key(&self, rng: &mut SplitMix64) -> Self::Key {
+        SubstitutionKey::random(&self.alphabet, rng.next_u64())
+    }
+
+    fn neighbour(&self, key: &Self::Key, rng: &mut SplitMix64) -> Self::Key {
+        let mut permutation = key.permutation().to_vec();
+        let (a, b) = (rng.below(permutation.len()), rng.below(permutation.len()));
+        permutation.swap(a, b);
+        // SAFETY: swapping two entries of a permutation keeps it a permutation
+        SubstitutionKey::new(&self.alphabet, permutation).unwrap()
+    }
+
+    /// The same as `unsubstitute`
+    fn decrypt(&self, key: &Self::Key) -> Vec<u8> {
+        let mut inverse = vec![0; key.permutation().len()];
+        key.permutation()
+            .iter()
+            .enumerate()
+            .for_each(|(i, &p)| inverse[p] = i);
+
+        let mut plaintext = Vec::with_capacity(self.tokens.len());
+        for token in &self.tokens {
+            match token {
+                Token::Symbol { index, lowercase } => {
+                    self.alphabet
+                        .write_symbol(inverse[*index], *lowercase, &mut plaintext)
+                }
+                Token::Other(bytes) => match self.alphabet.policy() {
+                    OutOfAlphabet::PassThrough => plaintext.extend_from_slice(bytes),
+                    OutOfAlphabet::Drop => {}
+                    OutOfAlphabet::Error => break,
+                },
+            }
+        }
+        plaintext
+    }
+}
+
+/// The keys of a columnar transposition of a given width, without padding
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnarSpace {
+    ciphertext: Vec<u8>,
+    width: usize,
+}
+
+impl ColumnarSpace {
+    pub fn new<I: CryptographicIter>(ciphertext: I, width: usize) -> Result<Self, std::io::Error> {
+        if width == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The key has to have at least one column",
+            ));
+        }
+        Ok(ColumnarSpace {
+            ciphertext: ciphertext.collect(),
+            width,
+        })
+    }
+
+    #[inline]
+    fn key(columns: Vec<usize>) -> ColumnarKey {
+        // SAFETY: the columns are always a permutation
+        ColumnarKey::new(columns, Padding::Irregular).unwrap()
+    }
+}
+
+impl KeySpace for ColumnarSpace {
+    type Key = ColumnarKey;
+
+    fn random_key(&self, rng: &mut SplitMix64) -> Self::Key {
+        let mut columns: Vec<usize> = (0..self.width).collect();
+        rng.shuffle(&mut columns);
+        Self::key(columns)
+    }
+
+    /// Either swaps two columns, or moves one column somewhere else
+    fn neighbour(&self, key: &Self::Key, rng: &mut SplitMix64) -> Self::Key {
+        let mut columns = key.columns().to_vec();
+        let (a, b) = (rng.below(self.width), rng.below(self.width));
+        if rng.below(2) == 0 {
+            columns.swap(a, b);
+        } else {
+            let column = columns.remove(a);
+            columns.insert(b, column);
+        }
+        Self::key(columns)
+    }
+
+    /// The same as `untranspose`
+    fn decrypt(&self, key: &Self::Key) -> Vec<u8> {
+        let mut plaintext = vec![0; self.ciphertext.len()];
+        key.order(self.ciphertext.len())
+            .into_iter()
+            .zip(&self.ciphertext)
+            .for_each(|(i, &c)| plaintext[i] = c);
+        plaintext
+    }
+}
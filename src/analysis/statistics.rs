@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+/// How many times every byte value occurs
+pub fn byte_counts(symbols: &[u8]) -> [usize; 256] {
+    let mut counts = [0; 256];
+    symbols.iter().for_each(|&b| counts[b as usize] += 1);
+    counts
+}
+
+/// The probability that two symbols picked at random from the text are the same. About 0.066 for English letters,
+/// and 1/26 for uniformly random ones.
+pub fn index_of_coincidence(symbols: &[u8]) -> f64 {
+    let n = symbols.len();
+    if n < 2 {
+        return 0.0;
+    }
+
+    let pairs: usize = byte_counts(symbols)
+        .iter()
+        .map(|&c| c * c.saturating_sub(1))
+        .sum();
+    pairs as f64 / (n * (n - 1)) as f64
+}
+
+/// The average index of coincidence of the columns of the text written in rows of `period` symbols
+pub fn periodic_index_of_coincidence(symbols: &[u8], period: usize) -> f64 {
+    assert!(period > 0, "The period has to be positive");
+
+    let total: f64 = columns(symbols, period)
+        .iter()
+        .map(|c| index_of_coincidence(c))
+        .sum();
+    total / period as f64
+}
+
+/// The text written in rows of `period` symbols, read column by column
+pub fn columns(symbols: &[u8], period: usize) -> Vec<Vec<u8>> {
+    assert!(period > 0, "The period has to be positive");
+
+    let mut columns = vec![Vec::with_capacity(symbols.len() / period + 1); period];
+    symbols
+        .iter()
+        .enumerate()
+        .for_each(|(i, &b)| columns[i % period].push(b));
+    columns
+}
+
+/// The number of bits that differ, over the length of the shorter slice
+pub fn hamming_distance(a: &[u8], b: &[u8]) -> u32 {
+    a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum()
+}
+
+/// The distances between consecutive occurrences of every repeated run of `len` symbols, as used by the Kasiski
+/// examination
+pub fn repeat_distances(symbols: &[u8], len: usize) -> Vec<usize> {
+    assert!(
+        len > 0,
+        "The repeated runs have to be at least one symbol long"
+    );
+
+    let mut last: HashMap<&[u8], usize> = HashMap::new();
+    let mut distances = Vec::new();
+    symbols.windows(len).enumerate().for_each(|(i, w)| {
+        if let Some(previous) = last.insert(w, i) {
+            distances.push(i - previous);
+        }
+    });
+    distances
+}
+
+/// The Shannon entropy of the symbols in bits per symbol: about 4.1 for English letters, and 8 for random bytes
+pub fn entropy(symbols: &[u8]) -> f64 {
+    let n = symbols.len() as f64;
+    -byte_counts(symbols)
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / n;
+            p * p.log2()
+        })
+        .sum::<f64>()
+}
@@ -1,10 +1,13 @@
 use std::{
-    fmt::Debug,
-    iter::once,
+    fmt::{Debug, Display},
+    iter::{once, FusedIterator},
     ops::{Add, AddAssign},
 };
 
-use crate::traits::Serialisable;
+use crate::{
+    common::{read_varint, write_varint},
+    traits::{BitIter, CryptographicIter, Serialisable},
+};
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SubByteValue {
@@ -12,6 +15,12 @@ pub struct SubByteValue {
     len: u8,
 }
 
+impl Default for SubByteValue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SubByteValue {
     pub fn new() -> Self {
         Self { len: 0, val: 0 }
@@ -30,6 +39,10 @@ impl SubByteValue {
     pub fn len(&self) -> u8 {
         self.len
     }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
 }
 
 impl AddAssign<bool> for SubByteValue {
@@ -83,7 +96,7 @@ impl From<SubByteValue> for String {
         // get binary repr
         let s = format!("{:08b}", v.val).to_owned();
 
-        let s = s.chars().into_iter();
+        let s = s.chars();
 
         // remove all the unneeded bits
         s.take(v.len as usize).collect()
@@ -99,12 +112,175 @@ impl Debug for SubByteValue {
     }
 }
 
+/// A run of up to 64 bits. Like `SubByteValue`, the bits are stored starting from the most significant one.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct BitChunk {
+    val: u64,
+    len: u8,
+}
+
+impl BitChunk {
+    pub const MAX_LEN: u8 = 64;
+
+    pub fn new() -> Self {
+        Self { len: 0, val: 0 }
+    }
+
+    // The lowest `len` bits of `value`, e.g. a 9-bit LZW code
+    pub fn checked_from_value(value: u64, len: u8) -> Option<Self> {
+        if len > Self::MAX_LEN || (len < Self::MAX_LEN && value >> len != 0) {
+            return None;
+        }
+        Some(Self {
+            val: if len == 0 { 0 } else { value << (64 - len) },
+            len,
+        })
+    }
+
+    pub fn from_value(value: u64, len: u8) -> Self {
+        Self::checked_from_value(value, len)
+            .expect("The value does not fit into the given number of bits")
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.val = 0;
+        self.len = 0;
+    }
+
+    #[inline]
+    pub fn raw_value(&self) -> u64 {
+        self.val
+    }
+    // The bits as a number, i.e. aligned to the least significant bit
+    #[inline]
+    pub fn value(&self) -> u64 {
+        if self.len == 0 {
+            0
+        } else {
+            self.val >> (64 - self.len)
+        }
+    }
+    #[inline]
+    pub fn len(&self) -> u8 {
+        self.len
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn checked_add(mut self, bit: bool) -> Option<Self> {
+        if self.len == Self::MAX_LEN {
+            return None;
+        }
+        self.len += 1;
+        self.val |= (bit as u64) << (64 - self.len);
+        Some(self)
+    }
+}
+
+impl AddAssign<bool> for BitChunk {
+    fn add_assign(&mut self, bit: bool) {
+        *self = self
+            .checked_add(bit)
+            .expect("The length of a BitChunk can not be longer than 64 bits");
+    }
+}
+
+impl Add<bool> for BitChunk {
+    type Output = BitChunk;
+
+    fn add(mut self, v: bool) -> Self::Output {
+        self += v;
+        self
+    }
+}
+
+impl From<SubByteValue> for BitChunk {
+    fn from(v: SubByteValue) -> Self {
+        Self {
+            val: (v.val as u64) << 56,
+            len: v.len,
+        }
+    }
+}
+
+impl TryFrom<BitChunk> for SubByteValue {
+    type Error = std::io::Error;
+
+    fn try_from(v: BitChunk) -> Result<Self, Self::Error> {
+        if v.len > 8 {
+            return Err(Self::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The length of a SubByteValue can not be longer than a byte",
+            ));
+        }
+        Ok(SubByteValue {
+            val: (v.val >> 56) as u8,
+            len: v.len,
+        })
+    }
+}
+
+impl TryFrom<&str> for BitChunk {
+    type Error = std::io::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut ret = BitChunk::new();
+        for c in value.chars() {
+            let bit = match c {
+                '0' => false,
+                '1' => true,
+                _ => {
+                    return Err(Self::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "The string has to contain only '0' and '1' characters",
+                    ));
+                }
+            };
+            ret = ret.checked_add(bit).ok_or_else(|| {
+                Self::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "The length of a BitChunk can not be longer than 64 bits",
+                )
+            })?;
+        }
+        Ok(ret)
+    }
+}
+
+impl From<BitChunk> for String {
+    fn from(v: BitChunk) -> Self {
+        // get binary repr and remove all the unneeded bits
+        format!("{:064b}", v.val)
+            .chars()
+            .take(v.len as usize)
+            .collect()
+    }
+}
+
+impl Debug for BitChunk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BitChunk")
+            .field("v", &<String as From<BitChunk>>::from(*self))
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct BitVec {
     bytes: Vec<u8>,
     last_sub_byte: SubByteValue,
 }
 
+impl Default for BitVec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BitVec {
     pub fn with_capacity(cap: usize) -> Self {
         Self {
@@ -119,9 +295,15 @@ impl BitVec {
         }
     }
 
-    // TODO: make sure this does not overflow
     pub fn len(&self) -> usize {
-        self.bytes.len() * 8 + self.last_sub_byte.len as usize
+        self.bytes
+            .len()
+            .checked_mul(8)
+            .and_then(|len| len.checked_add(self.last_sub_byte.len as usize))
+            .expect("The number of bits in the BitVec does not fit into a usize")
+    }
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty() && self.last_sub_byte.is_empty()
     }
     pub fn clear(&mut self) {
         self.bytes.clear();
@@ -137,6 +319,20 @@ impl BitVec {
     pub fn last_sub_byte(&self) -> &SubByteValue {
         &self.last_sub_byte
     }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        let (vec_index, bit_index) = (index / 8, (index % 8) as u8);
+
+        let byte = match self.bytes.get(vec_index) {
+            Some(byte) => *byte,
+            None if vec_index == self.bytes.len() && bit_index < self.last_sub_byte.len => {
+                self.last_sub_byte.val
+            }
+            None => return None,
+        };
+
+        Some(byte & (1 << (7 - bit_index)) != 0)
+    }
 }
 
 impl AddAssign<bool> for BitVec {
@@ -196,6 +392,27 @@ impl Add<SubByteValue> for BitVec {
     }
 }
 
+impl AddAssign<BitChunk> for BitVec {
+    fn add_assign(&mut self, v: BitChunk) {
+        // split the chunk into bytes, starting from the most significant one
+        (0..v.len).step_by(8).for_each(|offset| {
+            *self += SubByteValue {
+                val: (v.val >> (56 - offset)) as u8,
+                len: (v.len - offset).min(8),
+            };
+        });
+    }
+}
+
+impl Add<BitChunk> for BitVec {
+    type Output = BitVec;
+
+    fn add(mut self, v: BitChunk) -> Self::Output {
+        self += v;
+        self
+    }
+}
+
 impl AddAssign<BitVec> for BitVec {
     fn add_assign(&mut self, v: BitVec) {
         // TODO: do those extra allocations of the len byte matter? Should we optimise them?
@@ -215,9 +432,12 @@ impl Add<BitVec> for BitVec {
     }
 }
 
+// `front..back` is the range of bits that have not been yielded yet
+#[derive(Clone)]
 pub struct IntoIter {
     stream: BitVec,
-    index: usize, // TODO: make sure it does not overflow
+    front: usize,
+    back: usize,
 }
 
 impl IntoIterator for BitVec {
@@ -225,8 +445,9 @@ impl IntoIterator for BitVec {
     type IntoIter = IntoIter;
     fn into_iter(self) -> Self::IntoIter {
         IntoIter {
+            back: self.len(),
             stream: self,
-            index: 0,
+            front: 0,
         }
     }
 }
@@ -234,33 +455,157 @@ impl IntoIterator for BitVec {
 impl Iterator for IntoIter {
     type Item = bool;
 
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let vec_index = self.index / 8;
-        let bit_index = (self.index % 8) as u8;
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        self.stream.get(self.front - 1)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
 
-        let vec_len = self.stream.bytes.len();
-        let byte = if vec_index > vec_len {
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.front.saturating_add(n).min(self.back);
+        self.next()
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
             return None;
-        } else if vec_len == vec_index {
-            if bit_index >= self.stream.last_sub_byte.len {
-                return None;
-            } else {
-                self.stream.last_sub_byte.val
-            }
-        } else {
-            // SAFETY: we have checked the length already - it is safe to get the value
-            unsafe { *self.stream.bytes.get_unchecked(vec_index) }
-        };
+        }
+        self.back -= 1;
+        self.stream.get(self.back)
+    }
+
+    #[inline]
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.back = self.back.saturating_sub(n).max(self.front);
+        self.next_back()
+    }
+}
 
-        let ret = byte & (1 << 7 - bit_index) != 0;
+impl ExactSizeIterator for IntoIter {}
 
-        self.index += 1;
+impl FusedIterator for IntoIter {}
+
+impl BitIter for IntoIter {}
+
+impl FromIterator<bool> for BitVec {
+    fn from_iter<T: IntoIterator<Item = bool>>(iter: T) -> Self {
+        let mut ret = BitVec::new();
+        iter.into_iter().for_each(|bit| ret += bit);
+        ret
+    }
+}
+
+/// What to do with the trailing bits if a bit stream does not end on a byte boundary
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitPadding {
+    /// Fill the rest of the last byte with `0`s
+    Zeros,
+    /// Fill the rest of the last byte with `1`s
+    Ones,
+    /// Discard the incomplete last byte
+    Drop,
+}
+
+/// Splits each byte into bits, most significant bit first, matching the layout of `BitVec`
+#[derive(Clone)]
+pub struct ByteToBitTransform<I: CryptographicIter> {
+    iter: I,
+    current: SubByteValue,
+    index: u8,
+}
+
+impl<I: CryptographicIter> ByteToBitTransform<I> {
+    #[inline]
+    pub fn new(iter: I) -> Self {
+        ByteToBitTransform {
+            iter,
+            current: SubByteValue::new(),
+            index: 0,
+        }
+    }
+}
+
+impl<I: CryptographicIter> Iterator for ByteToBitTransform<I> {
+    type Item = bool;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.current.len {
+            self.current = SubByteValue {
+                val: self.iter.next()?,
+                len: 8,
+            };
+            self.index = 0;
+        }
 
+        let ret = self.current.val & (1 << (7 - self.index)) != 0;
+        self.index += 1;
         Some(ret)
     }
 }
+impl<I: CryptographicIter> BitIter for ByteToBitTransform<I> {}
 
-impl Debug for BitVec {
+/// Packs bits back into bytes, most significant bit first
+#[derive(Clone)]
+pub struct BitToByteTransform<I: BitIter> {
+    iter: I,
+    padding: BitPadding,
+}
+
+impl<I: BitIter> BitToByteTransform<I> {
+    #[inline]
+    pub fn new(iter: I, padding: BitPadding) -> Self {
+        BitToByteTransform { iter, padding }
+    }
+}
+
+impl<I: BitIter> Iterator for BitToByteTransform<I> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut byte = SubByteValue::new();
+        while byte.len < 8 {
+            match self.iter.next() {
+                Some(bit) => byte += bit,
+                None => break,
+            }
+        }
+
+        match (byte.len, self.padding) {
+            (0, _) => None,
+            (8, _) => Some(byte.val),
+            (_, BitPadding::Zeros) => Some(byte.val),
+            (len, BitPadding::Ones) => Some(byte.val | (u8::MAX >> len)),
+            (_, BitPadding::Drop) => None,
+        }
+    }
+}
+impl<I: BitIter> CryptographicIter for BitToByteTransform<I> {}
+
+impl Display for BitVec {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = self.bytes.iter().map(|b| format!("{:08b}", b));
 
@@ -270,36 +615,85 @@ impl Debug for BitVec {
             s.chain(once(self.last_sub_byte.clone().into())).collect()
         };
 
-        f.debug_struct("BitVec").field("data", &s).finish()
+        f.write_str(&s)
+    }
+}
+
+impl Debug for BitVec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BitVec")
+            .field("data", &self.to_string())
+            .finish()
+    }
+}
+
+/// The first byte of a `BitVec` serialised in the current format.
+///
+/// The legacy layout starts with the number of leftover bits instead, which is always less than 8.
+pub const BIT_VEC_FORMAT_VERSION: u8 = 0x81;
+
+impl BitVec {
+    // the layout before the format was versioned: `[len_of_tail, tail_val, bytes...]`
+    pub fn deserialise_legacy<I: Iterator<Item = u8>>(mut b: I) -> Option<Self> {
+        let (len, val) = (b.next()?, b.next()?);
+        if len >= 8 || val & (u8::MAX >> len) != 0 {
+            return None;
+        }
+
+        Some(BitVec {
+            last_sub_byte: SubByteValue { val, len },
+            bytes: b.collect(),
+        })
     }
 }
 
 impl Serialisable for BitVec {
-    type CryptoIter = std::iter::Chain<
-        std::iter::Chain<std::iter::Once<u8>, std::iter::Once<u8>>,
-        std::vec::IntoIter<u8>,
-    >;
+    type CryptoIter = std::vec::IntoIter<u8>;
 
+    // `[version, varint(total bit length), bytes..., leftover bits padded with zeros]`
     fn serialise(&self) -> Self::CryptoIter {
-        once(self.last_sub_byte.len)
-            .chain(once(self.last_sub_byte.val))
-            .chain(self.bytes.clone().into_iter())
+        let mut ret = Vec::with_capacity(self.bytes.len() + 12);
+        ret.push(BIT_VEC_FORMAT_VERSION);
+        write_varint(self.len() as u64, &mut ret);
+        ret.extend_from_slice(&self.bytes);
+        if !self.last_sub_byte.is_empty() {
+            ret.push(self.last_sub_byte.val);
+        }
+        ret.into_iter()
     }
 
-    fn deserialise<I: Iterator<Item = u8>>(mut b: I) -> Option<Self>
+    fn deserialise<I: Iterator<Item = u8>>(b: I) -> Option<Self>
     where
         Self: Sized,
     {
-        // the first two bytes are the length of the "leftover" bits and their values.
-        if let (Some(len), Some(val)) = (b.next(), b.next()) {
-            let sub_byte_value = SubByteValue { val, len };
-            Some(BitVec {
-                last_sub_byte: sub_byte_value,
-                bytes: b.collect(),
-            })
-        } else {
-            None
+        let mut b = b.peekable();
+        if *b.peek()? != BIT_VEC_FORMAT_VERSION {
+            return Self::deserialise_legacy(b);
         }
+        b.next();
+
+        let len: usize = read_varint(&mut b)?.try_into().ok()?;
+        let (byte_count, tail_len) = (len / 8, (len % 8) as u8);
+
+        let bytes: Vec<u8> = b.by_ref().take(byte_count).collect();
+        if bytes.len() != byte_count {
+            return None;
+        }
+
+        let mut last_sub_byte = SubByteValue::new();
+        if tail_len != 0 {
+            let val = b.next()?;
+            // the padding bits have to be zero, otherwise the header does not match the data
+            if val & (u8::MAX >> tail_len) != 0 {
+                return None;
+            }
+            last_sub_byte = SubByteValue { val, len: tail_len };
+        }
+
+        Some(BitVec {
+            bytes,
+            last_sub_byte,
+        })
     }
 }
 
@@ -327,3 +721,113 @@ impl TryFrom<&str> for BitVec {
         Ok(ret)
     }
 }
+
+impl BitVec {
+    pub fn from_bytes(bytes: &[u8], bit_len: usize) -> Result<Self, std::io::Error> {
+        if bytes.len() != bit_len.div_ceil(8) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The number of bytes has to match the number of bits",
+            ));
+        }
+
+        let tail_len = (bit_len % 8) as u8;
+        let (bytes, last_sub_byte) = if tail_len == 0 {
+            (bytes.to_vec(), SubByteValue::new())
+        } else {
+            let (last, bytes) = bytes.split_last().unwrap(); // SAFETY: there is at least one byte for the leftover bits
+            (
+                bytes.to_vec(),
+                SubByteValue {
+                    // the padding bits are ignored
+                    val: last & !(u8::MAX >> tail_len),
+                    len: tail_len,
+                },
+            )
+        };
+
+        Ok(BitVec {
+            bytes,
+            last_sub_byte,
+        })
+    }
+
+    // The bytes with the last one padded with zeros, and the number of padding bits at the end
+    pub fn into_padded_bytes(self) -> (Vec<u8>, u8) {
+        let BitVec {
+            mut bytes,
+            last_sub_byte,
+        } = self;
+        if last_sub_byte.is_empty() {
+            (bytes, 0)
+        } else {
+            bytes.push(last_sub_byte.val);
+            (bytes, 8 - last_sub_byte.len)
+        }
+    }
+
+    pub fn from_uint(value: u128, width: usize) -> Result<Self, std::io::Error> {
+        if width > 128 || (width < 128 && value >> width != 0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The value does not fit into the given number of bits",
+            ));
+        }
+
+        Ok((0..width).rev().map(|i| value >> i & 1 == 1).collect())
+    }
+}
+
+impl TryFrom<(&[u8], usize)> for BitVec {
+    type Error = std::io::Error;
+
+    fn try_from((bytes, bit_len): (&[u8], usize)) -> Result<Self, Self::Error> {
+        BitVec::from_bytes(bytes, bit_len)
+    }
+}
+
+impl From<&[bool]> for BitVec {
+    fn from(bits: &[bool]) -> Self {
+        bits.iter().copied().collect()
+    }
+}
+
+impl From<Vec<bool>> for BitVec {
+    fn from(bits: Vec<bool>) -> Self {
+        bits.into_iter().collect()
+    }
+}
+
+impl From<BitVec> for Vec<bool> {
+    fn from(v: BitVec) -> Self {
+        v.into_iter().collect()
+    }
+}
+
+macro_rules! impl_from_uint {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for BitVec {
+                fn from(value: $t) -> Self {
+                    // SAFETY: a value always fits into the width of its own type
+                    BitVec::from_uint(value as u128, <$t>::BITS as usize).unwrap()
+                }
+            }
+        )*
+    };
+}
+impl_from_uint!(u8, u16, u32, u64, u128);
+
+/// Builds a `BitVec` out of a list of `0`s and `1`s, e.g. `bits![0, 1, 1]`
+#[macro_export]
+macro_rules! bits {
+    ($($bit:literal),* $(,)?) => {
+        $crate::bitstream::BitVec::from(
+            &[$({
+                let bit: u8 = $bit;
+                assert!(bit <= 1, "A bit can only be 0 or 1");
+                bit == 1
+            }),*][..]
+        )
+    };
+}
@@ -12,3 +12,40 @@ impl Serialisable for String {
         Some(String::from_utf8(b).expect("Expected a valid UTF-8 string"))
     }
 }
+
+// Unsigned LEB128: 7 bits per byte, least significant group first, the high bit marks a continuation
+pub fn write_varint(mut v: u64, out: &mut Vec<u8>) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+pub fn read_varint<I: Iterator<Item = u8>>(b: &mut I) -> Option<u64> {
+    let mut ret = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = b.next()?;
+        let group = (byte & 0x7F) as u64;
+
+        // reject values that do not fit into a u64
+        if shift == 63 && group > 1 {
+            return None;
+        }
+        ret |= group << shift;
+
+        if byte & 0x80 == 0 {
+            // reject non-canonical encodings with redundant trailing zero groups
+            if group == 0 && shift != 0 {
+                return None;
+            }
+            return Some(ret);
+        }
+    }
+    None
+}
+
+// Lowercase hexadecimal, two digits per byte
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -46,7 +46,7 @@ impl Serialisable for BTreeMap<u8, BitVec> {
         ret.into_iter().chain(bit_vec.serialise())
     }
 
-    fn deserialise<I: Iterator<Item = u8>>(b: I) -> Option<Self>
+    fn deserialise<I: Iterator<Item = u8>>(_b: I) -> Option<Self>
     where
         Self: Sized,
     {
@@ -56,10 +56,10 @@ impl Serialisable for BTreeMap<u8, BitVec> {
 
 // TODO: maybe use an array representation instead
 enum ByteFrequencyTreeNode {
-    LEAF {
+    Leaf {
         byte: u8,
     },
-    NODE {
+    Node {
         left: Box<ByteFrequencyEntry>,
         right: Box<ByteFrequencyEntry>,
     },
@@ -72,7 +72,7 @@ struct ByteFrequencyEntry {
 impl ByteFrequencyEntry {
     fn fill_in_map(&self, m: &mut BTreeMap<u8, BitVec>, current_val: BitVec) {
         match &self.data {
-            ByteFrequencyTreeNode::NODE { left, right } => {
+            ByteFrequencyTreeNode::Node { left, right } => {
                 let mut l = current_val.clone();
                 let mut r = current_val.clone();
                 l += false;
@@ -80,7 +80,7 @@ impl ByteFrequencyEntry {
                 left.fill_in_map(m, l);
                 right.fill_in_map(m, r);
             }
-            ByteFrequencyTreeNode::LEAF { byte } => {
+            ByteFrequencyTreeNode::Leaf { byte } => {
                 m.insert(*byte, current_val);
             }
         }
@@ -96,8 +96,7 @@ impl Eq for ByteFrequencyEntry {}
 
 impl PartialOrd for ByteFrequencyEntry {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        // NOTE: `other` and `self` are intentionally the other way round than expected, to make sure it is a min heap
-        other.f.partial_cmp(&self.f)
+        Some(self.cmp(other))
     }
 }
 impl Ord for ByteFrequencyEntry {
@@ -115,7 +114,7 @@ impl HuffmanEncoding {
 
     pub fn from_crypto_iter<I: CryptographicIter>(iter: I) -> Self {
         let data: Vec<_> = iter.collect();
-        if data.len() == 0 {
+        if data.is_empty() {
             return Self {
                 dictionary: BTreeMap::new(),
                 data: BitVec::new(),
@@ -133,7 +132,7 @@ impl HuffmanEncoding {
         frequencies.iter().for_each(|(b, f)| {
             frequency_heap.push(ByteFrequencyEntry {
                 f: *f,
-                data: ByteFrequencyTreeNode::LEAF { byte: *b },
+                data: ByteFrequencyTreeNode::Leaf { byte: *b },
             })
         });
 
@@ -147,7 +146,7 @@ impl HuffmanEncoding {
 
             frequency_heap.push(ByteFrequencyEntry {
                 f: a.f + b.f,
-                data: ByteFrequencyTreeNode::NODE {
+                data: ByteFrequencyTreeNode::Node {
                     left: Box::new(a),
                     right: Box::new(b),
                 },
@@ -199,7 +198,7 @@ impl HuffmanEncoding {
             }
         }
 
-        if buffer.len() != 0 {
+        if !buffer.is_empty() {
             return None;
         }
 
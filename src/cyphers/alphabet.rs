@@ -0,0 +1,484 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    sync::OnceLock,
+};
+
+use crate::{
+    common::{read_varint, write_varint},
+    traits::{CryptographicIter, Serialisable},
+};
+
+/// What happens to the characters of a message that are not in the alphabet
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutOfAlphabet {
+    /// Copy them to the output unchanged
+    PassThrough,
+    /// Leave them out of the output
+    Drop,
+    /// Stop the stream; the offending character can be retrieved with `AlphabetTransform::error`
+    Error,
+}
+
+/// An ordered set of symbols that classical cyphers operate on, e.g. `A`-`Z`.
+///
+/// A symbol is a sequence of bytes, so it can be a single byte, an ASCII letter or a whole Unicode grapheme.
+/// Alphabets with case folding also recognise the lowercase forms of their symbols and keep the case in the output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alphabet {
+    symbols: Vec<Vec<u8>>,
+    lowercase_symbols: Vec<Option<Vec<u8>>>,
+    // every recognised byte sequence, and the symbol it stands for and whether it is lowercase
+    lookup: HashMap<Vec<u8>, (usize, bool)>,
+    max_symbol_len: usize,
+    policy: OutOfAlphabet,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+    Symbol { index: usize, lowercase: bool },
+    Other(Vec<u8>),
+}
+
+impl Alphabet {
+    fn from_symbols(symbols: Vec<Vec<u8>>) -> Result<Self, std::io::Error> {
+        if symbols.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "An alphabet has to contain at least one symbol",
+            ));
+        }
+
+        let mut lookup = HashMap::with_capacity(symbols.len());
+        for (i, s) in symbols.iter().enumerate() {
+            if s.is_empty() || lookup.insert(s.clone(), (i, false)).is_some() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "The symbols of an alphabet have to be non-empty and unique",
+                ));
+            }
+        }
+
+        Ok(Alphabet {
+            max_symbol_len: symbols.iter().map(Vec::len).max().unwrap_or(0),
+            lowercase_symbols: vec![None; symbols.len()],
+            symbols,
+            lookup,
+            policy: OutOfAlphabet::PassThrough,
+        })
+    }
+
+    /// Every byte value, in order
+    pub fn bytes() -> Self {
+        // SAFETY: all the bytes are distinct
+        Self::from_symbols((0..=255).map(|b| vec![b]).collect()).unwrap()
+    }
+
+    // the alphabet of every byte value, built once for the transforms that default to it
+    pub(crate) fn shared_bytes() -> &'static Self {
+        static BYTES: OnceLock<Alphabet> = OnceLock::new();
+        BYTES.get_or_init(Self::bytes)
+    }
+
+    /// `A`-`Z`, also accepting `a`-`z` and keeping their case
+    pub fn letters() -> Self {
+        Self::uppercase().with_case_folding()
+    }
+
+    /// `A`-`Z` only
+    pub fn uppercase() -> Self {
+        // SAFETY: all the letters are distinct
+        Self::from_symbols((b'A'..=b'Z').map(|b| vec![b]).collect()).unwrap()
+    }
+
+    /// `a`-`z` only
+    pub fn lowercase() -> Self {
+        // SAFETY: all the letters are distinct
+        Self::from_symbols((b'a'..=b'z').map(|b| vec![b]).collect()).unwrap()
+    }
+
+    /// `A`-`Z` followed by `0`-`9`, also accepting `a`-`z`.
+    ///
+    /// NOTE: a lowercase letter that gets replaced by a digit loses its case
+    pub fn alphanumeric() -> Self {
+        // SAFETY: all the characters are distinct
+        Self::from_symbols((b'A'..=b'Z').chain(b'0'..=b'9').map(|b| vec![b]).collect())
+            .unwrap()
+            .with_case_folding()
+    }
+
+    /// Every character of `symbols` is a symbol, in the order they appear in
+    pub fn custom(symbols: &str) -> Result<Self, std::io::Error> {
+        Self::from_symbols(
+            symbols
+                .chars()
+                .map(|c| c.to_string().into_bytes())
+                .collect(),
+        )
+    }
+
+    /// Every string is a symbol, so that a grapheme made out of several characters (e.g. a letter with a
+    /// combining accent) can be used as a single symbol
+    pub fn graphemes<S: AsRef<str>>(symbols: &[S]) -> Result<Self, std::io::Error> {
+        Self::from_symbols(
+            symbols
+                .iter()
+                .map(|s| s.as_ref().as_bytes().to_vec())
+                .collect(),
+        )
+    }
+
+    /// Also accept the lowercase form of every symbol, as long as it is not a separate symbol already
+    pub fn with_case_folding(mut self) -> Self {
+        for (i, s) in self.symbols.iter().enumerate() {
+            let Ok(s) = std::str::from_utf8(s) else {
+                continue;
+            };
+            let lower = s.to_lowercase().into_bytes();
+            if !self.lookup.contains_key(&lower) {
+                self.max_symbol_len = self.max_symbol_len.max(lower.len());
+                self.lookup.insert(lower.clone(), (i, true));
+                self.lowercase_symbols[i] = Some(lower);
+            }
+        }
+        self
+    }
+
+    pub fn with_policy(mut self, policy: OutOfAlphabet) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    #[inline]
+    pub fn policy(&self) -> OutOfAlphabet {
+        self.policy
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    #[inline]
+    pub fn symbol(&self, index: usize) -> Option<&[u8]> {
+        self.symbols.get(index).map(Vec::as_slice)
+    }
+
+    #[inline]
+    pub fn index_of(&self, symbol: &[u8]) -> Option<usize> {
+        self.lookup.get(symbol).map(|(i, _)| *i)
+    }
+
+    /// Appends a symbol to `out`, in lowercase if requested and the alphabet has a lowercase form of it
+    pub fn write_symbol(&self, index: usize, lowercase: bool, out: &mut Vec<u8>) {
+        match (&self.lowercase_symbols[index], lowercase) {
+            (Some(lower), true) => out.extend_from_slice(lower),
+            _ => out.extend_from_slice(&self.symbols[index]),
+        }
+    }
+
+    // Finds the longest symbol at the start of `bytes`, returning the token and the number of bytes it takes up.
+    // Anything that is not a symbol is split into whole UTF-8 characters, so that they are not torn apart.
+    fn match_prefix(&self, bytes: &[u8]) -> (Token, usize) {
+        for len in (1..=self.max_symbol_len.min(bytes.len())).rev() {
+            if let Some((index, lowercase)) = self.lookup.get(&bytes[..len]) {
+                return (
+                    Token::Symbol {
+                        index: *index,
+                        lowercase: *lowercase,
+                    },
+                    len,
+                );
+            }
+        }
+
+        let len = match bytes[0] {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        }
+        .min(bytes.len());
+        (Token::Other(bytes[..len].to_vec()), len)
+    }
+
+    pub fn tokenise<I: Iterator<Item = u8>>(&self, iter: I) -> Tokens<I> {
+        Tokens {
+            iter,
+            alphabet: self.clone(),
+            reader: SymbolReader::new(),
+        }
+    }
+}
+
+impl Serialisable for Alphabet {
+    type CryptoIter = std::vec::IntoIter<u8>;
+
+    // `[varint(symbol count), (varint(symbol length), symbol bytes)..., case folding, policy]`
+    fn serialise(&self) -> Self::CryptoIter {
+        let mut ret = Vec::new();
+        write_varint(self.symbols.len() as u64, &mut ret);
+        self.symbols.iter().for_each(|s| {
+            write_varint(s.len() as u64, &mut ret);
+            ret.extend_from_slice(s);
+        });
+
+        // NOTE: case folding is deterministic, so it is enough to store whether it was applied
+        ret.push(self.lowercase_symbols.iter().any(Option::is_some) as u8);
+        ret.push(match self.policy {
+            OutOfAlphabet::PassThrough => 0,
+            OutOfAlphabet::Drop => 1,
+            OutOfAlphabet::Error => 2,
+        });
+        ret.into_iter()
+    }
+
+    fn deserialise<I: Iterator<Item = u8>>(mut b: I) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let count = read_varint(&mut b)?;
+        let mut symbols = Vec::new();
+        for _ in 0..count {
+            let len = read_varint(&mut b)?;
+            let symbol: Vec<u8> = b.by_ref().take(len.try_into().ok()?).collect();
+            if symbol.len() as u64 != len {
+                return None;
+            }
+            symbols.push(symbol);
+        }
+
+        let mut ret = Self::from_symbols(symbols).ok()?;
+        match b.next()? {
+            0 => {}
+            1 => ret = ret.with_case_folding(),
+            _ => return None,
+        }
+        ret.policy = match b.next()? {
+            0 => OutOfAlphabet::PassThrough,
+            1 => OutOfAlphabet::Drop,
+            2 => OutOfAlphabet::Error,
+            _ => return None,
+        };
+        Some(ret)
+    }
+}
+
+/// Reads symbols out of a byte stream, keeping enough bytes to recognise the longest symbol of an alphabet
+#[derive(Clone, Debug, Default)]
+pub struct SymbolReader {
+    buffer: VecDeque<u8>,
+}
+
+impl SymbolReader {
+    pub fn new() -> Self {
+        Self {
+            buffer: VecDeque::new(),
+        }
+    }
+
+    pub fn next_token<I: Iterator<Item = u8>>(
+        &mut self,
+        iter: &mut I,
+        alphabet: &Alphabet,
+    ) -> Option<Token> {
+        while self.buffer.len() < alphabet.max_symbol_len.max(4) {
+            match iter.next() {
+                Some(b) => self.buffer.push_back(b),
+                None => break,
+            }
+        }
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        let (token, len) = alphabet.match_prefix(self.buffer.make_contiguous());
+        self.buffer.drain(..len);
+        Some(token)
+    }
+
+    /// The index of the next symbol, skipping everything that is not in the alphabet
+    pub fn next_symbol<I: Iterator<Item = u8>>(
+        &mut self,
+        iter: &mut I,
+        alphabet: &Alphabet,
+    ) -> Option<usize> {
+        loop {
+            if let Token::Symbol { index, .. } = self.next_token(iter, alphabet)? {
+                return Some(index);
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Tokens<I: Iterator<Item = u8>> {
+    iter: I,
+    alphabet: Alphabet,
+    reader: SymbolReader,
+}
+
+impl<I: Iterator<Item = u8>> Iterator for Tokens<I> {
+    type Item = Token;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.next_token(&mut self.iter, &self.alphabet)
+    }
+}
+
+/// A cypher that replaces every symbol of an alphabet with another one, one at a time
+pub trait SymbolCypher {
+    /// Maps the index of a symbol to the index of its replacement. Returning `None` ends the stream, and `error`
+    /// should then say why.
+    fn apply(&mut self, index: usize, alphabet: &Alphabet) -> Option<usize>;
+
+    /// The error that made `apply` end the stream, if any
+    #[inline]
+    fn error(&self) -> Option<std::io::Error> {
+        None
+    }
+}
+
+#[derive(Clone)]
+pub struct AlphabetTransform<I: CryptographicIter, C: SymbolCypher> {
+    iter: I,
+    cypher: C,
+    alphabet: Cow<'static, Alphabet>,
+    reader: SymbolReader,
+    output: VecDeque<u8>,
+    error: Option<Vec<u8>>,
+    // whether the cypher ended the stream
+    stopped: bool,
+}
+
+impl<I: CryptographicIter, C: SymbolCypher> AlphabetTransform<I, C> {
+    #[inline]
+    pub fn new(iter: I, alphabet: &Alphabet, cypher: C) -> Self {
+        Self::with_alphabet(iter, Cow::Owned(alphabet.clone()), cypher)
+    }
+
+    /// Works over all the bytes, without building a new alphabet for every transform
+    #[inline]
+    pub(crate) fn over_bytes(iter: I, cypher: C) -> Self {
+        Self::with_alphabet(iter, Cow::Borrowed(Alphabet::shared_bytes()), cypher)
+    }
+
+    #[inline]
+    fn with_alphabet(iter: I, alphabet: Cow<'static, Alphabet>, cypher: C) -> Self {
+        AlphabetTransform {
+            iter,
+            cypher,
+            alphabet,
+            reader: SymbolReader::new(),
+            output: VecDeque::new(),
+            error: None,
+            stopped: false,
+        }
+    }
+
+    /// Switches the alphabet the cypher works over. This has to be done before the stream is read.
+    #[inline]
+    pub fn in_alphabet(mut self, alphabet: &Alphabet) -> Self {
+        self.alphabet = Cow::Owned(alphabet.clone());
+        self
+    }
+
+    /// Only works on ASCII letters, modulo 26 and keeping their case, as in the textbook cyphers
+    #[inline]
+    pub fn letters_only(self) -> Self {
+        self.in_alphabet(&Alphabet::letters())
+    }
+
+    #[inline]
+    pub fn cypher(&self) -> &C {
+        &self.cypher
+    }
+
+    /// The error that stopped the stream, if a character outside of the alphabet was found with
+    /// `OutOfAlphabet::Error`, or the cypher could not go on
+    pub fn error(&self) -> Option<std::io::Error> {
+        if self.stopped {
+            return Some(self.cypher.error().unwrap_or_else(|| {
+                std::io::Error::other("The cypher ended the stream before the end of the message")
+            }));
+        }
+        self.error.as_ref().map(|c| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{:?} is not in the alphabet",
+                    String::from_utf8_lossy(c).as_ref()
+                ),
+            )
+        })
+    }
+}
+
+impl<I: CryptographicIter, C: SymbolCypher> Iterator for AlphabetTransform<I, C> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.output.is_empty() {
+            if self.error.is_some() || self.stopped {
+                return None;
+            }
+
+            match self.reader.next_token(&mut self.iter, &self.alphabet)? {
+                Token::Symbol { index, lowercase } => {
+                    let Some(index) = self.cypher.apply(index, &self.alphabet) else {
+                        self.stopped = true;
+                        return None;
+                    };
+                    let mut symbol = Vec::new();
+                    self.alphabet.write_symbol(index, lowercase, &mut symbol);
+                    self.output.extend(symbol);
+                }
+                Token::Other(bytes) => match self.alphabet.policy {
+                    OutOfAlphabet::PassThrough => self.output.extend(bytes),
+                    OutOfAlphabet::Drop => {}
+                    OutOfAlphabet::Error => self.error = Some(bytes),
+                },
+            }
+        }
+
+        self.output.pop_front()
+    }
+}
+impl<I: CryptographicIter, C: SymbolCypher> CryptographicIter for AlphabetTransform<I, C> {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Encrypt,
+    Decrypt,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CaesarShift {
+    shift: usize,
+    direction: Direction,
+}
+
+impl CaesarShift {
+    #[inline]
+    pub fn new(shift: usize, direction: Direction) -> Self {
+        CaesarShift { shift, direction }
+    }
+}
+
+impl SymbolCypher for CaesarShift {
+    #[inline]
+    fn apply(&mut self, index: usize, alphabet: &Alphabet) -> Option<usize> {
+        let n = alphabet.len();
+        let shift = match self.direction {
+            Direction::Encrypt => self.shift % n,
+            Direction::Decrypt => n - self.shift % n,
+        };
+        Some((index + shift) % n)
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+
+use crate::traits::BitIter;
+
+#[derive(Clone)]
+pub struct BitXorTransform<I1: BitIter, I2: BitIter> {
+    iter: I1,
+    key: I2,
+}
+
+impl<I1: BitIter, I2: BitIter> BitXorTransform<I1, I2> {
+    #[inline]
+    pub fn new(iter: I1, key: I2) -> Self {
+        BitXorTransform { iter, key }
+    }
+}
+
+impl<I1: BitIter, I2: BitIter> Iterator for BitXorTransform<I1, I2> {
+    type Item = bool;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if let (Some(bit), Some(k)) = (self.iter.next(), self.key.next()) {
+            Some(bit ^ k)
+        } else {
+            None
+        }
+    }
+}
+impl<I1: BitIter, I2: BitIter> BitIter for BitXorTransform<I1, I2> {}
+
+/// Reorders every block of `permutation.len()` bits, so that the `i`th output bit of a block is the
+/// `permutation[i]`th input bit of it. An incomplete block at the end of the stream is passed through unchanged.
+#[derive(Clone)]
+pub struct BitPermutationTransform<I: BitIter> {
+    iter: I,
+    permutation: Vec<usize>,
+    block: Vec<bool>,
+    output: VecDeque<bool>,
+}
+
+impl<I: BitIter> BitPermutationTransform<I> {
+    pub fn new(iter: I, permutation: Vec<usize>) -> Self {
+        assert_permutation(&permutation);
+
+        BitPermutationTransform {
+            iter,
+            block: Vec::with_capacity(permutation.len()),
+            output: VecDeque::with_capacity(permutation.len()),
+            permutation,
+        }
+    }
+
+    pub fn new_inverse(iter: I, permutation: Vec<usize>) -> Self {
+        assert_permutation(&permutation);
+
+        let mut inverse = vec![0; permutation.len()];
+        permutation
+            .iter()
+            .enumerate()
+            .for_each(|(i, &p)| inverse[p] = i);
+
+        Self::new(iter, inverse)
+    }
+}
+
+fn assert_permutation(permutation: &[usize]) {
+    let mut seen = vec![false; permutation.len()];
+    permutation.iter().for_each(|&i| {
+        assert!(
+            i < seen.len() && !seen[i],
+            "The bit permutation has to contain every index in the block exactly once"
+        );
+        seen[i] = true;
+    });
+}
+
+impl<I: BitIter> Iterator for BitPermutationTransform<I> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.output.is_empty() {
+            self.block.clear();
+            self.block
+                .extend(self.iter.by_ref().take(self.permutation.len()));
+
+            if self.block.len() == self.permutation.len() {
+                let block = &self.block;
+                self.output
+                    .extend(self.permutation.iter().map(|&i| block[i]));
+            } else {
+                self.output.extend(self.block.iter());
+            }
+        }
+
+        self.output.pop_front()
+    }
+}
+impl<I: BitIter> BitIter for BitPermutationTransform<I> {}
+
+/// A Fibonacci linear-feedback shift register producing an endless keystream of bits.
+///
+/// Each step outputs the lowest bit of the register, shifts it right and feeds the parity of
+/// `state & taps` into the highest bit.
+#[derive(Clone, Debug)]
+pub struct Lfsr {
+    state: u64,
+    taps: u64,
+    width: u8,
+}
+
+impl Lfsr {
+    pub fn new(width: u8, taps: u64, seed: u64) -> Self {
+        assert!(
+            (1..=64).contains(&width),
+            "The width of an LFSR has to be between 1 and 64 bits"
+        );
+        let mask = u64::MAX >> (64 - width);
+        assert!(
+            seed & mask != 0,
+            "An LFSR seeded with zeros only ever produces zeros"
+        );
+
+        Lfsr {
+            state: seed & mask,
+            taps: taps & mask,
+            width,
+        }
+    }
+
+    #[inline]
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+}
+
+impl Iterator for Lfsr {
+    type Item = bool;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let ret = self.state & 1 != 0;
+        let feedback = (self.state & self.taps).count_ones() as u64 & 1;
+        self.state = (self.state >> 1) | (feedback << (self.width - 1));
+        Some(ret)
+    }
+}
+impl BitIter for Lfsr {}
@@ -0,0 +1,304 @@
+use std::collections::VecDeque;
+
+use crate::{cyphers::alphabet::Direction, random::SplitMix64, traits::CryptographicIter};
+
+/// A cypher that encrypts blocks of a fixed number of bytes
+pub trait BlockCypher {
+    fn block_size(&self) -> usize;
+
+    fn encrypt_block(&self, block: &mut [u8]);
+
+    fn decrypt_block(&self, block: &mut [u8]);
+}
+
+impl<C: BlockCypher + ?Sized> BlockCypher for &C {
+    #[inline]
+    fn block_size(&self) -> usize {
+        (**self).block_size()
+    }
+
+    #[inline]
+    fn encrypt_block(&self, block: &mut [u8]) {
+        (**self).encrypt_block(block)
+    }
+
+    #[inline]
+    fn decrypt_block(&self, block: &mut [u8]) {
+        (**self).decrypt_block(block)
+    }
+}
+
+const FEISTEL_ROUNDS: usize = 8;
+
+/// A toy Feistel network over 16 byte blocks, with round keys derived from a key of any length.
+///
+/// NOTE: it is only meant for showing attacks on block cypher modes, and is not secure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Feistel {
+    round_keys: [u64; FEISTEL_ROUNDS],
+}
+
+impl Feistel {
+    pub fn new(key: &[u8]) -> Self {
+        // the key is hashed with FNV-1a into the seed of the round keys
+        let seed = key.iter().fold(0xCBF2_9CE4_8422_2325u64, |h, &b| {
+            (h ^ b as u64).wrapping_mul(0x0100_0000_01B3)
+        });
+        let mut rng = SplitMix64::new(seed);
+        Feistel {
+            round_keys: std::array::from_fn(|_| rng.next_u64()),
+        }
+    }
+
+    // the finaliser of SplitMix64, which mixes every bit of the input into every bit of the output
+    #[inline]
+    fn round(half: u64, key: u64) -> u64 {
+        let mut z = half ^ key;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    #[inline]
+    fn split(block: &[u8]) -> (u64, u64) {
+        assert_eq!(block.len(), 16, "The blocks have to be 16 bytes long");
+        // SAFETY: both halves are 8 bytes long
+        (
+            u64::from_be_bytes(block[..8].try_into().unwrap()),
+            u64::from_be_bytes(block[8..].try_into().unwrap()),
+        )
+    }
+
+    #[inline]
+    fn join(block: &mut [u8], left: u64, right: u64) {
+        block[..8].copy_from_slice(&left.to_be_bytes());
+        block[8..].copy_from_slice(&right.to_be_bytes());
+    }
+}
+
+impl BlockCypher for Feistel {
+    #[inline]
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        let (mut left, mut right) = Self::split(block);
+        for &key in &self.round_keys {
+            (left, right) = (right, left ^ Self::round(right, key));
+        }
+        Self::join(block, left, right);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let (mut left, mut right) = Self::split(block);
+        for &key in self.round_keys.iter().rev() {
+            (left, right) = (right ^ Self::round(left, key), left);
+        }
+        Self::join(block, left, right);
+    }
+}
+
+/// How the blocks of a message are chained together
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockMode {
+    /// Every block on its own, so equal blocks of plaintext give equal blocks of ciphertext
+    Ecb,
+    /// Every block of plaintext is XORed with the block of ciphertext before it, and the first one with `iv`
+    Cbc { iv: Vec<u8> },
+}
+
+/// Pads the message to a multiple of `block_size` with `n` bytes of the value `n`. A message that already is a
+/// multiple gets a whole block of padding, so that the padding can always be told apart from the message.
+pub fn pkcs7_pad(message: &[u8], block_size: usize) -> Vec<u8> {
+    assert!(
+        (1..=255).contains(&block_size),
+        "The block size has to be between 1 and 255 bytes"
+    );
+
+    let n = block_size - message.len() % block_size;
+    let mut ret = Vec::with_capacity(message.len() + n);
+    ret.extend_from_slice(message);
+    ret.extend(std::iter::repeat_n(n as u8, n));
+    ret
+}
+
+/// The message without its padding, or an error if the padding is not valid
+pub fn pkcs7_unpad(message: &[u8], block_size: usize) -> Result<Vec<u8>, std::io::Error> {
+    let n = message.last().copied().unwrap_or(0) as usize;
+    let valid = message.len().is_multiple_of(block_size)
+        && (1..=block_size.min(message.len())).contains(&n)
+        && message[message.len() - n..]
+            .iter()
+            .all(|&b| b as usize == n);
+    if !valid {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "The padding is not valid",
+        ));
+    }
+    Ok(message[..message.len() - n].to_vec())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BlockError {
+    // the number of bytes of ciphertext
+    Length(usize),
+    Padding,
+    // the length of the IV
+    Iv(usize),
+    BlockSize,
+}
+
+/// Encrypts or decrypts a message block by block, padding it with PKCS#7
+#[derive(Clone)]
+pub struct BlockTransform<I: CryptographicIter, C: BlockCypher> {
+    iter: I,
+    cypher: C,
+    mode: BlockMode,
+    direction: Direction,
+    // the block the next one is chained to in the CBC mode
+    chain: Vec<u8>,
+    // NOTE: a block of ciphertext is only decrypted once the next one is read, so that the padding of the last block
+    // can be removed
+    pending: Option<Vec<u8>>,
+    output: VecDeque<u8>,
+    position: usize,
+    finished: bool,
+    error: Option<BlockError>,
+}
+
+impl<I: CryptographicIter, C: BlockCypher> BlockTransform<I, C> {
+    pub fn new(iter: I, cypher: C, mode: BlockMode, direction: Direction) -> Self {
+        let (chain, error) = match &mode {
+            BlockMode::Ecb => (Vec::new(), None),
+            BlockMode::Cbc { iv } => (
+                iv.clone(),
+                (iv.len() != cypher.block_size()).then_some(BlockError::Iv(iv.len())),
+            ),
+        };
+        // NOTE: the padding stores the number of padding bytes in a single byte
+        let error = if (1..=255).contains(&cypher.block_size()) {
+            error
+        } else {
+            Some(BlockError::BlockSize)
+        };
+        BlockTransform {
+            iter,
+            cypher,
+            mode,
+            direction,
+            chain,
+            pending: None,
+            output: VecDeque::new(),
+            position: 0,
+            finished: false,
+            error,
+        }
+    }
+
+    /// The error that stopped the stream, if the block size was not between 1 and 255 bytes, the IV or the ciphertext
+    /// was not a whole block, or the padding was not valid
+    pub fn error(&self) -> Option<std::io::Error> {
+        let size = self.cypher.block_size();
+        self.error.map(|e| match e {
+            BlockError::Length(len) => std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "The ciphertext is {} bytes long, which is not a positive multiple of the block size of {} bytes",
+                    len, size
+                ),
+            ),
+            BlockError::Padding => std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The padding of the last block is not valid",
+            ),
+            BlockError::Iv(len) => std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "The IV is {} bytes long, but the blocks are {} bytes long",
+                    len, size
+                ),
+            ),
+            BlockError::BlockSize => std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "The block size has to be between 1 and 255 bytes, but it is {} bytes",
+                    size
+                ),
+            ),
+        })
+    }
+
+    fn encrypt(&mut self, mut block: Vec<u8>) {
+        if self.mode != BlockMode::Ecb {
+            block.iter_mut().zip(&self.chain).for_each(|(b, c)| *b ^= c);
+        }
+        self.cypher.encrypt_block(&mut block);
+        self.output.extend(&block);
+        self.chain = block;
+    }
+
+    fn decrypt(&mut self, ciphertext: Vec<u8>) -> Vec<u8> {
+        let mut block = ciphertext.clone();
+        self.cypher.decrypt_block(&mut block);
+        if self.mode != BlockMode::Ecb {
+            block.iter_mut().zip(&self.chain).for_each(|(b, c)| *b ^= c);
+        }
+        self.chain = ciphertext;
+        block
+    }
+
+    fn process_block(&mut self) {
+        let size = self.cypher.block_size();
+        let mut block: Vec<u8> = self.iter.by_ref().take(size).collect();
+        self.position += block.len();
+
+        match self.direction {
+            Direction::Encrypt => {
+                if block.len() < size {
+                    let n = size - block.len();
+                    block.extend(std::iter::repeat_n(n as u8, n));
+                    self.finished = true;
+                }
+                self.encrypt(block);
+            }
+            Direction::Decrypt if block.len() == size => {
+                if let Some(previous) = self.pending.replace(block) {
+                    let plaintext = self.decrypt(previous);
+                    self.output.extend(plaintext);
+                }
+            }
+            Direction::Decrypt if block.is_empty() => {
+                self.finished = true;
+                let Some(last) = self.pending.take() else {
+                    self.error = Some(BlockError::Length(self.position));
+                    return;
+                };
+                let plaintext = self.decrypt(last);
+                match pkcs7_unpad(&plaintext, size) {
+                    Ok(plaintext) => self.output.extend(plaintext),
+                    Err(_) => self.error = Some(BlockError::Padding),
+                }
+            }
+            Direction::Decrypt => self.error = Some(BlockError::Length(self.position)),
+        }
+    }
+}
+
+impl<I: CryptographicIter, C: BlockCypher> Iterator for BlockTransform<I, C> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(b) = self.output.pop_front() {
+                return Some(b);
+            }
+            if self.finished || self.error.is_some() {
+                return None;
+            }
+            self.process_block();
+        }
+    }
+}
+impl<I: CryptographicIter, C: BlockCypher> CryptographicIter for BlockTransform<I, C> {}
@@ -0,0 +1,380 @@
+use std::path::Path;
+
+use crate::{
+    common::{read_varint, write_varint},
+    random::SplitMix64,
+    traits::{CryptographicIter, Serialisable},
+};
+
+/// A reference to a single byte of the book
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BookToken {
+    /// The offset of the byte from the start of the book
+    Offset(u64),
+    /// An Ottendorf reference. Everything is counted from 1, and words are separated by whitespace.
+    Ottendorf {
+        page: u64,
+        line: u64,
+        word: u64,
+        letter: u64,
+    },
+    /// The gap between two words, which Ottendorf references can not point to. It stands for any whitespace of the
+    /// message, and is looked up as a single space.
+    WordBoundary,
+}
+
+impl std::fmt::Display for BookToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BookToken::Offset(offset) => write!(f, "{}", offset),
+            BookToken::Ottendorf {
+                page,
+                line,
+                word,
+                letter,
+            } => write!(f, "{}.{}.{}.{}", page, line, word, letter),
+            BookToken::WordBoundary => write!(f, "/"),
+        }
+    }
+}
+
+impl Serialisable for BookToken {
+    type CryptoIter = std::vec::IntoIter<u8>;
+
+    // `[0, varint(offset)]`, `[1, varint(page), varint(line), varint(word), varint(letter)]` or `[2]`
+    fn serialise(&self) -> Self::CryptoIter {
+        let mut ret = Vec::new();
+        match *self {
+            BookToken::Offset(offset) => {
+                ret.push(0);
+                write_varint(offset, &mut ret);
+            }
+            BookToken::Ottendorf {
+                page,
+                line,
+                word,
+                letter,
+            } => {
+                ret.push(1);
+                [page, line, word, letter]
+                    .iter()
+                    .for_each(|&v| write_varint(v, &mut ret));
+            }
+            BookToken::WordBoundary => ret.push(2),
+        }
+        ret.into_iter()
+    }
+
+    fn deserialise<I: Iterator<Item = u8>>(mut b: I) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        match b.next()? {
+            0 => Some(BookToken::Offset(read_varint(&mut b)?)),
+            1 => Some(BookToken::Ottendorf {
+                page: read_varint(&mut b)?,
+                line: read_varint(&mut b)?,
+                word: read_varint(&mut b)?,
+                letter: read_varint(&mut b)?,
+            }),
+            2 => Some(BookToken::WordBoundary),
+            _ => None,
+        }
+    }
+}
+
+/// Reads serialised tokens one after the other, until the bytes run out or a token can not be read
+#[derive(Clone)]
+pub struct TokenReader<I: Iterator<Item = u8>> {
+    bytes: I,
+}
+
+impl<I: Iterator<Item = u8>> TokenReader<I> {
+    #[inline]
+    pub fn new(bytes: I) -> Self {
+        TokenReader { bytes }
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for TokenReader<I> {
+    type Item = BookToken;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        BookToken::deserialise(self.bytes.by_ref())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Referencing {
+    Offset,
+    /// Whitespace in the message is encoded as `BookToken::WordBoundary`, so it comes back as a single space
+    Ottendorf,
+}
+
+/// How to pick a reference when a byte occurs more than once in the book
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Occurrence {
+    /// Always the first one, which makes repeated letters easy to spot
+    First,
+    /// The next one every time, starting over after the last one
+    Cycle,
+    /// A random one, from a seeded generator
+    Random(u64),
+}
+
+/// The reference text, split into pages of a fixed number of lines
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Book {
+    text: Vec<u8>,
+    lines_per_page: usize,
+    // the byte ranges of the words of every line
+    lines: Vec<Vec<(usize, usize)>>,
+    // every offset and Ottendorf reference of every byte value
+    offsets: Vec<Vec<u64>>,
+    references: Vec<Vec<BookToken>>,
+}
+
+impl Book {
+    pub fn new(text: Vec<u8>, lines_per_page: usize) -> Result<Self, std::io::Error> {
+        if lines_per_page == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The pages have to have at least one line",
+            ));
+        }
+
+        let mut offsets = vec![Vec::new(); 256];
+        text.iter()
+            .enumerate()
+            .for_each(|(i, &b)| offsets[b as usize].push(i as u64));
+
+        let mut lines = Vec::new();
+        let mut references = vec![Vec::new(); 256];
+        let mut start = 0;
+        for line in text.split(|&b| b == b'\n') {
+            let mut words = Vec::new();
+            let mut i = 0;
+            while i < line.len() {
+                if line[i].is_ascii_whitespace() {
+                    i += 1;
+                    continue;
+                }
+                let word_start = i;
+                while i < line.len() && !line[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                words.push((start + word_start, start + i));
+            }
+
+            let (page, line_number) = (lines.len() / lines_per_page, lines.len() % lines_per_page);
+            words.iter().enumerate().for_each(|(w, &(from, to))| {
+                (from..to).enumerate().for_each(|(l, i)| {
+                    references[text[i] as usize].push(BookToken::Ottendorf {
+                        page: page as u64 + 1,
+                        line: line_number as u64 + 1,
+                        word: w as u64 + 1,
+                        letter: l as u64 + 1,
+                    })
+                })
+            });
+
+            lines.push(words);
+            start += line.len() + 1;
+        }
+
+        Ok(Book {
+            text,
+            lines_per_page,
+            lines,
+            offsets,
+            references,
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(
+        path: P,
+        lines_per_page: usize,
+    ) -> Result<Self, std::io::Error> {
+        Self::new(std::fs::read(path)?, lines_per_page)
+    }
+
+    #[inline]
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    /// Every reference to the byte, in the order it appears in the book
+    pub fn occurrences(&self, byte: u8, referencing: Referencing) -> Vec<BookToken> {
+        match referencing {
+            Referencing::Offset => self.offsets[byte as usize]
+                .iter()
+                .map(|&o| BookToken::Offset(o))
+                .collect(),
+            Referencing::Ottendorf => self.references[byte as usize].clone(),
+        }
+    }
+
+    #[inline]
+    fn occurrence_count(&self, byte: u8, referencing: Referencing) -> usize {
+        match referencing {
+            Referencing::Offset => self.offsets[byte as usize].len(),
+            Referencing::Ottendorf => self.references[byte as usize].len(),
+        }
+    }
+
+    #[inline]
+    fn occurrence(&self, byte: u8, referencing: Referencing, i: usize) -> BookToken {
+        match referencing {
+            Referencing::Offset => BookToken::Offset(self.offsets[byte as usize][i]),
+            Referencing::Ottendorf => self.references[byte as usize][i],
+        }
+    }
+
+    /// The byte a reference points to, if it is inside of the book
+    pub fn lookup(&self, token: BookToken) -> Option<u8> {
+        match token {
+            BookToken::Offset(offset) => self.text.get(usize::try_from(offset).ok()?).copied(),
+            BookToken::Ottendorf {
+                page,
+                line,
+                word,
+                letter,
+            } => {
+                let index = |v: u64| usize::try_from(v.checked_sub(1)?).ok();
+                let (page, line, word, letter) =
+                    (index(page)?, index(line)?, index(word)?, index(letter)?);
+                if line >= self.lines_per_page {
+                    return None;
+                }
+
+                let &(from, to) = self
+                    .lines
+                    .get(page.checked_mul(self.lines_per_page)?.checked_add(line)?)?
+                    .get(word)?;
+                (letter < to - from).then(|| self.text[from + letter])
+            }
+            BookToken::WordBoundary => Some(b' '),
+        }
+    }
+}
+
+/// Replaces every byte of the message with a reference to one of its occurrences in the book
+#[derive(Clone)]
+pub struct BookEncoder<'a, I: CryptographicIter> {
+    iter: I,
+    book: &'a Book,
+    referencing: Referencing,
+    occurrence: Occurrence,
+    // the next occurrence of every byte value to use when cycling
+    next: Vec<usize>,
+    rng: SplitMix64,
+    error: Option<u8>,
+}
+
+impl<'a, I: CryptographicIter> BookEncoder<'a, I> {
+    pub fn new(iter: I, book: &'a Book, referencing: Referencing, occurrence: Occurrence) -> Self {
+        let seed = match occurrence {
+            Occurrence::Random(seed) => seed,
+            _ => 0,
+        };
+
+        BookEncoder {
+            iter,
+            book,
+            referencing,
+            occurrence,
+            next: vec![0; 256],
+            rng: SplitMix64::new(seed),
+            error: None,
+        }
+    }
+
+    /// The error that stopped the stream, if a byte of the message does not occur in the book
+    pub fn error(&self) -> Option<std::io::Error> {
+        self.error.map(|b| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{:?} does not occur in the book", b as char),
+            )
+        })
+    }
+}
+
+impl<I: CryptographicIter> Iterator for BookEncoder<'_, I> {
+    type Item = BookToken;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        let b = self.iter.next()?;
+        if self.referencing == Referencing::Ottendorf && b.is_ascii_whitespace() {
+            return Some(BookToken::WordBoundary);
+        }
+        let count = self.book.occurrence_count(b, self.referencing);
+        if count == 0 {
+            self.error = Some(b);
+            return None;
+        }
+
+        let i = match self.occurrence {
+            Occurrence::First => 0,
+            Occurrence::Cycle => {
+                let i = self.next[b as usize] % count;
+                self.next[b as usize] = i + 1;
+                i
+            }
+            Occurrence::Random(_) => self.rng.below(count),
+        };
+        Some(self.book.occurrence(b, self.referencing, i))
+    }
+}
+
+/// Looks every reference up in the book
+#[derive(Clone)]
+pub struct BookDecoder<'a, T: Iterator<Item = BookToken>> {
+    tokens: T,
+    book: &'a Book,
+    error: Option<BookToken>,
+}
+
+impl<'a, T: Iterator<Item = BookToken>> BookDecoder<'a, T> {
+    #[inline]
+    pub fn new(tokens: T, book: &'a Book) -> Self {
+        BookDecoder {
+            tokens,
+            book,
+            error: None,
+        }
+    }
+
+    /// The error that stopped the stream, if a reference points outside of the book
+    pub fn error(&self) -> Option<std::io::Error> {
+        self.error.map(|t| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} is not in the book", t),
+            )
+        })
+    }
+}
+
+impl<T: Iterator<Item = BookToken>> Iterator for BookDecoder<'_, T> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        let token = self.tokens.next()?;
+        let b = self.book.lookup(token);
+        if b.is_none() {
+            self.error = Some(token);
+        }
+        b
+    }
+}
+impl<T: Iterator<Item = BookToken>> CryptographicIter for BookDecoder<'_, T> {}
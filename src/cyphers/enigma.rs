@@ -0,0 +1,319 @@
+use crate::{
+    cyphers::alphabet::{Alphabet, AlphabetTransform, SymbolCypher},
+    traits::{CryptographicIter, InspectableState},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rotor {
+    I,
+    II,
+    III,
+    IV,
+    V,
+    VI,
+    VII,
+    VIII,
+    /// Only fits as the fourth, non-stepping rotor of the M4
+    Beta,
+    /// Only fits as the fourth, non-stepping rotor of the M4
+    Gamma,
+}
+
+impl Rotor {
+    fn wiring(self) -> &'static [u8; 26] {
+        match self {
+            Rotor::I => b"EKMFLGDQVZNTOWYHXUSPAIBRCJ",
+            Rotor::II => b"AJDKSIRUXBLHWTMCQGZNPYFVOE",
+            Rotor::III => b"BDFHJLCPRTXVZNYEIWGAKMUSQO",
+            Rotor::IV => b"ESOVPZJAYQUIRHXLNFTGKDCMWB",
+            Rotor::V => b"VZBRGITYUPSDNHLXAWMJQOFECK",
+            Rotor::VI => b"JPGVOUMFYQBENHZRDKASXLICTW",
+            Rotor::VII => b"NZJHGRCXMYSWBOUFAIVLPEKQDT",
+            Rotor::VIII => b"FKQHTLXOCBJSPDZRAMEWNIUYGV",
+            Rotor::Beta => b"LEYJVCNIXWPBQMDRTAKZGFUHOS",
+            Rotor::Gamma => b"FSOKANUERHMBTIYCWLQPZXVGJD",
+        }
+    }
+
+    /// The positions at which the rotor turns over the one to its left on the next key press
+    fn notches(self) -> &'static [u8] {
+        match self {
+            Rotor::I => b"Q",
+            Rotor::II => b"E",
+            Rotor::III => b"V",
+            Rotor::IV => b"J",
+            Rotor::V => b"Z",
+            Rotor::VI | Rotor::VII | Rotor::VIII => b"ZM",
+            Rotor::Beta | Rotor::Gamma => b"",
+        }
+    }
+
+    #[inline]
+    fn is_greek(self) -> bool {
+        matches!(self, Rotor::Beta | Rotor::Gamma)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reflector {
+    B,
+    C,
+    /// Only fits the M4, next to the fourth rotor
+    BThin,
+    /// Only fits the M4, next to the fourth rotor
+    CThin,
+}
+
+impl Reflector {
+    fn wiring(self) -> &'static [u8; 26] {
+        match self {
+            Reflector::B => b"YRUHQSLDPXNGOKMIEBFZCWVJAT",
+            Reflector::C => b"FVPJIAOYEDRZXWGCTKUQSBNMHL",
+            Reflector::BThin => b"ENKQAUYWJICOPBLMDXZVFTHRGS",
+            Reflector::CThin => b"RDOBJNTKVEHMLFCWZAXGYIPSUQ",
+        }
+    }
+
+    #[inline]
+    fn is_thin(self) -> bool {
+        matches!(self, Reflector::BThin | Reflector::CThin)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct RotorSlot {
+    rotor: Rotor,
+    ring: u8,
+    position: u8,
+    forward: [u8; 26],
+    backward: [u8; 26],
+}
+
+impl RotorSlot {
+    fn new(rotor: Rotor, ring: u8, position: u8) -> Self {
+        let mut forward = [0; 26];
+        let mut backward = [0; 26];
+        rotor.wiring().iter().enumerate().for_each(|(i, &c)| {
+            forward[i] = c - b'A';
+            backward[(c - b'A') as usize] = i as u8;
+        });
+
+        RotorSlot {
+            rotor,
+            ring,
+            position,
+            forward,
+            backward,
+        }
+    }
+
+    #[inline]
+    fn at_notch(&self) -> bool {
+        self.rotor.notches().contains(&(self.position + b'A'))
+    }
+
+    #[inline]
+    fn step(&mut self) {
+        self.position = (self.position + 1) % 26;
+    }
+
+    fn pass(&self, c: u8, wiring: &[u8; 26]) -> u8 {
+        let shift = (26 + self.position - self.ring) % 26;
+        (wiring[((c + shift) % 26) as usize] + 26 - shift) % 26
+    }
+}
+
+/// An Enigma M3 or M4 machine. Encrypting and decrypting are the same operation from the same starting positions.
+///
+/// Rings and positions are written as letters, with `A` for the ring setting 01.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Enigma {
+    reflector: Reflector,
+    // the non-stepping fourth rotor of the M4
+    greek: Option<RotorSlot>,
+    // from left to right
+    rotors: [RotorSlot; 3],
+    plugboard: [u8; 26],
+}
+
+fn letters(s: &str, count: usize, what: &str) -> Result<Vec<u8>, std::io::Error> {
+    let letters: Vec<u8> = s
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .map(|b| b.to_ascii_uppercase())
+        .collect();
+    if letters.len() != count || !letters.iter().all(u8::is_ascii_uppercase) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("The {} have to be {} letters", what, count),
+        ));
+    }
+    Ok(letters.into_iter().map(|b| b - b'A').collect())
+}
+
+impl Enigma {
+    /// `rotors` are from left to right, and `plugboard` lists the swapped pairs of letters, e.g. `"AV BS CG"`
+    pub fn m3(
+        reflector: Reflector,
+        rotors: [Rotor; 3],
+        rings: &str,
+        positions: &str,
+        plugboard: &str,
+    ) -> Result<Self, std::io::Error> {
+        if reflector.is_thin() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The thin reflectors only fit the M4",
+            ));
+        }
+        Self::build(reflector, None, rotors, rings, positions, plugboard)
+    }
+
+    /// `greek` is the fourth rotor, to the left of the three stepping `rotors`
+    pub fn m4(
+        reflector: Reflector,
+        greek: Rotor,
+        rotors: [Rotor; 3],
+        rings: &str,
+        positions: &str,
+        plugboard: &str,
+    ) -> Result<Self, std::io::Error> {
+        if !reflector.is_thin() || !greek.is_greek() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The M4 takes a thin reflector and a Beta or Gamma fourth rotor",
+            ));
+        }
+        Self::build(reflector, Some(greek), rotors, rings, positions, plugboard)
+    }
+
+    fn build(
+        reflector: Reflector,
+        greek: Option<Rotor>,
+        rotors: [Rotor; 3],
+        rings: &str,
+        positions: &str,
+        plugboard: &str,
+    ) -> Result<Self, std::io::Error> {
+        let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg);
+
+        if rotors.iter().any(|r| r.is_greek()) {
+            return Err(invalid("Beta and Gamma can only be the fourth rotor"));
+        }
+        if rotors[0] == rotors[1] || rotors[1] == rotors[2] || rotors[0] == rotors[2] {
+            return Err(invalid("Every rotor can only be used once"));
+        }
+
+        let count = 3 + greek.is_some() as usize;
+        let rings = letters(rings, count, "rings")?;
+        let positions = letters(positions, count, "positions")?;
+
+        let mut board: [u8; 26] = std::array::from_fn(|i| i as u8);
+        for plug in plugboard.split_whitespace() {
+            let pair = letters(plug, 2, "plugs")?;
+            let (a, b) = (pair[0] as usize, pair[1] as usize);
+            if a == b || board[a] != a as u8 || board[b] != b as u8 {
+                return Err(invalid("Every letter can only be plugged once"));
+            }
+            board.swap(a, b);
+        }
+
+        let offset = count - 3;
+        Ok(Enigma {
+            reflector,
+            greek: greek.map(|g| RotorSlot::new(g, rings[0], positions[0])),
+            rotors: std::array::from_fn(|i| {
+                RotorSlot::new(rotors[i], rings[i + offset], positions[i + offset])
+            }),
+            plugboard: board,
+        })
+    }
+
+    /// The letters showing in the windows, from left to right
+    pub fn positions(&self) -> String {
+        self.greek
+            .iter()
+            .chain(self.rotors.iter())
+            .map(|r| (r.position + b'A') as char)
+            .collect()
+    }
+
+    /// Moves the rotors like a key press would, including the double step of the middle rotor
+    pub fn step(&mut self) {
+        let [left, middle, right] = &mut self.rotors;
+        if middle.at_notch() {
+            middle.step();
+            left.step();
+        } else if right.at_notch() {
+            middle.step();
+        }
+        right.step();
+    }
+
+    /// The letter that lights up for `letter`, both from 0 to 25, without moving the rotors
+    pub fn encode(&self, letter: u8) -> u8 {
+        let mut c = self.plugboard[letter as usize];
+        for r in self.rotors.iter().rev().chain(self.greek.iter()) {
+            c = r.pass(c, &r.forward);
+        }
+        c = self.reflector.wiring()[c as usize] - b'A';
+        for r in self.greek.iter().chain(self.rotors.iter()) {
+            c = r.pass(c, &r.backward);
+        }
+        self.plugboard[c as usize]
+    }
+
+    /// Works over the letters, leaving the case and everything else as it is
+    pub fn transform<I: CryptographicIter>(self, iter: I) -> EnigmaTransform<I> {
+        AlphabetTransform::new(iter, &Alphabet::letters(), self)
+    }
+}
+
+impl SymbolCypher for Enigma {
+    fn apply(&mut self, index: usize, alphabet: &Alphabet) -> Option<usize> {
+        // NOTE: the machine only has 26 keys
+        if alphabet.len() != 26 {
+            return None;
+        }
+        self.step();
+        Some(self.encode(index as u8) as usize)
+    }
+}
+
+impl InspectableState for Enigma {
+    fn inspect_state(&self) -> String {
+        let rotors: Vec<String> = self
+            .greek
+            .iter()
+            .chain(self.rotors.iter())
+            .map(|r| format!("{:?}", r.rotor))
+            .collect();
+        let rings: String = self
+            .greek
+            .iter()
+            .chain(self.rotors.iter())
+            .map(|r| (r.ring + b'A') as char)
+            .collect();
+        let plugs: Vec<String> = (0..26u8)
+            .filter(|&i| self.plugboard[i as usize] > i)
+            .map(|i| {
+                format!(
+                    "{}{}",
+                    (i + b'A') as char,
+                    (self.plugboard[i as usize] + b'A') as char
+                )
+            })
+            .collect();
+
+        format!(
+            "Reflector {:?}, rotors {}, rings {}, positions {}, plugboard {}",
+            self.reflector,
+            rotors.join(" "),
+            rings,
+            self.positions(),
+            plugs.join(" ")
+        )
+    }
+}
+
+pub type EnigmaTransform<I> = AlphabetTransform<I, Enigma>;
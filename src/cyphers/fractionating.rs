@@ -0,0 +1,405 @@
+use std::collections::VecDeque;
+
+use crate::{
+    cyphers::{
+        alphabet::{Alphabet, Direction, OutOfAlphabet, SymbolReader},
+        polybius::{PolybiusSquare, PolybiusTransform},
+        polygraphic::{KeySquare, PolygraphicCypher},
+        transposition::{ColumnarKey, TranspositionTransform},
+    },
+    traits::CryptographicIter,
+};
+
+/// A Polybius square followed by a columnar transposition of the labels
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Adfgvx {
+    square: PolybiusSquare,
+    key: ColumnarKey,
+}
+
+pub type AdfgvxTransform<I> = TranspositionTransform<PolybiusTransform<I>, ColumnarKey>;
+pub type AdfgvxDecryptTransform<I> = PolybiusTransform<TranspositionTransform<I, ColumnarKey>>;
+
+impl Adfgvx {
+    #[inline]
+    pub fn new(square: PolybiusSquare, key: ColumnarKey) -> Self {
+        Adfgvx { square, key }
+    }
+
+    /// Everything outside of the alphabet of the square is dropped, as only the labels are transposed
+    pub fn encrypt<I: CryptographicIter>(&self, iter: I) -> AdfgvxTransform<I> {
+        let labels = self
+            .square
+            .labels()
+            .clone()
+            .with_policy(OutOfAlphabet::Drop);
+        TranspositionTransform::new(
+            PolybiusTransform::new(iter, self.square.clone(), Direction::Encrypt)
+                .with_policy(OutOfAlphabet::Drop),
+            self.key.clone(),
+            Direction::Encrypt,
+        )
+        .in_alphabet(&labels)
+    }
+
+    pub fn decrypt<I: CryptographicIter>(&self, iter: I) -> AdfgvxDecryptTransform<I> {
+        let labels = self
+            .square
+            .labels()
+            .clone()
+            .with_policy(OutOfAlphabet::Drop);
+        PolybiusTransform::new(
+            TranspositionTransform::new(iter, self.key.clone(), Direction::Decrypt)
+                .in_alphabet(&labels),
+            self.square.clone(),
+            Direction::Decrypt,
+        )
+    }
+}
+
+// Writes down the coordinates of every symbol of the gram in `depth` rows, one per coordinate, and reads them
+// back out row by row as the coordinates of the new symbols. Decryption does the opposite.
+fn fractionate(
+    gram: &mut [usize],
+    depth: usize,
+    direction: Direction,
+    coordinates: impl Fn(usize) -> Vec<usize>,
+    symbol: impl Fn(&[usize]) -> usize,
+) {
+    let n = gram.len();
+    let coords: Vec<Vec<usize>> = gram.iter().map(|&s| coordinates(s)).collect();
+
+    match direction {
+        Direction::Encrypt => {
+            let by_row: Vec<usize> = (0..depth)
+                .flat_map(|d| coords.iter().map(move |c| c[d]))
+                .collect();
+            by_row
+                .chunks(depth)
+                .zip(gram.iter_mut())
+                .for_each(|(c, s)| *s = symbol(c));
+        }
+        Direction::Decrypt => {
+            let by_symbol: Vec<usize> = coords.into_iter().flatten().collect();
+            for (i, s) in gram.iter_mut().enumerate() {
+                let c: Vec<usize> = (0..depth).map(|d| by_symbol[d * n + i]).collect();
+                *s = symbol(&c);
+            }
+        }
+    }
+}
+
+fn check_period(period: Option<usize>) -> Result<(), std::io::Error> {
+    if period == Some(0) {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The period has to be at least 1",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Writes the rows and then the columns of a group of `period` symbols in a key square, and reads them back in pairs.
+/// Without a period, the whole message is one group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bifid {
+    square: KeySquare,
+    period: Option<usize>,
+}
+
+impl Bifid {
+    pub fn new(square: KeySquare, period: Option<usize>) -> Result<Self, std::io::Error> {
+        check_period(period)?;
+        Ok(Bifid { square, period })
+    }
+
+    /// A 5x5 square with `J` written as `I`
+    pub fn latin(keyword: &str, period: Option<usize>) -> Result<Self, std::io::Error> {
+        Self::new(KeySquare::latin(keyword), period)
+    }
+
+    fn apply(&self, gram: &mut [usize], direction: Direction) {
+        let sq = &self.square;
+        fractionate(
+            gram,
+            2,
+            direction,
+            // SAFETY: the transform only passes in symbols of the alphabet, and every one of them has a cell
+            |s| {
+                let (r, c) = sq.position(s).unwrap();
+                vec![r, c]
+            },
+            |c| sq.at(c[0], c[1]),
+        );
+    }
+}
+
+impl PolygraphicCypher for Bifid {
+    #[inline]
+    fn alphabet(&self) -> &Alphabet {
+        self.square.alphabet()
+    }
+
+    #[inline]
+    fn gram_len(&self) -> usize {
+        self.period.unwrap_or(usize::MAX)
+    }
+
+    #[inline]
+    fn filler(&self, _previous: Option<usize>) -> Option<usize> {
+        None
+    }
+
+    fn encrypt(&self, gram: &mut [usize]) {
+        self.apply(gram, Direction::Encrypt);
+    }
+
+    fn decrypt(&self, gram: &mut [usize]) {
+        self.apply(gram, Direction::Decrypt);
+    }
+}
+
+/// Like Bifid, but with the 27 symbols of the alphabet in a 3x3x3 cube, i.e. three coordinates per symbol
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trifid {
+    // the three layers of the cube, one under the other
+    cube: KeySquare,
+    period: Option<usize>,
+}
+
+impl Trifid {
+    pub fn new(
+        alphabet: &Alphabet,
+        keyword: &str,
+        period: Option<usize>,
+    ) -> Result<Self, std::io::Error> {
+        if alphabet.len() != 27 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The alphabet has to have 27 symbols to fill the cube",
+            ));
+        }
+        check_period(period)?;
+        Ok(Trifid {
+            cube: KeySquare::new(alphabet, 9, 3, keyword, &[])?,
+            period,
+        })
+    }
+
+    /// The letters followed by `+`
+    pub fn latin(keyword: &str, period: Option<usize>) -> Result<Self, std::io::Error> {
+        // SAFETY: the letters and `+` are 27 distinct symbols
+        let alphabet = Alphabet::custom("ABCDEFGHIJKLMNOPQRSTUVWXYZ+")
+            .unwrap()
+            .with_case_folding();
+        Self::new(&alphabet, keyword, period)
+    }
+
+    fn apply(&self, gram: &mut [usize], direction: Direction) {
+        let cube = &self.cube;
+        fractionate(
+            gram,
+            3,
+            direction,
+            // SAFETY: the transform only passes in symbols of the alphabet, and every one of them has a cell
+            |s| {
+                let (r, c) = cube.position(s).unwrap();
+                vec![r / 3, r % 3, c]
+            },
+            |c| cube.at(c[0] * 3 + c[1], c[2]),
+        );
+    }
+}
+
+impl PolygraphicCypher for Trifid {
+    #[inline]
+    fn alphabet(&self) -> &Alphabet {
+        self.cube.alphabet()
+    }
+
+    #[inline]
+    fn gram_len(&self) -> usize {
+        self.period.unwrap_or(usize::MAX)
+    }
+
+    #[inline]
+    fn filler(&self, _previous: Option<usize>) -> Option<usize> {
+        None
+    }
+
+    fn encrypt(&self, gram: &mut [usize]) {
+        self.apply(gram, Direction::Encrypt);
+    }
+
+    fn decrypt(&self, gram: &mut [usize]) {
+        self.apply(gram, Direction::Decrypt);
+    }
+}
+
+/// Writes every symbol as the two digit number of its row and column, and adds the number of the next symbol
+/// of the repeating keyword to it. The numbers are separated by spaces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Nihilist {
+    square: KeySquare,
+    key: Vec<usize>,
+}
+
+impl Nihilist {
+    pub fn new(square: KeySquare, keyword: &str) -> Result<Self, std::io::Error> {
+        if square.rows() > 9 || square.cols() > 9 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The rows and columns have to be numbered with single digits",
+            ));
+        }
+
+        let mut bytes = keyword.bytes();
+        let mut reader = SymbolReader::new();
+        let key: Vec<usize> =
+            std::iter::from_fn(|| reader.next_symbol(&mut bytes, square.alphabet()))
+                .map(|s| Self::number(&square, s))
+                .collect();
+        if key.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The keyword has to have at least one symbol of the alphabet",
+            ));
+        }
+
+        Ok(Nihilist { square, key })
+    }
+
+    /// A 5x5 square with `J` written as `I`
+    pub fn latin(square_keyword: &str, keyword: &str) -> Result<Self, std::io::Error> {
+        Self::new(KeySquare::latin(square_keyword), keyword)
+    }
+
+    fn number(square: &KeySquare, symbol: usize) -> usize {
+        // SAFETY: every symbol of the alphabet has a cell
+        let (r, c) = square.position(symbol).unwrap();
+        (r + 1) * 10 + c + 1
+    }
+
+    fn symbol(&self, number: usize) -> Option<usize> {
+        let (r, c) = (number / 10, number % 10);
+        ((1..=self.square.rows()).contains(&r) && (1..=self.square.cols()).contains(&c))
+            .then(|| self.square.at(r - 1, c - 1))
+    }
+}
+
+/// Everything outside of the alphabet is dropped when encrypting. When decrypting, anything but digits and
+/// whitespace stops the stream with an error.
+#[derive(Clone)]
+pub struct NihilistTransform<I: CryptographicIter> {
+    iter: I,
+    cypher: Nihilist,
+    direction: Direction,
+    reader: SymbolReader,
+    position: usize,
+    output: VecDeque<u8>,
+    error: Option<Vec<u8>>,
+}
+
+impl<I: CryptographicIter> NihilistTransform<I> {
+    #[inline]
+    pub fn new(iter: I, cypher: Nihilist, direction: Direction) -> Self {
+        NihilistTransform {
+            iter,
+            cypher,
+            direction,
+            reader: SymbolReader::new(),
+            position: 0,
+            output: VecDeque::new(),
+            error: None,
+        }
+    }
+
+    /// The error that stopped the stream, if the cyphertext was not made of valid numbers
+    pub fn error(&self) -> Option<std::io::Error> {
+        self.error.as_ref().map(|c| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{:?} is not a valid number",
+                    String::from_utf8_lossy(c).as_ref()
+                ),
+            )
+        })
+    }
+
+    #[inline]
+    fn next_key(&mut self) -> usize {
+        let k = self.cypher.key[self.position % self.cypher.key.len()];
+        self.position += 1;
+        k
+    }
+
+    fn encrypt_next(&mut self) -> Option<()> {
+        let alphabet = self.cypher.square.alphabet();
+        let symbol = self.reader.next_symbol(&mut self.iter, alphabet)?;
+        let number = Nihilist::number(&self.cypher.square, symbol) + self.next_key();
+        if self.position > 1 {
+            self.output.push_back(b' ');
+        }
+        self.output.extend(number.to_string().bytes());
+        Some(())
+    }
+
+    fn decrypt_next(&mut self) -> Option<()> {
+        let mut digits = Vec::new();
+        loop {
+            match self.iter.next() {
+                Some(b) if b.is_ascii_digit() => digits.push(b),
+                Some(b) if b.is_ascii_whitespace() => {
+                    if !digits.is_empty() {
+                        break;
+                    }
+                }
+                Some(b) => {
+                    digits.push(b);
+                    self.error = Some(digits);
+                    return None;
+                }
+                None if digits.is_empty() => return None,
+                None => break,
+            }
+        }
+
+        let symbol = String::from_utf8_lossy(&digits)
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(self.next_key()))
+            .and_then(|n| self.cypher.symbol(n));
+        match symbol {
+            Some(s) => {
+                let mut out = Vec::new();
+                self.cypher
+                    .square
+                    .alphabet()
+                    .write_symbol(s, false, &mut out);
+                self.output.extend(out);
+                Some(())
+            }
+            None => {
+                self.error = Some(digits);
+                None
+            }
+        }
+    }
+}
+
+impl<I: CryptographicIter> Iterator for NihilistTransform<I> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.output.is_empty() && self.error.is_none() {
+            match self.direction {
+                Direction::Encrypt => self.encrypt_next(),
+                Direction::Decrypt => self.decrypt_next(),
+            };
+        }
+        self.output.pop_front()
+    }
+}
+impl<I: CryptographicIter> CryptographicIter for NihilistTransform<I> {}
@@ -1 +1,13 @@
+pub mod alphabet;
+pub mod bitwise;
+pub mod block;
+pub mod book;
+pub mod enigma;
+pub mod fractionating;
+pub mod one_time_pad;
+pub mod polyalphabetic;
+pub mod polybius;
+pub mod polygraphic;
 pub mod simple;
+pub mod substitution;
+pub mod transposition;
@@ -0,0 +1,363 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    common::{read_varint, write_varint},
+    traits::{CryptographicIter, Serialisable},
+};
+
+/// A ciphertext along with the offset of the pad material it was encrypted with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PadMessage {
+    offset: u64,
+    ciphertext: Vec<u8>,
+}
+
+impl PadMessage {
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    #[inline]
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext
+    }
+
+    #[inline]
+    fn range(&self) -> Range<u64> {
+        self.offset..self.offset + self.ciphertext.len() as u64
+    }
+}
+
+impl Serialisable for PadMessage {
+    type CryptoIter = std::vec::IntoIter<u8>;
+
+    // `[varint(offset), varint(ciphertext length), ciphertext...]`
+    fn serialise(&self) -> Self::CryptoIter {
+        let mut ret = Vec::with_capacity(self.ciphertext.len() + 20);
+        write_varint(self.offset, &mut ret);
+        write_varint(self.ciphertext.len() as u64, &mut ret);
+        ret.extend_from_slice(&self.ciphertext);
+        ret.into_iter()
+    }
+
+    fn deserialise<I: Iterator<Item = u8>>(mut b: I) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let offset = read_varint(&mut b)?;
+        let len: usize = read_varint(&mut b)?.try_into().ok()?;
+        offset.checked_add(len as u64)?;
+
+        let ciphertext: Vec<u8> = b.take(len).collect();
+        (ciphertext.len() == len).then_some(PadMessage { offset, ciphertext })
+    }
+}
+
+// how long to wait for another handle to let go of the index, and how often to check
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const LOCK_RETRY: Duration = Duration::from_millis(10);
+
+// an exclusive lock on the index, held as long as the lock file exists
+struct IndexLock {
+    path: PathBuf,
+}
+
+impl IndexLock {
+    fn acquire(index_path: &Path) -> Result<Self, std::io::Error> {
+        let mut path = index_path.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+
+        let start = Instant::now();
+        loop {
+            // NOTE: creating a file that must not exist yet is atomic, so only one handle can succeed at a time
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(IndexLock { path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if start.elapsed() >= LOCK_TIMEOUT {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::WouldBlock,
+                            format!(
+                                "The index is still locked after {:?}; if no other process is using the pad, delete {}",
+                                LOCK_TIMEOUT,
+                                path.display()
+                            ),
+                        ));
+                    }
+                    std::thread::sleep(LOCK_RETRY);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for IndexLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A pad file along with an index of the ranges of it that have already been used, kept next to it.
+///
+/// The index is locked and read again before every use of the pad, and every range is marked as used, and the
+/// index saved, before the lock is released and the ciphertext handed out, so the same pad material is never used
+/// twice, even by several handles on the same pad, or across runs.
+#[derive(Debug)]
+pub struct OneTimePad {
+    pad: Vec<u8>,
+    index_path: PathBuf,
+    // sorted and merged
+    used: Vec<Range<u64>>,
+}
+
+impl OneTimePad {
+    /// Keeps the index in `<pad>.used`
+    pub fn open<P: AsRef<Path>>(pad_path: P) -> Result<Self, std::io::Error> {
+        let mut index_path = pad_path.as_ref().as_os_str().to_owned();
+        index_path.push(".used");
+        Self::with_index(pad_path, index_path)
+    }
+
+    /// A missing index is created on the first use of the pad
+    pub fn with_index<P: AsRef<Path>, Q: AsRef<Path>>(
+        pad_path: P,
+        index_path: Q,
+    ) -> Result<Self, std::io::Error> {
+        let pad = std::fs::read(pad_path)?;
+        let index_path = index_path.as_ref().to_path_buf();
+
+        let used = Self::read_index(&index_path, pad.len() as u64)?;
+
+        Ok(OneTimePad {
+            pad,
+            index_path,
+            used,
+        })
+    }
+
+    fn read_index(index_path: &Path, pad_len: u64) -> Result<Vec<Range<u64>>, std::io::Error> {
+        match std::fs::read(index_path) {
+            Ok(index) => Self::parse_index(index, pad_len),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // locks the index, and merges in whatever other handles have marked as used since it was last read
+    fn lock(&mut self) -> Result<IndexLock, std::io::Error> {
+        let lock = IndexLock::acquire(&self.index_path)?;
+        let on_disk = Self::read_index(&self.index_path, self.len())?;
+        self.used = Self::merge(self.used.drain(..).chain(on_disk).collect());
+        Ok(lock)
+    }
+
+    fn merge(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+        ranges.sort_by_key(|r| r.start);
+        let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+        for r in ranges {
+            match merged.last_mut() {
+                Some(last) if last.end >= r.start => last.end = last.end.max(r.end),
+                _ => merged.push(r),
+            }
+        }
+        merged
+    }
+
+    // `[varint(count), (varint(start), varint(end))...]`
+    fn parse_index(index: Vec<u8>, pad_len: u64) -> Result<Vec<Range<u64>>, std::io::Error> {
+        let invalid = || {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The index of used pad material is corrupted",
+            )
+        };
+
+        let mut b = index.into_iter();
+        let count = read_varint(&mut b).ok_or_else(invalid)?;
+        let mut used: Vec<Range<u64>> = Vec::new();
+        for _ in 0..count {
+            let start = read_varint(&mut b).ok_or_else(invalid)?;
+            let end = read_varint(&mut b).ok_or_else(invalid)?;
+            // the ranges are saved sorted, without overlaps, and inside of the pad
+            if start >= end || end > pad_len || used.last().is_some_and(|r| r.end >= start) {
+                return Err(invalid());
+            }
+            used.push(start..end);
+        }
+        if b.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(used)
+    }
+
+    fn save_index(&self) -> Result<(), std::io::Error> {
+        let mut index = Vec::new();
+        write_varint(self.used.len() as u64, &mut index);
+        self.used.iter().for_each(|r| {
+            write_varint(r.start, &mut index);
+            write_varint(r.end, &mut index);
+        });
+
+        // NOTE: written to a temporary file first, so that a failed write can not lose the index
+        let mut tmp_path = self.index_path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        std::fs::write(&tmp_path, index)?;
+        std::fs::rename(tmp_path, &self.index_path)
+    }
+
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.pad.len() as u64
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pad.is_empty()
+    }
+
+    #[inline]
+    pub fn used_ranges(&self) -> &[Range<u64>] {
+        &self.used
+    }
+
+    /// The number of bytes of the pad that have not been used yet
+    pub fn remaining(&self) -> u64 {
+        self.len() - self.used.iter().map(|r| r.end - r.start).sum::<u64>()
+    }
+
+    /// Whether any of the range is used, as of the last time this handle read the index
+    #[inline]
+    pub fn is_used(&self, range: &Range<u64>) -> bool {
+        self.used
+            .iter()
+            .any(|r| r.start < range.end && range.start < r.end)
+    }
+
+    // NOTE: the index has to be locked by the caller
+    fn mark_used(&mut self, range: Range<u64>) -> Result<(), std::io::Error> {
+        if range.is_empty() {
+            return Ok(());
+        }
+
+        let previous = self.used.clone();
+        self.used.push(range);
+        self.used = Self::merge(std::mem::take(&mut self.used));
+
+        self.save_index().inspect_err(|_| self.used = previous)
+    }
+
+    // the start of the first gap between the used ranges that fits `len` bytes
+    fn find_unused(&self, len: u64) -> Option<u64> {
+        let mut start = 0;
+        for r in &self.used {
+            if r.start - start >= len {
+                return Some(start);
+            }
+            start = r.end;
+        }
+        (self.len() - start >= len).then_some(start)
+    }
+
+    /// Encrypts the message with the first unused run of pad material that is long enough for it
+    pub fn encrypt<I: CryptographicIter>(
+        &mut self,
+        message: I,
+    ) -> Result<PadMessage, std::io::Error> {
+        let message: Vec<u8> = message.collect();
+        let len = message.len() as u64;
+
+        let _lock = self.lock()?;
+        let offset = self.find_unused(len).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!(
+                    "There is no unused run of {} bytes left in the pad, out of {} unused bytes",
+                    len,
+                    self.remaining()
+                ),
+            )
+        })?;
+
+        self.encrypt_locked(offset, message)
+    }
+
+    /// Encrypts the message with the pad material starting at `offset`, which must not have been used yet
+    pub fn encrypt_at<I: CryptographicIter>(
+        &mut self,
+        offset: u64,
+        message: I,
+    ) -> Result<PadMessage, std::io::Error> {
+        let _lock = self.lock()?;
+        self.encrypt_locked(offset, message.collect())
+    }
+
+    fn encrypt_locked(
+        &mut self,
+        offset: u64,
+        message: Vec<u8>,
+    ) -> Result<PadMessage, std::io::Error> {
+        let range = offset..offset.saturating_add(message.len() as u64);
+        if range.end > self.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!(
+                    "The message needs the pad up to byte {}, but it is only {} bytes long",
+                    range.end,
+                    self.len()
+                ),
+            ));
+        }
+        if self.is_used(&range) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!(
+                    "The pad material from byte {} to {} has already been used",
+                    range.start, range.end
+                ),
+            ));
+        }
+        self.mark_used(range.clone())?;
+
+        let key = self.pad[range.start as usize..range.end as usize].to_vec();
+        let mut transform = message.into_iter().xor(key.into_iter());
+        let ciphertext = transform.by_ref().collect();
+        // NOTE: the key is exactly as long as the message
+        debug_assert!(transform.error().is_none());
+
+        Ok(PadMessage { offset, ciphertext })
+    }
+
+    /// Marks the pad material of the message as used too, so that it is not used again for sending
+    pub fn decrypt(&mut self, message: &PadMessage) -> Result<Vec<u8>, std::io::Error> {
+        let range = message.range();
+        if range.end > self.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "The message needs the pad up to byte {}, but it is only {} bytes long",
+                    range.end,
+                    self.len()
+                ),
+            ));
+        }
+        let lock = self.lock()?;
+        self.mark_used(range.clone())?;
+        drop(lock);
+
+        let key = self.pad[range.start as usize..range.end as usize].to_vec();
+        let mut transform = message.ciphertext.clone().into_iter().xor(key.into_iter());
+        let plaintext = transform.by_ref().collect();
+        debug_assert!(transform.error().is_none());
+        Ok(plaintext)
+    }
+}
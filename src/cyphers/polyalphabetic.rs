@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+
+use crate::{
+    cyphers::alphabet::{Alphabet, AlphabetTransform, Direction, SymbolCypher, SymbolReader},
+    traits::CryptographicIter,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolyalphabeticVariant {
+    /// `c = p + k`
+    Vigenere,
+    /// `c = k - p`, which is its own inverse
+    Beaufort,
+    /// `c = p - k`, i.e. Vigenère decryption used for encryption
+    VariantBeaufort,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyMode {
+    /// The key is repeated once it runs out. A key at least as long as the message makes it a running-key cypher.
+    Repeating,
+    /// The key is followed by the plaintext itself
+    Autokey,
+}
+
+/// Works over all the bytes by default; see `AlphabetTransform::in_alphabet` and `AlphabetTransform::letters_only`
+pub type PolyalphabeticTransform<I, K> = AlphabetTransform<I, PolyalphabeticCypher<K>>;
+
+#[derive(Clone)]
+pub struct PolyalphabeticCypher<K: CryptographicIter> {
+    key: K,
+    key_reader: SymbolReader,
+    // the key symbols read so far, for repeating the key, or the plaintext symbols waiting to be used as the key
+    key_buffer: VecDeque<usize>,
+    key_exhausted: bool,
+    // whether the key ran out before there was anything to encrypt with
+    key_missing: bool,
+    variant: PolyalphabeticVariant,
+    direction: Direction,
+    key_mode: KeyMode,
+}
+
+impl<K: CryptographicIter> PolyalphabeticCypher<K> {
+    #[inline]
+    pub fn new(
+        key: K,
+        variant: PolyalphabeticVariant,
+        direction: Direction,
+        key_mode: KeyMode,
+    ) -> Self {
+        PolyalphabeticCypher {
+            key,
+            key_reader: SymbolReader::new(),
+            key_buffer: VecDeque::new(),
+            key_exhausted: false,
+            key_missing: false,
+            variant,
+            direction,
+            key_mode,
+        }
+    }
+
+    pub fn transform<I: CryptographicIter>(self, iter: I) -> PolyalphabeticTransform<I, K> {
+        AlphabetTransform::over_bytes(iter, self)
+    }
+
+    // NOTE: characters of the key that are not in the alphabet are skipped
+    fn next_key(&mut self, alphabet: &Alphabet) -> Option<usize> {
+        if !self.key_exhausted {
+            match self.key_reader.next_symbol(&mut self.key, alphabet) {
+                Some(k) => {
+                    if self.key_mode == KeyMode::Repeating {
+                        self.key_buffer.push_back(k);
+                    }
+                    return Some(k);
+                }
+                None => self.key_exhausted = true,
+            }
+        }
+
+        // repeating keys are rotated, while the plaintext used by autokey is consumed
+        let k = self.key_buffer.pop_front()?;
+        if self.key_mode == KeyMode::Repeating {
+            self.key_buffer.push_back(k);
+        }
+        Some(k)
+    }
+}
+
+impl<K: CryptographicIter> SymbolCypher for PolyalphabeticCypher<K> {
+    fn apply(&mut self, v: usize, alphabet: &Alphabet) -> Option<usize> {
+        // NOTE: there is nothing to encrypt with if the key has no symbols of the alphabet
+        let Some(k) = self.next_key(alphabet) else {
+            self.key_missing = true;
+            return None;
+        };
+
+        let n = alphabet.len();
+        let add = |a: usize, b: usize| (a + b) % n;
+        let sub = |a: usize, b: usize| (a + n - b) % n;
+
+        let (ret, plaintext) = match (self.variant, self.direction) {
+            (PolyalphabeticVariant::Vigenere, Direction::Encrypt) => (add(v, k), v),
+            (PolyalphabeticVariant::Vigenere, Direction::Decrypt) => {
+                let p = sub(v, k);
+                (p, p)
+            }
+            (PolyalphabeticVariant::Beaufort, Direction::Encrypt) => (sub(k, v), v),
+            (PolyalphabeticVariant::Beaufort, Direction::Decrypt) => {
+                let p = sub(k, v);
+                (p, p)
+            }
+            (PolyalphabeticVariant::VariantBeaufort, Direction::Encrypt) => (sub(v, k), v),
+            (PolyalphabeticVariant::VariantBeaufort, Direction::Decrypt) => {
+                let p = add(v, k);
+                (p, p)
+            }
+        };
+
+        if self.key_mode == KeyMode::Autokey {
+            self.key_buffer.push_back(plaintext);
+        }
+
+        Some(ret)
+    }
+
+    fn error(&self) -> Option<std::io::Error> {
+        self.key_missing.then(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The key does not contain any symbol of the alphabet, so there is nothing to encrypt with",
+            )
+        })
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::VecDeque;
+
+use crate::{
+    cyphers::{
+        alphabet::{Alphabet, Direction, OutOfAlphabet, SymbolReader, Token},
+        polygraphic::KeySquare,
+    },
+    traits::CryptographicIter,
+};
+
+/// A key square with a label for every row and column, so that every symbol can be written as two labels
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolybiusSquare {
+    square: KeySquare,
+    labels: Alphabet,
+}
+
+impl PolybiusSquare {
+    /// The same labels are used for the rows and the columns, so the square has to have one per row
+    pub fn new(square: KeySquare, labels: &Alphabet) -> Result<Self, std::io::Error> {
+        if square.rows() != square.cols() || square.rows() != labels.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The square has to have as many rows and columns as there are labels",
+            ));
+        }
+
+        Ok(PolybiusSquare {
+            square,
+            labels: labels.clone(),
+        })
+    }
+
+    /// A 5x5 square with `J` written as `I`, labelled `1` to `5`
+    pub fn numeric(keyword: &str) -> Self {
+        // SAFETY: there are 5 labels for a 5x5 square
+        Self::new(
+            KeySquare::latin(keyword),
+            &Alphabet::custom("12345").unwrap(),
+        )
+        .unwrap()
+    }
+
+    /// A 5x5 square with `J` written as `I`, labelled `ADFGX`
+    pub fn adfgx(keyword: &str) -> Self {
+        // SAFETY: there are 5 labels for a 5x5 square
+        Self::new(
+            KeySquare::latin(keyword),
+            &Alphabet::custom("ADFGX").unwrap().with_case_folding(),
+        )
+        .unwrap()
+    }
+
+    /// A 6x6 square of the letters and the digits, labelled `ADFGVX`
+    pub fn adfgvx(keyword: &str) -> Self {
+        // SAFETY: the 36 letters and digits fill a 6x6 square, with 6 labels
+        Self::new(
+            KeySquare::new(&Alphabet::alphanumeric(), 6, 6, keyword, &[]).unwrap(),
+            &Alphabet::custom("ADFGVX").unwrap().with_case_folding(),
+        )
+        .unwrap()
+    }
+
+    #[inline]
+    pub fn square(&self) -> &KeySquare {
+        &self.square
+    }
+
+    #[inline]
+    pub fn labels(&self) -> &Alphabet {
+        &self.labels
+    }
+}
+
+/// Replaces every symbol with the labels of its row and column, or every pair of labels with their symbol.
+/// A label left without its pair at the end of the message is dropped.
+#[derive(Clone)]
+pub struct PolybiusTransform<I: CryptographicIter> {
+    iter: I,
+    square: PolybiusSquare,
+    direction: Direction,
+    // the alphabet being read: the symbols of the square when encrypting, the labels when decrypting
+    alphabet: Alphabet,
+    reader: SymbolReader,
+    // the row label waiting for its column when decrypting
+    row: Option<(usize, bool)>,
+    output: VecDeque<u8>,
+    error: Option<Vec<u8>>,
+}
+
+impl<I: CryptographicIter> PolybiusTransform<I> {
+    pub fn new(iter: I, square: PolybiusSquare, direction: Direction) -> Self {
+        let alphabet = match direction {
+            Direction::Encrypt => square.square().alphabet().clone(),
+            Direction::Decrypt => square.labels().clone(),
+        };
+
+        PolybiusTransform {
+            iter,
+            square,
+            direction,
+            alphabet,
+            reader: SymbolReader::new(),
+            row: None,
+            output: VecDeque::new(),
+            error: None,
+        }
+    }
+
+    /// Changes what happens to the characters outside of the alphabet being read
+    #[inline]
+    pub fn with_policy(mut self, policy: OutOfAlphabet) -> Self {
+        self.alphabet = self.alphabet.with_policy(policy);
+        self
+    }
+
+    /// The error that stopped the stream, if a character outside of the alphabet was found with `OutOfAlphabet::Error`
+    pub fn error(&self) -> Option<std::io::Error> {
+        self.error.as_ref().map(|c| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{:?} is not in the alphabet",
+                    String::from_utf8_lossy(c).as_ref()
+                ),
+            )
+        })
+    }
+
+    fn process(&mut self, index: usize, lowercase: bool) {
+        let mut out = Vec::new();
+        match self.direction {
+            Direction::Encrypt => {
+                // SAFETY: every symbol of the alphabet has a cell
+                let (row, col) = self.square.square().position(index).unwrap();
+                self.square.labels().write_symbol(row, lowercase, &mut out);
+                self.square.labels().write_symbol(col, lowercase, &mut out);
+            }
+            Direction::Decrypt => match self.row.take() {
+                None => self.row = Some((index, lowercase)),
+                Some((row, lowercase)) => {
+                    let symbol = self.square.square().at(row, index);
+                    self.square
+                        .square()
+                        .alphabet()
+                        .write_symbol(symbol, lowercase, &mut out);
+                }
+            },
+        }
+        self.output.extend(out);
+    }
+}
+
+impl<I: CryptographicIter> Iterator for PolybiusTransform<I> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.output.is_empty() {
+            if self.error.is_some() {
+                return None;
+            }
+
+            match self.reader.next_token(&mut self.iter, &self.alphabet)? {
+                Token::Symbol { index, lowercase } => self.process(index, lowercase),
+                Token::Other(bytes) => match self.alphabet.policy() {
+                    OutOfAlphabet::PassThrough => self.output.extend(bytes),
+                    OutOfAlphabet::Drop => {}
+                    OutOfAlphabet::Error => self.error = Some(bytes),
+                },
+            }
+        }
+
+        self.output.pop_front()
+    }
+}
+impl<I: CryptographicIter> CryptographicIter for PolybiusTransform<I> {}
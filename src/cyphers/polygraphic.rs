@@ -0,0 +1,721 @@
+use std::collections::VecDeque;
+
+use crate::{
+    cyphers::alphabet::{Alphabet, Direction, OutOfAlphabet, SymbolReader, Token},
+    traits::CryptographicIter,
+};
+
+/// A grid of the symbols of an alphabet, starting with a keyword and followed by the rest of the alphabet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeySquare {
+    alphabet: Alphabet,
+    rows: usize,
+    cols: usize,
+    // the alphabet indices of the cells, row by row
+    cells: Vec<usize>,
+    // the cell of every symbol of the alphabet. Merged symbols share the cell of the symbol they are merged into.
+    positions: Vec<Option<usize>>,
+}
+
+impl KeySquare {
+    /// `merges` lists pairs of symbols where the first one is written as the second one, e.g. `("J", "I")`
+    pub fn new(
+        alphabet: &Alphabet,
+        rows: usize,
+        cols: usize,
+        keyword: &str,
+        merges: &[(&str, &str)],
+    ) -> Result<Self, std::io::Error> {
+        let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg);
+
+        let mut merged_into: Vec<Option<usize>> = vec![None; alphabet.len()];
+        for (from, to) in merges {
+            let (Some(from), Some(to)) = (
+                alphabet.index_of(from.as_bytes()),
+                alphabet.index_of(to.as_bytes()),
+            ) else {
+                return Err(invalid("The merged symbols have to be in the alphabet"));
+            };
+            merged_into[from] = Some(to);
+        }
+
+        let mut positions = vec![None; alphabet.len()];
+        let mut cells = Vec::with_capacity(rows * cols);
+
+        let mut bytes = keyword.bytes();
+        let mut reader = SymbolReader::new();
+        let keyword = std::iter::from_fn(|| reader.next_symbol(&mut bytes, alphabet));
+        for symbol in keyword.chain(0..alphabet.len()) {
+            let symbol = merged_into[symbol].unwrap_or(symbol);
+            if positions[symbol].is_none() {
+                positions[symbol] = Some(cells.len());
+                cells.push(symbol);
+            }
+        }
+
+        if cells.len() != rows * cols {
+            return Err(invalid(
+                "The number of distinct symbols does not match the size of the square",
+            ));
+        }
+
+        for (from, to) in merged_into.iter().enumerate() {
+            if let Some(to) = to {
+                positions[from] = positions[*to];
+            }
+        }
+
+        Ok(KeySquare {
+            alphabet: alphabet.clone(),
+            rows,
+            cols,
+            cells,
+            positions,
+        })
+    }
+
+    /// The usual 5x5 square of the letters, with `J` written as `I`
+    pub fn latin(keyword: &str) -> Self {
+        // SAFETY: 25 distinct letters always fill a 5x5 square
+        Self::new(&Alphabet::letters(), 5, 5, keyword, &[("J", "I")]).unwrap()
+    }
+
+    #[inline]
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[inline]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The row and column of a symbol
+    #[inline]
+    pub fn position(&self, symbol: usize) -> Option<(usize, usize)> {
+        self.positions[symbol].map(|i| (i / self.cols, i % self.cols))
+    }
+
+    #[inline]
+    pub fn at(&self, row: usize, col: usize) -> usize {
+        self.cells[row * self.cols + col]
+    }
+}
+
+/// A cypher that replaces groups of `gram_len` symbols at a time
+pub trait PolygraphicCypher {
+    fn alphabet(&self) -> &Alphabet;
+
+    fn gram_len(&self) -> usize;
+
+    /// The symbol used to fill an incomplete gram, or to split one up, after `previous`.
+    /// Without one, an incomplete last gram is replaced as it is.
+    fn filler(&self, previous: Option<usize>) -> Option<usize>;
+
+    /// Whether `next` can not be added to the incomplete `gram` and a filler has to go in between
+    #[inline]
+    fn needs_separator(&self, _gram: &[usize], _next: usize) -> bool {
+        false
+    }
+
+    fn encrypt(&self, gram: &mut [usize]);
+
+    fn decrypt(&self, gram: &mut [usize]);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Playfair {
+    square: KeySquare,
+    filler: usize,
+    // used instead of the filler when the filler itself is doubled
+    alternative_filler: usize,
+}
+
+impl Playfair {
+    pub fn new(
+        square: KeySquare,
+        filler: &str,
+        alternative_filler: &str,
+    ) -> Result<Self, std::io::Error> {
+        let alphabet = square.alphabet();
+        let (Some(filler), Some(alternative_filler)) = (
+            alphabet.index_of(filler.as_bytes()),
+            alphabet.index_of(alternative_filler.as_bytes()),
+        ) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The fillers have to be in the alphabet",
+            ));
+        };
+
+        if square.position(filler).is_none()
+            || square.position(alternative_filler).is_none()
+            || square.position(filler) == square.position(alternative_filler)
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The fillers have to be in different cells of the square",
+            ));
+        }
+
+        Ok(Playfair {
+            square,
+            filler,
+            alternative_filler,
+        })
+    }
+
+    /// The textbook variant: a 5x5 square with `J` written as `I`, and `X` (or `Q` after an `X`) as the filler
+    pub fn latin(keyword: &str) -> Self {
+        // SAFETY: `X` and `Q` are in the square
+        Self::new(KeySquare::latin(keyword), "X", "Q").unwrap()
+    }
+
+    // moves right and down when encrypting, left and up when decrypting
+    fn shift(&self, gram: &mut [usize], direction: Direction) {
+        let sq = &self.square;
+        let (rows, cols) = (sq.rows(), sq.cols());
+        let step = |size: usize| match direction {
+            Direction::Encrypt => 1,
+            Direction::Decrypt => size - 1,
+        };
+        // SAFETY: the transform only passes in symbols of the alphabet, and every one of them has a cell
+        let (r1, c1) = sq.position(gram[0]).unwrap();
+        let (r2, c2) = sq.position(gram[1]).unwrap();
+
+        let ((r1, c1), (r2, c2)) = if r1 == r2 {
+            (
+                (r1, (c1 + step(cols)) % cols),
+                (r2, (c2 + step(cols)) % cols),
+            )
+        } else if c1 == c2 {
+            (
+                ((r1 + step(rows)) % rows, c1),
+                ((r2 + step(rows)) % rows, c2),
+            )
+        } else {
+            ((r1, c2), (r2, c1))
+        };
+        gram[0] = sq.at(r1, c1);
+        gram[1] = sq.at(r2, c2);
+    }
+}
+
+impl PolygraphicCypher for Playfair {
+    #[inline]
+    fn alphabet(&self) -> &Alphabet {
+        self.square.alphabet()
+    }
+
+    #[inline]
+    fn gram_len(&self) -> usize {
+        2
+    }
+
+    fn filler(&self, previous: Option<usize>) -> Option<usize> {
+        if previous.and_then(|p| self.square.position(p)) == self.square.position(self.filler) {
+            Some(self.alternative_filler)
+        } else {
+            Some(self.filler)
+        }
+    }
+
+    fn needs_separator(&self, gram: &[usize], next: usize) -> bool {
+        gram.len() == 1 && self.square.position(gram[0]) == self.square.position(next)
+    }
+
+    fn encrypt(&self, gram: &mut [usize]) {
+        self.shift(gram, Direction::Encrypt);
+    }
+
+    fn decrypt(&self, gram: &mut [usize]) {
+        self.shift(gram, Direction::Decrypt);
+    }
+}
+
+/// Two key squares on top of each other: the first symbol of every pair is looked up in the top one and
+/// the second one in the bottom one. Pairs in the same column are left as they are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TwoSquare {
+    top: KeySquare,
+    bottom: KeySquare,
+    filler: usize,
+}
+
+impl TwoSquare {
+    pub fn new(top: KeySquare, bottom: KeySquare, filler: &str) -> Result<Self, std::io::Error> {
+        check_squares(&[&top, &bottom])?;
+        let filler = check_filler(top.alphabet(), filler)?;
+        Ok(TwoSquare {
+            top,
+            bottom,
+            filler,
+        })
+    }
+
+    /// Two 5x5 squares with `J` written as `I`, and `X` as the filler
+    pub fn latin(top: &str, bottom: &str) -> Self {
+        // SAFETY: both of the squares are over the same letters
+        Self::new(KeySquare::latin(top), KeySquare::latin(bottom), "X").unwrap()
+    }
+}
+
+impl PolygraphicCypher for TwoSquare {
+    #[inline]
+    fn alphabet(&self) -> &Alphabet {
+        self.top.alphabet()
+    }
+
+    #[inline]
+    fn gram_len(&self) -> usize {
+        2
+    }
+
+    #[inline]
+    fn filler(&self, _previous: Option<usize>) -> Option<usize> {
+        Some(self.filler)
+    }
+
+    fn encrypt(&self, gram: &mut [usize]) {
+        // SAFETY: the transform only passes in symbols of the alphabet, and every one of them has a cell
+        let (r1, c1) = self.top.position(gram[0]).unwrap();
+        let (r2, c2) = self.bottom.position(gram[1]).unwrap();
+        if c1 != c2 {
+            gram[0] = self.top.at(r1, c2);
+            gram[1] = self.bottom.at(r2, c1);
+        }
+    }
+
+    fn decrypt(&self, gram: &mut [usize]) {
+        // swapping the corners of the rectangle is its own inverse
+        self.encrypt(gram);
+    }
+}
+
+/// Four squares in a 2x2 grid: the plain top left and bottom right ones are used to look up the plaintext,
+/// and the keyed top right and bottom left ones give the cyphertext
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FourSquare {
+    plain: KeySquare,
+    top_right: KeySquare,
+    bottom_left: KeySquare,
+    filler: usize,
+}
+
+impl FourSquare {
+    pub fn new(
+        plain: KeySquare,
+        top_right: KeySquare,
+        bottom_left: KeySquare,
+        filler: &str,
+    ) -> Result<Self, std::io::Error> {
+        check_squares(&[&plain, &top_right, &bottom_left])?;
+        let filler = check_filler(plain.alphabet(), filler)?;
+        Ok(FourSquare {
+            plain,
+            top_right,
+            bottom_left,
+            filler,
+        })
+    }
+
+    /// 5x5 squares with `J` written as `I`, and `X` as the filler
+    pub fn latin(top_right: &str, bottom_left: &str) -> Self {
+        // SAFETY: all the squares are over the same letters
+        Self::new(
+            KeySquare::latin(""),
+            KeySquare::latin(top_right),
+            KeySquare::latin(bottom_left),
+            "X",
+        )
+        .unwrap()
+    }
+}
+
+impl PolygraphicCypher for FourSquare {
+    #[inline]
+    fn alphabet(&self) -> &Alphabet {
+        self.plain.alphabet()
+    }
+
+    #[inline]
+    fn gram_len(&self) -> usize {
+        2
+    }
+
+    #[inline]
+    fn filler(&self, _previous: Option<usize>) -> Option<usize> {
+        Some(self.filler)
+    }
+
+    fn encrypt(&self, gram: &mut [usize]) {
+        // SAFETY: the transform only passes in symbols of the alphabet, and every one of them has a cell
+        let (r1, c1) = self.plain.position(gram[0]).unwrap();
+        let (r2, c2) = self.plain.position(gram[1]).unwrap();
+        gram[0] = self.top_right.at(r1, c2);
+        gram[1] = self.bottom_left.at(r2, c1);
+    }
+
+    fn decrypt(&self, gram: &mut [usize]) {
+        // SAFETY: the transform only passes in symbols of the alphabet, and every one of them has a cell
+        let (r1, c2) = self.top_right.position(gram[0]).unwrap();
+        let (r2, c1) = self.bottom_left.position(gram[1]).unwrap();
+        gram[0] = self.plain.at(r1, c1);
+        gram[1] = self.plain.at(r2, c2);
+    }
+}
+
+fn check_squares(squares: &[&KeySquare]) -> Result<(), std::io::Error> {
+    let first = squares[0];
+    if squares.iter().all(|s| {
+        s.alphabet() == first.alphabet() && s.rows() == first.rows() && s.cols() == first.cols()
+    }) {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The squares have to be over the same alphabet and of the same size",
+        ))
+    }
+}
+
+fn check_filler(alphabet: &Alphabet, filler: &str) -> Result<usize, std::io::Error> {
+    alphabet.index_of(filler.as_bytes()).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The filler has to be in the alphabet",
+        )
+    })
+}
+
+/// Multiplies every group of `n` symbols, as a vector, by an invertible `n`x`n` matrix modulo the size of the alphabet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hill {
+    alphabet: Alphabet,
+    n: usize,
+    matrix: Vec<usize>,
+    inverse: Vec<usize>,
+    filler: usize,
+}
+
+impl Hill {
+    /// `matrix` is given row by row, as indices of symbols of the alphabet
+    pub fn new(
+        alphabet: &Alphabet,
+        matrix: Vec<usize>,
+        filler: &str,
+    ) -> Result<Self, std::io::Error> {
+        let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg);
+
+        let n = (1..=matrix.len())
+            .find(|n| n * n >= matrix.len())
+            .unwrap_or(0);
+        if n == 0 || n * n != matrix.len() {
+            return Err(invalid(String::from(
+                "The key has to be a non-empty square matrix",
+            )));
+        }
+
+        let m = alphabet.len() as i64;
+        let entries: Vec<i64> = matrix.iter().map(|&v| v as i64 % m).collect();
+
+        let det = determinant(&entries, n, m);
+        let Some(det_inverse) = mod_inverse(det, m) else {
+            return Err(invalid(format!(
+                "The key is not invertible: its determinant, {}, is not coprime with {}",
+                det, m
+            )));
+        };
+
+        // the inverse is the adjugate divided by the determinant
+        let mut inverse = vec![0; n * n];
+        for r in 0..n {
+            for c in 0..n {
+                let sign = if (r + c) % 2 == 0 { 1 } else { m - 1 };
+                let cofactor = sign * determinant(&minor(&entries, n, r, c), n - 1, m) % m;
+                // NOTE: the adjugate is the transpose of the cofactor matrix
+                inverse[c * n + r] = (cofactor * det_inverse % m) as usize;
+            }
+        }
+
+        Ok(Hill {
+            alphabet: alphabet.clone(),
+            n,
+            matrix: entries.into_iter().map(|v| v as usize).collect(),
+            inverse,
+            filler: check_filler(alphabet, filler)?,
+        })
+    }
+
+    /// The key is written out as symbols of the alphabet row by row, e.g. `"GYBNQKURP"` for a 3x3 matrix
+    pub fn from_keyword(
+        alphabet: &Alphabet,
+        keyword: &str,
+        filler: &str,
+    ) -> Result<Self, std::io::Error> {
+        let mut bytes = keyword.bytes();
+        let mut reader = SymbolReader::new();
+        let matrix = std::iter::from_fn(|| reader.next_symbol(&mut bytes, alphabet)).collect();
+        Self::new(alphabet, matrix, filler)
+    }
+
+    #[inline]
+    pub fn matrix(&self) -> &[usize] {
+        &self.matrix
+    }
+
+    #[inline]
+    pub fn inverse(&self) -> &[usize] {
+        &self.inverse
+    }
+
+    fn multiply(&self, matrix: &[usize], gram: &mut [usize]) {
+        let m = self.alphabet.len();
+        let v: Vec<usize> = gram.to_vec();
+        for (r, out) in gram.iter_mut().enumerate() {
+            *out = (0..self.n)
+                .map(|c| matrix[r * self.n + c] * v[c] % m)
+                .sum::<usize>()
+                % m;
+        }
+    }
+}
+
+// NOTE: the determinant is expanded along the first row, which is fine for the small keys used in practice
+fn determinant(matrix: &[i64], n: usize, m: i64) -> i64 {
+    match n {
+        0 => 1 % m,
+        1 => matrix[0].rem_euclid(m),
+        _ => (0..n)
+            .map(|c| {
+                let sign = if c % 2 == 0 { 1 } else { -1 };
+                sign * matrix[c] * determinant(&minor(matrix, n, 0, c), n - 1, m)
+            })
+            .sum::<i64>()
+            .rem_euclid(m),
+    }
+}
+
+fn minor(matrix: &[i64], n: usize, row: usize, col: usize) -> Vec<i64> {
+    (0..n)
+        .filter(|&r| r != row)
+        .flat_map(|r| {
+            (0..n)
+                .filter(move |&c| c != col)
+                .map(move |c| matrix[r * n + c])
+        })
+        .collect()
+}
+
+fn mod_inverse(a: i64, m: i64) -> Option<i64> {
+    // the extended Euclidean algorithm
+    let (mut old_r, mut r) = (a.rem_euclid(m), m);
+    let (mut old_s, mut s) = (1i64, 0i64);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+    }
+    (old_r == 1).then(|| old_s.rem_euclid(m))
+}
+
+impl PolygraphicCypher for Hill {
+    #[inline]
+    fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    #[inline]
+    fn gram_len(&self) -> usize {
+        self.n
+    }
+
+    #[inline]
+    fn filler(&self, _previous: Option<usize>) -> Option<usize> {
+        Some(self.filler)
+    }
+
+    fn encrypt(&self, gram: &mut [usize]) {
+        self.multiply(&self.matrix, gram);
+    }
+
+    fn decrypt(&self, gram: &mut [usize]) {
+        self.multiply(&self.inverse, gram);
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Slot {
+    Symbol { lowercase: bool },
+    Other(Vec<u8>),
+}
+
+/// Splits the message into groups of symbols and replaces them a group at a time.
+///
+/// An incomplete last group is filled up with the filler of the cypher, if it has one. Characters outside of the alphabet
+/// that pass through stay in place, between the symbols they were found between.
+#[derive(Clone)]
+pub struct PolygraphicTransform<I: CryptographicIter, C: PolygraphicCypher> {
+    iter: I,
+    cypher: C,
+    direction: Direction,
+    alphabet: Alphabet,
+    reader: SymbolReader,
+    pending: Vec<Slot>,
+    gram: Vec<usize>,
+    output: VecDeque<u8>,
+    error: Option<Vec<u8>>,
+}
+
+impl<I: CryptographicIter, C: PolygraphicCypher> PolygraphicTransform<I, C> {
+    pub fn new(iter: I, cypher: C, direction: Direction) -> Self {
+        PolygraphicTransform {
+            iter,
+            alphabet: cypher.alphabet().clone(),
+            gram: Vec::new(),
+            cypher,
+            direction,
+            reader: SymbolReader::new(),
+            pending: Vec::new(),
+            output: VecDeque::new(),
+            error: None,
+        }
+    }
+
+    /// Changes what happens to the characters outside of the alphabet of the cypher
+    #[inline]
+    pub fn with_policy(mut self, policy: OutOfAlphabet) -> Self {
+        self.alphabet = self.alphabet.with_policy(policy);
+        self
+    }
+
+    /// The error that stopped the stream, if a character outside of the alphabet was found with `OutOfAlphabet::Error`
+    pub fn error(&self) -> Option<std::io::Error> {
+        self.error.as_ref().map(|c| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{:?} is not in the alphabet",
+                    String::from_utf8_lossy(c).as_ref()
+                ),
+            )
+        })
+    }
+
+    fn push_symbol(&mut self, symbol: usize, lowercase: bool) {
+        self.gram.push(symbol);
+        self.pending.push(Slot::Symbol { lowercase });
+        if self.gram.len() == self.cypher.gram_len() {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        match self.direction {
+            Direction::Encrypt => self.cypher.encrypt(&mut self.gram),
+            Direction::Decrypt => self.cypher.decrypt(&mut self.gram),
+        }
+
+        let mut gram = self.gram.drain(..);
+        let mut out = Vec::new();
+        self.pending.drain(..).for_each(|slot| match slot {
+            Slot::Other(bytes) => out.extend(bytes),
+            Slot::Symbol { lowercase } => {
+                // SAFETY: there is a symbol in the gram for every symbol slot
+                self.alphabet
+                    .write_symbol(gram.next().unwrap(), lowercase, &mut out);
+            }
+        });
+        self.output.extend(out);
+    }
+}
+
+impl<I: CryptographicIter, C: PolygraphicCypher> Iterator for PolygraphicTransform<I, C> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.output.is_empty() {
+            if self.error.is_some() {
+                return None;
+            }
+
+            match self.reader.next_token(&mut self.iter, &self.alphabet) {
+                Some(Token::Symbol { index, lowercase }) => {
+                    if self.direction == Direction::Encrypt
+                        && self.cypher.needs_separator(&self.gram, index)
+                    {
+                        if let Some(filler) = self.cypher.filler(self.gram.last().copied()) {
+                            self.push_symbol(filler, lowercase);
+                        }
+                    }
+                    self.push_symbol(index, lowercase);
+                }
+                Some(Token::Other(bytes)) => match self.alphabet.policy() {
+                    OutOfAlphabet::PassThrough if self.pending.is_empty() => {
+                        self.output.extend(bytes)
+                    }
+                    OutOfAlphabet::PassThrough => self.pending.push(Slot::Other(bytes)),
+                    OutOfAlphabet::Drop => {}
+                    OutOfAlphabet::Error => self.error = Some(bytes),
+                },
+                None if self.gram.is_empty() => {
+                    // only the characters outside of the alphabet are left
+                    self.pending.drain(..).for_each(|slot| {
+                        if let Slot::Other(bytes) = slot {
+                            self.output.extend(bytes);
+                        }
+                    });
+                    return self.output.pop_front();
+                }
+                None => {
+                    let lowercase = self.pending.iter().rev().find_map(|slot| match slot {
+                        Slot::Symbol { lowercase } => Some(*lowercase),
+                        Slot::Other(_) => None,
+                    });
+                    while !self.gram.is_empty() {
+                        match self.cypher.filler(self.gram.last().copied()) {
+                            Some(filler) => self.push_symbol(filler, lowercase.unwrap_or(false)),
+                            None => self.flush(),
+                        }
+                    }
+                }
+            }
+        }
+
+        self.output.pop_front()
+    }
+}
+impl<I: CryptographicIter, C: PolygraphicCypher> CryptographicIter for PolygraphicTransform<I, C> {}
+
+/// Splits a byte stream into chunks of `size` bytes. The last chunk can be shorter.
+#[derive(Clone)]
+pub struct Chunks<I: CryptographicIter> {
+    iter: I,
+    size: usize,
+}
+
+impl<I: CryptographicIter> Chunks<I> {
+    #[inline]
+    pub fn new(iter: I, size: usize) -> Self {
+        assert!(size > 0, "The chunks have to be at least one byte long");
+        Chunks { iter, size }
+    }
+}
+
+impl<I: CryptographicIter> Iterator for Chunks<I> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk: Vec<u8> = self.iter.by_ref().take(self.size).collect();
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    }
+}
@@ -23,16 +23,76 @@ impl<I: CryptographicIter> Iterator for CaesarCypherTransform<I> {
 }
 impl<I: CryptographicIter> CryptographicIter for CaesarCypherTransform<I> {}
 
+/// What happens when the key of a `XorTransform` is shorter than the message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XorMode {
+    /// The key has to be at least as long as the message, as in a one-time pad. The stream stops with an error
+    /// once the key runs out.
+    Strict,
+    /// The key starts over once it runs out
+    Repeating,
+    /// The message is cut short once the key runs out
+    Truncating,
+}
+
 #[derive(Clone)]
 pub struct XorTransform<I1: CryptographicIter, I2: CryptographicIter> {
     iter: I1,
     key: I2,
+    mode: XorMode,
+    // the key read so far, to start over with in the repeating mode
+    key_buffer: Vec<u8>,
+    key_exhausted: bool,
+    position: usize,
+    error: bool,
 }
 
 impl<I1: CryptographicIter, I2: CryptographicIter> XorTransform<I1, I2> {
     #[inline]
-    pub fn new(iter: I1, key: I2) -> Self {
-        XorTransform { iter, key }
+    pub fn new(iter: I1, key: I2, mode: XorMode) -> Self {
+        XorTransform {
+            iter,
+            key,
+            mode,
+            key_buffer: Vec::new(),
+            key_exhausted: false,
+            position: 0,
+            error: false,
+        }
+    }
+
+    /// The error that stopped the stream, if the key ran out before the message did
+    pub fn error(&self) -> Option<std::io::Error> {
+        self.error.then(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!(
+                    "The key ran out after {} bytes, before the end of the message",
+                    self.position
+                ),
+            )
+        })
+    }
+
+    fn next_key(&mut self) -> Option<u8> {
+        if !self.key_exhausted {
+            match self.key.next() {
+                Some(k) => {
+                    if self.mode == XorMode::Repeating {
+                        self.key_buffer.push(k);
+                    }
+                    return Some(k);
+                }
+                None => self.key_exhausted = true,
+            }
+        }
+
+        match self.mode {
+            XorMode::Repeating if !self.key_buffer.is_empty() => {
+                Some(self.key_buffer[self.position % self.key_buffer.len()])
+            }
+            _ => None,
+        }
     }
 }
 
@@ -41,10 +101,21 @@ impl<I1: CryptographicIter, I2: CryptographicIter> Iterator for XorTransform<I1,
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        if let (Some(ch), Some(k)) = (self.iter.next(), self.key.next()) {
-            Some(ch ^ k)
-        } else {
-            None
+        if self.error {
+            return None;
+        }
+
+        let ch = self.iter.next()?;
+        match self.next_key() {
+            Some(k) => {
+                self.position += 1;
+                Some(ch ^ k)
+            }
+            None => {
+                // NOTE: truncating is the only mode that is allowed to lose the rest of the message
+                self.error = self.mode != XorMode::Truncating;
+                None
+            }
         }
     }
 }
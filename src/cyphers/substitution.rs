@@ -0,0 +1,188 @@
+use crate::{
+    common::{read_varint, write_varint},
+    cyphers::alphabet::{Alphabet, AlphabetTransform, Direction, SymbolCypher, SymbolReader},
+    random::SplitMix64,
+    traits::Serialisable,
+};
+
+/// A monoalphabetic substitution key: the `i`th symbol of the alphabet is replaced with the `permutation[i]`th one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubstitutionKey {
+    alphabet: Alphabet,
+    permutation: Vec<usize>,
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl SubstitutionKey {
+    pub fn new(alphabet: &Alphabet, permutation: Vec<usize>) -> Result<Self, std::io::Error> {
+        let mut seen = vec![false; alphabet.len()];
+        let is_permutation = permutation.len() == alphabet.len()
+            && permutation
+                .iter()
+                .all(|&i| i < seen.len() && !std::mem::replace(&mut seen[i], true));
+        if !is_permutation {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The key has to map every symbol of the alphabet to a distinct symbol",
+            ));
+        }
+
+        Ok(SubstitutionKey {
+            alphabet: alphabet.clone(),
+            permutation,
+        })
+    }
+
+    /// The replacements of the symbols of the alphabet written out in order, e.g. `"QWERTYUIOPASDFGHJKLZXCVBNM"`
+    pub fn from_cypher_alphabet(
+        alphabet: &Alphabet,
+        cypher_alphabet: &str,
+    ) -> Result<Self, std::io::Error> {
+        let mut bytes = cypher_alphabet.bytes();
+        let mut reader = SymbolReader::new();
+        let permutation = std::iter::from_fn(|| reader.next_symbol(&mut bytes, alphabet)).collect();
+        Self::new(alphabet, permutation)
+    }
+
+    /// The keyword with the repeated symbols removed, followed by the rest of the alphabet in order
+    pub fn from_keyword(alphabet: &Alphabet, keyword: &str) -> Self {
+        let mut used = vec![false; alphabet.len()];
+        let mut bytes = keyword.bytes();
+        let mut reader = SymbolReader::new();
+
+        let permutation = std::iter::from_fn(|| reader.next_symbol(&mut bytes, alphabet))
+            .chain(0..alphabet.len())
+            .filter(|&i| !std::mem::replace(&mut used[i], true))
+            .collect();
+
+        SubstitutionKey {
+            alphabet: alphabet.clone(),
+            permutation,
+        }
+    }
+
+    pub fn random(alphabet: &Alphabet, seed: u64) -> Self {
+        let mut permutation: Vec<usize> = (0..alphabet.len()).collect();
+        SplitMix64::new(seed).shuffle(&mut permutation);
+
+        SubstitutionKey {
+            alphabet: alphabet.clone(),
+            permutation,
+        }
+    }
+
+    /// The alphabet written backwards, e.g. `A` <-> `Z`
+    pub fn atbash(alphabet: &Alphabet) -> Self {
+        SubstitutionKey {
+            alphabet: alphabet.clone(),
+            permutation: (0..alphabet.len()).rev().collect(),
+        }
+    }
+
+    /// `x -> a * x + b` modulo the size of the alphabet. `a` has to be coprime with it for the cypher to be reversible.
+    pub fn affine(alphabet: &Alphabet, a: usize, b: usize) -> Result<Self, std::io::Error> {
+        let n = alphabet.len();
+        if gcd(a % n, n) != 1 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "The multiplier {} is not coprime with the size of the alphabet, {}",
+                    a, n
+                ),
+            ));
+        }
+
+        // NOTE: reduced first, so that a large `b` can not overflow
+        let (a, b) = (a % n, b % n);
+        Ok(SubstitutionKey {
+            alphabet: alphabet.clone(),
+            permutation: (0..n).map(|x| (a * x + b) % n).collect(),
+        })
+    }
+
+    #[inline]
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    #[inline]
+    pub fn permutation(&self) -> &[usize] {
+        &self.permutation
+    }
+
+    pub fn inverse(&self) -> Self {
+        let mut permutation = vec![0; self.permutation.len()];
+        self.permutation
+            .iter()
+            .enumerate()
+            .for_each(|(i, &p)| permutation[p] = i);
+
+        SubstitutionKey {
+            alphabet: self.alphabet.clone(),
+            permutation,
+        }
+    }
+
+    /// The replacements of the symbols of the alphabet written out in order
+    pub fn cypher_alphabet(&self) -> String {
+        let mut ret = Vec::new();
+        self.permutation
+            .iter()
+            .for_each(|&i| self.alphabet.write_symbol(i, false, &mut ret));
+        String::from_utf8_lossy(&ret).into_owned()
+    }
+
+    pub fn cypher(&self, direction: Direction) -> Substitution {
+        Substitution {
+            permutation: match direction {
+                Direction::Encrypt => self.permutation.clone(),
+                Direction::Decrypt => self.inverse().permutation,
+            },
+        }
+    }
+}
+
+impl Serialisable for SubstitutionKey {
+    type CryptoIter = std::vec::IntoIter<u8>;
+
+    // `[alphabet, varint(permutation[i])...]`
+    fn serialise(&self) -> Self::CryptoIter {
+        let mut ret: Vec<u8> = self.alphabet.serialise().collect();
+        self.permutation
+            .iter()
+            .for_each(|&i| write_varint(i as u64, &mut ret));
+        ret.into_iter()
+    }
+
+    fn deserialise<I: Iterator<Item = u8>>(mut b: I) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let alphabet = Alphabet::deserialise(b.by_ref())?;
+        let permutation = (0..alphabet.len())
+            .map(|_| read_varint(&mut b)?.try_into().ok())
+            .collect::<Option<Vec<usize>>>()?;
+        Self::new(&alphabet, permutation).ok()
+    }
+}
+
+pub type SubstitutionTransform<I> = AlphabetTransform<I, Substitution>;
+
+#[derive(Clone, Debug)]
+pub struct Substitution {
+    permutation: Vec<usize>,
+}
+
+impl SymbolCypher for Substitution {
+    #[inline]
+    fn apply(&mut self, index: usize, _alphabet: &Alphabet) -> Option<usize> {
+        self.permutation.get(index).copied()
+    }
+}
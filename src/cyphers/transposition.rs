@@ -0,0 +1,436 @@
+use std::{borrow::Cow, collections::VecDeque};
+
+use crate::{
+    cyphers::alphabet::{Alphabet, Direction, OutOfAlphabet, SymbolReader, Token},
+    traits::CryptographicIter,
+};
+
+/// A cypher that reorders the symbols of a whole message
+pub trait Transposition {
+    /// The order the symbols of a message of length `len` are read out in, i.e. `output[i] = input[order[i]]`
+    fn order(&self, len: usize) -> Vec<usize>;
+
+    /// The length a message has to be padded to before it is encrypted
+    #[inline]
+    fn padded_len(&self, len: usize) -> usize {
+        len
+    }
+
+    /// The symbol to pad messages with, if any
+    #[inline]
+    fn filler(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// How to handle a message that does not fill the last row of a grid
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Padding {
+    /// Leave the last row incomplete, so that some columns are shorter
+    Irregular,
+    /// Fill the last row with a symbol. It is left in the plaintext after decryption.
+    Filler(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RailFence {
+    rails: usize,
+    offset: usize,
+}
+
+impl RailFence {
+    /// `offset` is how far into the zigzag the message starts
+    pub fn new(rails: usize, offset: usize) -> Result<Self, std::io::Error> {
+        if rails == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "A rail fence needs at least one rail",
+            ));
+        }
+        Ok(RailFence { rails, offset })
+    }
+}
+
+impl Transposition for RailFence {
+    fn order(&self, len: usize) -> Vec<usize> {
+        let cycle = (2 * (self.rails - 1)).max(1);
+        let rail = |i: usize| {
+            let p = (i + self.offset) % cycle;
+            p.min(cycle - p)
+        };
+
+        let mut order: Vec<usize> = (0..len).collect();
+        // NOTE: the sort is stable, so the symbols on each rail stay in order
+        order.sort_by_key(|&i| rail(i));
+        order
+    }
+}
+
+/// Writes the message into rows and reads it out column by column, in the order given by the key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnarKey {
+    // the columns in the order they are read out
+    columns: Vec<usize>,
+    padding: Padding,
+}
+
+impl ColumnarKey {
+    /// `columns` is the order the columns are read out in, e.g. `[2, 0, 1]` reads the last column first
+    pub fn new(columns: Vec<usize>, padding: Padding) -> Result<Self, std::io::Error> {
+        let mut seen = vec![false; columns.len()];
+        let is_permutation = columns
+            .iter()
+            .all(|&i| i < seen.len() && !std::mem::replace(&mut seen[i], true));
+        if columns.is_empty() || !is_permutation {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The key has to list every column exactly once",
+            ));
+        }
+        Ok(ColumnarKey { columns, padding })
+    }
+
+    /// The columns are read out in the alphabetical order of the letters of the keyword, left to right for repeated letters
+    pub fn from_keyword(keyword: &str, padding: Padding) -> Result<Self, std::io::Error> {
+        let keyword: Vec<String> = keyword
+            .chars()
+            .map(|c| c.to_uppercase().collect())
+            .collect();
+        let mut columns: Vec<usize> = (0..keyword.len()).collect();
+        columns.sort_by(|&a, &b| keyword[a].cmp(&keyword[b]));
+        Self::new(columns, padding)
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// The columns in the order they are read out
+    #[inline]
+    pub fn columns(&self) -> &[usize] {
+        &self.columns
+    }
+}
+
+impl Transposition for ColumnarKey {
+    fn order(&self, len: usize) -> Vec<usize> {
+        let width = self.width();
+        self.columns
+            .iter()
+            .flat_map(|&c| (c..len).step_by(width))
+            .collect()
+    }
+
+    fn padded_len(&self, len: usize) -> usize {
+        match self.padding {
+            Padding::Irregular => len,
+            Padding::Filler(_) => len.div_ceil(self.width()) * self.width(),
+        }
+    }
+
+    fn filler(&self) -> Option<&str> {
+        match &self.padding {
+            Padding::Irregular => None,
+            Padding::Filler(f) => Some(f),
+        }
+    }
+}
+
+/// Two columnar transpositions one after another. Only the first key pads the message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DoubleColumnar {
+    first: ColumnarKey,
+    second: ColumnarKey,
+}
+
+impl DoubleColumnar {
+    pub fn new(first: ColumnarKey, second: ColumnarKey) -> Self {
+        DoubleColumnar { first, second }
+    }
+}
+
+impl Transposition for DoubleColumnar {
+    fn order(&self, len: usize) -> Vec<usize> {
+        let first = self.first.order(len);
+        self.second
+            .order(len)
+            .into_iter()
+            .map(|i| first[i])
+            .collect()
+    }
+
+    fn padded_len(&self, len: usize) -> usize {
+        self.first.padded_len(len)
+    }
+
+    fn filler(&self) -> Option<&str> {
+        self.first.filler()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoutePath {
+    /// Clockwise and inwards, starting from the top left corner
+    Spiral,
+    /// Anticlockwise and inwards, starting from the top left corner
+    AnticlockwiseSpiral,
+    /// Down the first column, up the second one and so on
+    Boustrophedon,
+}
+
+/// Writes the message into rows of `width` symbols and reads it out along a path through the grid
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route {
+    width: usize,
+    path: RoutePath,
+    padding: Padding,
+}
+
+impl Route {
+    pub fn new(width: usize, path: RoutePath, padding: Padding) -> Result<Self, std::io::Error> {
+        if width == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The grid has to be at least one column wide",
+            ));
+        }
+        Ok(Route {
+            width,
+            path,
+            padding,
+        })
+    }
+
+    // the cells of the whole grid in the order they are visited, as (row, column)
+    fn path(&self, height: usize) -> Vec<(usize, usize)> {
+        let width = self.width;
+        let mut ret = Vec::with_capacity(width * height);
+
+        match self.path {
+            RoutePath::Boustrophedon => (0..width).for_each(|c| {
+                if c % 2 == 0 {
+                    ret.extend((0..height).map(|r| (r, c)));
+                } else {
+                    ret.extend((0..height).rev().map(|r| (r, c)));
+                }
+            }),
+            RoutePath::Spiral | RoutePath::AnticlockwiseSpiral => {
+                let (mut top, mut left) = (0, 0);
+                let (mut bottom, mut right) = (height, width);
+                while top < bottom && left < right {
+                    let mut ring = Vec::new();
+                    ring.extend((left..right).map(|c| (top, c)));
+                    ring.extend((top + 1..bottom).map(|r| (r, right - 1)));
+                    if bottom - top > 1 {
+                        ring.extend((left..right - 1).rev().map(|c| (bottom - 1, c)));
+                    }
+                    if right - left > 1 {
+                        ring.extend((top + 1..bottom - 1).rev().map(|r| (r, left)));
+                    }
+
+                    if self.path == RoutePath::AnticlockwiseSpiral {
+                        // the same ring, walked the other way round from the same corner
+                        ring[1..].reverse();
+                    }
+                    ret.extend(ring);
+
+                    (top, left, bottom, right) = (top + 1, left + 1, bottom - 1, right - 1);
+                }
+            }
+        }
+        ret
+    }
+}
+
+impl Transposition for Route {
+    fn order(&self, len: usize) -> Vec<usize> {
+        // NOTE: the cells of an incomplete last row are skipped
+        self.path(len.div_ceil(self.width))
+            .into_iter()
+            .map(|(r, c)| r * self.width + c)
+            .filter(|&i| i < len)
+            .collect()
+    }
+
+    fn padded_len(&self, len: usize) -> usize {
+        match self.padding {
+            Padding::Irregular => len,
+            Padding::Filler(_) => len.div_ceil(self.width) * self.width,
+        }
+    }
+
+    fn filler(&self) -> Option<&str> {
+        match &self.padding {
+            Padding::Irregular => None,
+            Padding::Filler(f) => Some(f),
+        }
+    }
+}
+
+/// A strip wound around a rod with `faces` sides: the message is written along the rod and read around it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scytale {
+    faces: usize,
+    padding: Padding,
+}
+
+impl Scytale {
+    pub fn new(faces: usize, padding: Padding) -> Result<Self, std::io::Error> {
+        if faces == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "A scytale needs at least one face",
+            ));
+        }
+        Ok(Scytale { faces, padding })
+    }
+}
+
+impl Transposition for Scytale {
+    fn order(&self, len: usize) -> Vec<usize> {
+        // the rows go along the rod, one per face
+        let width = len.div_ceil(self.faces).max(1);
+        (0..width).flat_map(|c| (c..len).step_by(width)).collect()
+    }
+
+    fn padded_len(&self, len: usize) -> usize {
+        match self.padding {
+            Padding::Irregular => len,
+            Padding::Filler(_) => len.div_ceil(self.faces) * self.faces,
+        }
+    }
+
+    fn filler(&self) -> Option<&str> {
+        match &self.padding {
+            Padding::Irregular => None,
+            Padding::Filler(f) => Some(f),
+        }
+    }
+}
+
+/// Buffers the whole message and reorders its symbols.
+///
+/// Works over all the bytes by default. Over a smaller alphabet, the characters that pass through stay where they are.
+#[derive(Clone)]
+pub struct TranspositionTransform<I: CryptographicIter, T: Transposition> {
+    iter: I,
+    transposition: T,
+    direction: Direction,
+    alphabet: Cow<'static, Alphabet>,
+    output: Option<VecDeque<u8>>,
+    error: Option<Vec<u8>>,
+}
+
+impl<I: CryptographicIter, T: Transposition> TranspositionTransform<I, T> {
+    #[inline]
+    pub fn new(iter: I, transposition: T, direction: Direction) -> Self {
+        TranspositionTransform {
+            iter,
+            transposition,
+            direction,
+            alphabet: Cow::Borrowed(Alphabet::shared_bytes()),
+            output: None,
+            error: None,
+        }
+    }
+
+    /// Switches the alphabet the cypher works over. This has to be done before the stream is read.
+    #[inline]
+    pub fn in_alphabet(mut self, alphabet: &Alphabet) -> Self {
+        self.alphabet = Cow::Owned(alphabet.clone());
+        self
+    }
+
+    #[inline]
+    pub fn letters_only(self) -> Self {
+        self.in_alphabet(&Alphabet::letters())
+    }
+
+    /// The error that stopped the stream, if a character or the filler was not in the alphabet
+    pub fn error(&self) -> Option<std::io::Error> {
+        self.error.as_ref().map(|c| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{:?} is not in the alphabet",
+                    String::from_utf8_lossy(c).as_ref()
+                ),
+            )
+        })
+    }
+
+    fn process(&mut self) -> VecDeque<u8> {
+        // the symbols, and the layout of the message with `None` in place of every symbol
+        let mut symbols = Vec::new();
+        let mut layout = Vec::new();
+
+        let mut reader = SymbolReader::new();
+        while let Some(token) = reader.next_token(&mut self.iter, &self.alphabet) {
+            match token {
+                Token::Symbol { index, lowercase } => {
+                    symbols.push((index, lowercase));
+                    layout.push(None);
+                }
+                Token::Other(bytes) => match self.alphabet.policy() {
+                    OutOfAlphabet::PassThrough => layout.push(Some(bytes)),
+                    OutOfAlphabet::Drop => {}
+                    OutOfAlphabet::Error => {
+                        self.error = Some(bytes);
+                        return VecDeque::new();
+                    }
+                },
+            }
+        }
+
+        if self.direction == Direction::Encrypt {
+            let padded_len = self.transposition.padded_len(symbols.len());
+            if padded_len > symbols.len() {
+                // NOTE: only transpositions with a filler pad the message
+                let filler = self.transposition.filler().unwrap_or_default();
+                let Some(filler) = self.alphabet.index_of(filler.as_bytes()) else {
+                    self.error = Some(filler.as_bytes().to_vec());
+                    return VecDeque::new();
+                };
+
+                let missing = padded_len - symbols.len();
+                symbols.extend(std::iter::repeat_n((filler, false), missing));
+                layout.extend(std::iter::repeat_n(None, missing));
+            }
+        }
+
+        let order = self.transposition.order(symbols.len());
+        let reordered = match self.direction {
+            Direction::Encrypt => order.iter().map(|&i| symbols[i]).collect(),
+            Direction::Decrypt => {
+                let mut ret = symbols.clone();
+                order.iter().zip(symbols).for_each(|(&i, s)| ret[i] = s);
+                ret
+            }
+        };
+
+        let mut reordered = reordered.into_iter();
+        let mut ret = Vec::new();
+        layout.into_iter().for_each(|slot| match slot {
+            Some(bytes) => ret.extend(bytes),
+            None => {
+                // SAFETY: there is a symbol for every empty slot
+                let (index, lowercase) = reordered.next().unwrap();
+                self.alphabet.write_symbol(index, lowercase, &mut ret);
+            }
+        });
+        ret.into()
+    }
+}
+
+impl<I: CryptographicIter, T: Transposition> Iterator for TranspositionTransform<I, T> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.output.is_none() {
+            self.output = Some(self.process());
+        }
+        self.output.as_mut()?.pop_front()
+    }
+}
+impl<I: CryptographicIter, T: Transposition> CryptographicIter for TranspositionTransform<I, T> {}
@@ -0,0 +1,166 @@
+pub mod md5;
+pub mod sha1;
+pub mod sha256;
+
+use crate::traits::CryptographicIter;
+
+// every Merkle–Damgård hash in the crate compresses blocks of this many bytes
+pub const BLOCK_SIZE: usize = 64;
+
+/// The chaining state of a Merkle–Damgård hash, which is compressed with one block of the message at a time. The
+/// digest is the state after the last block, so a digest is also a state that more blocks can be compressed into.
+pub trait MerkleDamgard: Clone {
+    const DIGEST_LEN: usize;
+    // the byte order of the words of the state and of the length in the padding
+    const BIG_ENDIAN: bool;
+
+    fn initial() -> Self;
+
+    /// The state that gave the digest, if it has the right length
+    fn from_digest(digest: &[u8]) -> Option<Self>;
+
+    fn compress(&mut self, block: &[u8]);
+
+    fn digest(&self) -> Vec<u8>;
+}
+
+/// The padding appended to a message of `len` bytes: a one bit, zeroes up to 8 bytes short of a whole block, and
+/// the length of the message in bits
+pub fn md_padding(len: u64, big_endian: bool) -> Vec<u8> {
+    let zeroes = (BLOCK_SIZE * 2 - 9 - len as usize % BLOCK_SIZE) % BLOCK_SIZE;
+    let mut ret = Vec::with_capacity(zeroes + 9);
+    ret.push(0x80);
+    ret.extend(std::iter::repeat_n(0, zeroes));
+    let bits = len.wrapping_mul(8);
+    ret.extend(if big_endian {
+        bits.to_be_bytes()
+    } else {
+        bits.to_le_bytes()
+    });
+    ret
+}
+
+/// Hashes a message that is given a piece at a time
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hasher<H: MerkleDamgard> {
+    state: H,
+    // the start of a block that is not complete yet
+    buffer: Vec<u8>,
+    length: u64,
+}
+
+impl<H: MerkleDamgard> Hasher<H> {
+    #[inline]
+    pub fn new() -> Self {
+        Hasher {
+            state: H::initial(),
+            buffer: Vec::with_capacity(BLOCK_SIZE),
+            length: 0,
+        }
+    }
+
+    /// Carries on from a digest of a message that was `length` bytes long once it was padded, as if those bytes had
+    /// just been hashed. This is what makes a length extension possible.
+    pub fn resume(digest: &[u8], length: u64) -> Result<Self, std::io::Error> {
+        if !length.is_multiple_of(BLOCK_SIZE as u64) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "A padded message is a multiple of {} bytes long, not {}",
+                    BLOCK_SIZE, length
+                ),
+            ));
+        }
+        let state = H::from_digest(digest).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "The digest has to be {} bytes long, not {}",
+                    H::DIGEST_LEN,
+                    digest.len()
+                ),
+            )
+        })?;
+
+        Ok(Hasher {
+            state,
+            buffer: Vec::with_capacity(BLOCK_SIZE),
+            length,
+        })
+    }
+
+    /// The number of bytes hashed so far
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+
+        let mut data = data;
+        if !self.buffer.is_empty() {
+            let missing = (BLOCK_SIZE - self.buffer.len()).min(data.len());
+            self.buffer.extend_from_slice(&data[..missing]);
+            data = &data[missing..];
+            if self.buffer.len() < BLOCK_SIZE {
+                return;
+            }
+            self.state.compress(&self.buffer);
+            self.buffer.clear();
+        }
+
+        let mut blocks = data.chunks_exact(BLOCK_SIZE);
+        blocks.by_ref().for_each(|b| self.state.compress(b));
+        self.buffer.extend_from_slice(blocks.remainder());
+    }
+
+    pub fn finalise(mut self) -> Vec<u8> {
+        let padding = md_padding(self.length, H::BIG_ENDIAN);
+        self.buffer.extend(padding);
+        // NOTE: the padding always makes up whole blocks
+        self.buffer
+            .chunks_exact(BLOCK_SIZE)
+            .for_each(|b| self.state.compress(b));
+        self.state.digest()
+    }
+}
+
+impl<H: MerkleDamgard> Default for Hasher<H> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn hash<H: MerkleDamgard, I: CryptographicIter>(message: I) -> Vec<u8> {
+    let mut hasher = Hasher::<H>::new();
+    hasher.update(&message.collect::<Vec<u8>>());
+    hasher.finalise()
+}
+
+/// `H((key ^ opad) || H((key ^ ipad) || message))`, with keys longer than a block hashed first
+pub fn hmac<H: MerkleDamgard, I: CryptographicIter>(key: &[u8], message: I) -> Vec<u8> {
+    let mut key = if key.len() > BLOCK_SIZE {
+        let mut hasher = Hasher::<H>::new();
+        hasher.update(key);
+        hasher.finalise()
+    } else {
+        key.to_vec()
+    };
+    key.resize(BLOCK_SIZE, 0);
+
+    let mut inner = Hasher::<H>::new();
+    inner.update(&key.iter().map(|k| k ^ 0x36).collect::<Vec<u8>>());
+    inner.update(&message.collect::<Vec<u8>>());
+
+    let mut outer = Hasher::<H>::new();
+    outer.update(&key.iter().map(|k| k ^ 0x5C).collect::<Vec<u8>>());
+    outer.update(&inner.finalise());
+    outer.finalise()
+}
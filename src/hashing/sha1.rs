@@ -0,0 +1,68 @@
+use crate::hashing::MerkleDamgard;
+
+/// SHA-1 (FIPS 180-4)
+///
+/// NOTE: it is broken, and only here to be attacked
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sha1 {
+    state: [u32; 5],
+}
+
+impl MerkleDamgard for Sha1 {
+    const DIGEST_LEN: usize = 20;
+    const BIG_ENDIAN: bool = true;
+
+    #[inline]
+    fn initial() -> Self {
+        Sha1 {
+            state: [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0],
+        }
+    }
+
+    fn from_digest(digest: &[u8]) -> Option<Self> {
+        (digest.len() == Self::DIGEST_LEN).then(|| Sha1 {
+            // SAFETY: the digest is exactly 5 words long
+            state: std::array::from_fn(|i| {
+                u32::from_be_bytes(digest[4 * i..4 * i + 4].try_into().unwrap())
+            }),
+        })
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        let mut words = [0u32; 80];
+        for i in 0..80 {
+            words[i] = if i < 16 {
+                // SAFETY: every block is 16 words long
+                u32::from_be_bytes(block[4 * i..4 * i + 4].try_into().unwrap())
+            } else {
+                (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1)
+            };
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = self.state;
+        for (i, w) in words.iter().enumerate() {
+            let (f, k) = match i / 20 {
+                0 => ((b & c) | (!b & d), 0x5A827999),
+                1 => (b ^ c ^ d, 0x6ED9EBA1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let t = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*w);
+            (a, b, c, d, e) = (t, a, b.rotate_left(30), c, d);
+        }
+
+        self.state
+            .iter_mut()
+            .zip([a, b, c, d, e])
+            .for_each(|(s, v)| *s = s.wrapping_add(v));
+    }
+
+    fn digest(&self) -> Vec<u8> {
+        self.state.iter().flat_map(|w| w.to_be_bytes()).collect()
+    }
+}
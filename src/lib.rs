@@ -1,5 +1,8 @@
+pub mod analysis;
 pub mod bitstream;
 pub mod common;
 pub mod compression;
 pub mod cyphers;
+pub mod hashing;
+pub mod random;
 pub mod traits;
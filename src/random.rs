@@ -0,0 +1,152 @@
+use crate::traits::CryptographicIter;
+
+/// A small, fast and seedable generator (SplitMix64), so that everything random in the crate is reproducible.
+///
+/// NOTE: it is not cryptographically secure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // A uniformly distributed number in `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "The range can not be empty");
+        // reject the values that would make the lower numbers more likely
+        let zone = u64::MAX - u64::MAX % n as u64;
+        loop {
+            let v = self.next_u64();
+            if v < zone {
+                return (v % n as u64) as usize;
+            }
+        }
+    }
+
+    // A uniformly distributed number in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn shuffle<T>(&mut self, v: &mut [T]) {
+        for i in (1..v.len()).rev() {
+            v.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+const MT_N: usize = 624;
+const MT_M: usize = 397;
+const MT_MATRIX_A: u32 = 0x9908_B0DF;
+const MT_UPPER_MASK: u32 = 0x8000_0000;
+const MT_LOWER_MASK: u32 = 0x7FFF_FFFF;
+
+/// The 32 bit Mersenne Twister (MT19937), as seeded by the reference implementation.
+///
+/// NOTE: it is not cryptographically secure: every output is a tempered word of the state, so 624 outputs give the
+/// whole state away.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mt19937 {
+    state: [u32; MT_N],
+    // the next word of the state to output. The state is twisted once all of them have been.
+    index: usize,
+}
+
+impl Mt19937 {
+    pub fn new(seed: u32) -> Self {
+        let mut state = [0; MT_N];
+        state[0] = seed;
+        for i in 1..MT_N {
+            state[i] = 1_812_433_253u32
+                .wrapping_mul(state[i - 1] ^ (state[i - 1] >> 30))
+                .wrapping_add(i as u32);
+        }
+        Self { state, index: MT_N }
+    }
+
+    /// A generator whose next 624 outputs are the tempered words of `state`
+    #[inline]
+    pub fn from_state(state: [u32; MT_N]) -> Self {
+        Self { state, index: 0 }
+    }
+
+    #[inline]
+    pub fn state(&self) -> &[u32; MT_N] {
+        &self.state
+    }
+
+    fn twist(&mut self) {
+        for i in 0..MT_N {
+            let y = (self.state[i] & MT_UPPER_MASK) | (self.state[(i + 1) % MT_N] & MT_LOWER_MASK);
+            let mut next = self.state[(i + MT_M) % MT_N] ^ (y >> 1);
+            if y & 1 == 1 {
+                next ^= MT_MATRIX_A;
+            }
+            self.state[i] = next;
+        }
+        self.index = 0;
+    }
+
+    /// Spreads the bits of a word of the state over the whole output
+    #[inline]
+    pub fn temper(mut y: u32) -> u32 {
+        y ^= y >> 11;
+        y ^= (y << 7) & 0x9D2C_5680;
+        y ^= (y << 15) & 0xEFC6_0000;
+        y ^ (y >> 18)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.index >= MT_N {
+            self.twist();
+        }
+        let y = self.state[self.index];
+        self.index += 1;
+        Self::temper(y)
+    }
+
+    /// Every output as 4 bytes, least significant first, to XOR a message with
+    #[inline]
+    pub fn keystream(self) -> Mt19937Keystream {
+        Mt19937Keystream {
+            rng: self,
+            word: [0; 4],
+            position: 4,
+        }
+    }
+}
+
+/// An endless keystream of the outputs of a `Mt19937`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mt19937Keystream {
+    rng: Mt19937,
+    word: [u8; 4],
+    // the next byte of `word`
+    position: usize,
+}
+
+impl Iterator for Mt19937Keystream {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position == 4 {
+            self.word = self.rng.next_u32().to_le_bytes();
+            self.position = 0;
+        }
+        self.position += 1;
+        Some(self.word[self.position - 1])
+    }
+}
+impl CryptographicIter for Mt19937Keystream {}
@@ -1,6 +1,26 @@
 use std::vec::IntoIter;
 
-use crate::cyphers::simple::{CaesarCypherTransform, XorTransform};
+use crate::{
+    bitstream::{BitPadding, BitToByteTransform, ByteToBitTransform},
+    cyphers::{
+        alphabet::{Alphabet, AlphabetTransform, CaesarShift, Direction},
+        bitwise::{BitPermutationTransform, BitXorTransform},
+        block::{BlockCypher, BlockMode, BlockTransform},
+        book::{Book, BookEncoder, Occurrence, Referencing},
+        enigma::{Enigma, EnigmaTransform},
+        fractionating::{
+            Adfgvx, AdfgvxDecryptTransform, AdfgvxTransform, Nihilist, NihilistTransform,
+        },
+        polyalphabetic::{
+            KeyMode, PolyalphabeticCypher, PolyalphabeticTransform, PolyalphabeticVariant,
+        },
+        polybius::{PolybiusSquare, PolybiusTransform},
+        polygraphic::{Chunks, PolygraphicCypher, PolygraphicTransform},
+        simple::{CaesarCypherTransform, XorMode, XorTransform},
+        substitution::{SubstitutionKey, SubstitutionTransform},
+        transposition::{Transposition, TranspositionTransform},
+    },
+};
 
 pub trait Serialisable {
     type CryptoIter;
@@ -28,17 +48,362 @@ pub trait CryptographicIter: Iterator<Item = u8> {
         CaesarCypherTransform::new(self, 0u8.wrapping_sub(shift))
     }
 
+    #[inline]
+    fn caesar_shift_in(
+        self,
+        alphabet: &Alphabet,
+        shift: usize,
+    ) -> AlphabetTransform<Self, CaesarShift>
+    where
+        Self: Sized,
+    {
+        AlphabetTransform::new(self, alphabet, CaesarShift::new(shift, Direction::Encrypt))
+    }
+
+    #[inline]
+    fn caesar_unshift_in(
+        self,
+        alphabet: &Alphabet,
+        shift: usize,
+    ) -> AlphabetTransform<Self, CaesarShift>
+    where
+        Self: Sized,
+    {
+        AlphabetTransform::new(self, alphabet, CaesarShift::new(shift, Direction::Decrypt))
+    }
+
+    #[inline]
+    fn substitute(self, key: &SubstitutionKey) -> SubstitutionTransform<Self>
+    where
+        Self: Sized,
+    {
+        AlphabetTransform::new(self, key.alphabet(), key.cypher(Direction::Encrypt))
+    }
+
+    #[inline]
+    fn unsubstitute(self, key: &SubstitutionKey) -> SubstitutionTransform<Self>
+    where
+        Self: Sized,
+    {
+        AlphabetTransform::new(self, key.alphabet(), key.cypher(Direction::Decrypt))
+    }
+
+    #[inline]
+    fn transpose<T: Transposition>(self, transposition: T) -> TranspositionTransform<Self, T>
+    where
+        Self: Sized,
+    {
+        TranspositionTransform::new(self, transposition, Direction::Encrypt)
+    }
+
+    #[inline]
+    fn untranspose<T: Transposition>(self, transposition: T) -> TranspositionTransform<Self, T>
+    where
+        Self: Sized,
+    {
+        TranspositionTransform::new(self, transposition, Direction::Decrypt)
+    }
+
+    #[inline]
+    fn encrypt_grams<C: PolygraphicCypher>(self, cypher: C) -> PolygraphicTransform<Self, C>
+    where
+        Self: Sized,
+    {
+        PolygraphicTransform::new(self, cypher, Direction::Encrypt)
+    }
+
+    #[inline]
+    fn decrypt_grams<C: PolygraphicCypher>(self, cypher: C) -> PolygraphicTransform<Self, C>
+    where
+        Self: Sized,
+    {
+        PolygraphicTransform::new(self, cypher, Direction::Decrypt)
+    }
+
+    #[inline]
+    fn polybius(self, square: &PolybiusSquare) -> PolybiusTransform<Self>
+    where
+        Self: Sized,
+    {
+        PolybiusTransform::new(self, square.clone(), Direction::Encrypt)
+    }
+
+    #[inline]
+    fn unpolybius(self, square: &PolybiusSquare) -> PolybiusTransform<Self>
+    where
+        Self: Sized,
+    {
+        PolybiusTransform::new(self, square.clone(), Direction::Decrypt)
+    }
+
+    #[inline]
+    fn adfgvx(self, cypher: &Adfgvx) -> AdfgvxTransform<Self>
+    where
+        Self: Sized,
+    {
+        cypher.encrypt(self)
+    }
+
+    #[inline]
+    fn adfgvx_decrypt(self, cypher: &Adfgvx) -> AdfgvxDecryptTransform<Self>
+    where
+        Self: Sized,
+    {
+        cypher.decrypt(self)
+    }
+
+    #[inline]
+    fn nihilist(self, cypher: &Nihilist) -> NihilistTransform<Self>
+    where
+        Self: Sized,
+    {
+        NihilistTransform::new(self, cypher.clone(), Direction::Encrypt)
+    }
+
+    #[inline]
+    fn nihilist_decrypt(self, cypher: &Nihilist) -> NihilistTransform<Self>
+    where
+        Self: Sized,
+    {
+        NihilistTransform::new(self, cypher.clone(), Direction::Decrypt)
+    }
+
+    /// The machine is copied, so that the same one can be used again from its starting positions
+    #[inline]
+    fn enigma(self, machine: &Enigma) -> EnigmaTransform<Self>
+    where
+        Self: Sized,
+    {
+        machine.clone().transform(self)
+    }
+
+    /// Turns the message into references into the book; see `BookDecoder` for the way back
+    #[inline]
+    fn book_encode(
+        self,
+        book: &Book,
+        referencing: Referencing,
+        occurrence: Occurrence,
+    ) -> BookEncoder<'_, Self>
+    where
+        Self: Sized,
+    {
+        BookEncoder::new(self, book, referencing, occurrence)
+    }
+
+    #[inline]
+    fn chunks(self, size: usize) -> Chunks<Self>
+    where
+        Self: Sized,
+    {
+        Chunks::new(self, size)
+    }
+
+    /// The key has to be at least as long as the message; see `XorTransform::error`
     #[inline]
     fn xor<I2: CryptographicIter>(self, key: I2) -> XorTransform<Self, I2>
     where
         Self: Sized,
     {
-        XorTransform::new(self, key)
+        XorTransform::new(self, key, XorMode::Strict)
+    }
+
+    #[inline]
+    fn xor_repeating<I2: CryptographicIter>(self, key: I2) -> XorTransform<Self, I2>
+    where
+        Self: Sized,
+    {
+        XorTransform::new(self, key, XorMode::Repeating)
+    }
+
+    /// Stops at the end of the key, dropping the rest of the message
+    #[inline]
+    fn xor_truncating<I2: CryptographicIter>(self, key: I2) -> XorTransform<Self, I2>
+    where
+        Self: Sized,
+    {
+        XorTransform::new(self, key, XorMode::Truncating)
+    }
+
+    #[inline]
+    fn vigenere<I2: CryptographicIter>(self, key: I2) -> PolyalphabeticTransform<Self, I2>
+    where
+        Self: Sized,
+    {
+        PolyalphabeticCypher::new(
+            key,
+            PolyalphabeticVariant::Vigenere,
+            Direction::Encrypt,
+            KeyMode::Repeating,
+        )
+        .transform(self)
+    }
+
+    #[inline]
+    fn vigenere_decrypt<I2: CryptographicIter>(self, key: I2) -> PolyalphabeticTransform<Self, I2>
+    where
+        Self: Sized,
+    {
+        PolyalphabeticCypher::new(
+            key,
+            PolyalphabeticVariant::Vigenere,
+            Direction::Decrypt,
+            KeyMode::Repeating,
+        )
+        .transform(self)
+    }
+
+    #[inline]
+    fn beaufort<I2: CryptographicIter>(self, key: I2) -> PolyalphabeticTransform<Self, I2>
+    where
+        Self: Sized,
+    {
+        PolyalphabeticCypher::new(
+            key,
+            PolyalphabeticVariant::Beaufort,
+            Direction::Encrypt,
+            KeyMode::Repeating,
+        )
+        .transform(self)
+    }
+
+    #[inline]
+    fn beaufort_decrypt<I2: CryptographicIter>(self, key: I2) -> PolyalphabeticTransform<Self, I2>
+    where
+        Self: Sized,
+    {
+        PolyalphabeticCypher::new(
+            key,
+            PolyalphabeticVariant::Beaufort,
+            Direction::Decrypt,
+            KeyMode::Repeating,
+        )
+        .transform(self)
+    }
+
+    #[inline]
+    fn variant_beaufort<I2: CryptographicIter>(self, key: I2) -> PolyalphabeticTransform<Self, I2>
+    where
+        Self: Sized,
+    {
+        PolyalphabeticCypher::new(
+            key,
+            PolyalphabeticVariant::VariantBeaufort,
+            Direction::Encrypt,
+            KeyMode::Repeating,
+        )
+        .transform(self)
+    }
+
+    #[inline]
+    fn variant_beaufort_decrypt<I2: CryptographicIter>(
+        self,
+        key: I2,
+    ) -> PolyalphabeticTransform<Self, I2>
+    where
+        Self: Sized,
+    {
+        PolyalphabeticCypher::new(
+            key,
+            PolyalphabeticVariant::VariantBeaufort,
+            Direction::Decrypt,
+            KeyMode::Repeating,
+        )
+        .transform(self)
+    }
+
+    #[inline]
+    fn autokey<I2: CryptographicIter>(self, key: I2) -> PolyalphabeticTransform<Self, I2>
+    where
+        Self: Sized,
+    {
+        PolyalphabeticCypher::new(
+            key,
+            PolyalphabeticVariant::Vigenere,
+            Direction::Encrypt,
+            KeyMode::Autokey,
+        )
+        .transform(self)
+    }
+
+    #[inline]
+    fn autokey_decrypt<I2: CryptographicIter>(self, key: I2) -> PolyalphabeticTransform<Self, I2>
+    where
+        Self: Sized,
+    {
+        PolyalphabeticCypher::new(
+            key,
+            PolyalphabeticVariant::Vigenere,
+            Direction::Decrypt,
+            KeyMode::Autokey,
+        )
+        .transform(self)
+    }
+
+    /// Pads the message with PKCS#7; see `BlockTransform::error`
+    #[inline]
+    fn encrypt_blocks<C: BlockCypher>(self, cypher: C, mode: BlockMode) -> BlockTransform<Self, C>
+    where
+        Self: Sized,
+    {
+        BlockTransform::new(self, cypher, mode, Direction::Encrypt)
+    }
+
+    #[inline]
+    fn decrypt_blocks<C: BlockCypher>(self, cypher: C, mode: BlockMode) -> BlockTransform<Self, C>
+    where
+        Self: Sized,
+    {
+        BlockTransform::new(self, cypher, mode, Direction::Decrypt)
+    }
+
+    #[inline]
+    fn bits(self) -> ByteToBitTransform<Self>
+    where
+        Self: Sized,
+    {
+        ByteToBitTransform::new(self)
     }
 }
 
 impl CryptographicIter for IntoIter<u8> {}
 
+pub trait BitIter: Iterator<Item = bool> {
+    #[inline]
+    fn bytes(self, padding: BitPadding) -> BitToByteTransform<Self>
+    where
+        Self: Sized,
+    {
+        BitToByteTransform::new(self, padding)
+    }
+
+    #[inline]
+    fn xor_bits<I2: BitIter>(self, key: I2) -> BitXorTransform<Self, I2>
+    where
+        Self: Sized,
+    {
+        BitXorTransform::new(self, key)
+    }
+
+    #[inline]
+    fn permute_bits(self, permutation: Vec<usize>) -> BitPermutationTransform<Self>
+    where
+        Self: Sized,
+    {
+        BitPermutationTransform::new(self, permutation)
+    }
+
+    #[inline]
+    fn unpermute_bits(self, permutation: Vec<usize>) -> BitPermutationTransform<Self>
+    where
+        Self: Sized,
+    {
+        BitPermutationTransform::new_inverse(self, permutation)
+    }
+}
+
+impl BitIter for IntoIter<bool> {}
+
 pub trait InspectableState {
     fn inspect_state(&self) -> String;
 }
@@ -0,0 +1,1005 @@
+#![allow(clippy::items_after_test_module)]
+
+use cryptography_whiteboard::{
+    analysis::{
+        brute_force::Candidate,
+        identify::{identify, Hypothesis},
+        length_extension::{extend_length, extend_length_guessing, MacScheme, MacVerifier},
+        periodic::PeriodicBreak,
+        scoring::{NGramScorer, Scorer},
+        search::{KeySpace, Search, SubstitutionSpace},
+    },
+    hashing::MerkleDamgard,
+    traits::{CryptographicIter, InspectableState, Serialisable},
+};
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_brute_force_raw, test_identify_raw, test_length_extension_raw, test_periodic_raw,
+        test_scorer_raw, test_search_raw,
+    };
+    use cryptography_whiteboard::{
+        analysis::{
+            brute_force::{break_caesar, break_single_byte_xor},
+            crib::{drag_crib, CribSession},
+            ecb::{break_ecb_suffix, detect_ecb, BlockRepetition},
+            identify::{identify, Hypothesis},
+            length_extension::{extend_length, MacScheme, MacVerifier},
+            mersenne::{
+                clone_mt19937, clone_mt19937_keystream, recover_keystream_seed, recover_seed,
+                untemper,
+            },
+            padding_oracle::{padding_oracle_decrypt, padding_oracle_forge, PaddingOracle},
+            periodic::{break_repeating_xor, break_vigenere},
+            scoring::{
+                ChiSquaredScorer, NGramScorer, NGramTable, PrintableRatio, Scorer,
+                ENGLISH_LETTER_FREQUENCIES,
+            },
+            search::{ColumnarSpace, KeySpace, Search, Strategy, SubstitutionSpace},
+            statistics::{
+                columns, entropy, hamming_distance, index_of_coincidence,
+                periodic_index_of_coincidence, repeat_distances,
+            },
+        },
+        common::to_hex,
+        cyphers::{
+            alphabet::Alphabet,
+            block::{BlockMode, Feistel},
+            fractionating::{Adfgvx, Nihilist},
+            polybius::PolybiusSquare,
+            polygraphic::Playfair,
+            substitution::SubstitutionKey,
+            transposition::{ColumnarKey, Padding},
+        },
+        hashing::{md5::Md5, sha1::Sha1, sha256::Sha256},
+        random::{Mt19937, SplitMix64},
+        traits::{CryptographicIter, InspectableState, Serialisable},
+    };
+
+    pub const TEST_MESSAGES: [&str; 4] = [
+        "AABAC",
+        "",
+        "Give a man a fish and you feed him for a day.
+        Teach a man to fish and you feed him for a lifetime.",
+        "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Nulla dignissim turpis sit amet turpis mattis consectetur. In venenatis nunc tortor, quis laoreet enim hendrerit id. Donec in tortor at tortor ullamcorper hendrerit sit amet non quam. Nunc faucibus neque iaculis felis commodo, eu aliquet ex dignissim. Nullam feugiat luctus libero id mollis. Proin vitae efficitur massa. Mauris in accumsan sem. Maecenas tristique, libero nec mattis tincidunt, ligula orci dignissim felis, vel malesuada est lectus sollicitudin elit.
+
+        Vestibulum purus nisl, ornare nec odio sit amet, porta volutpat arcu. Morbi risus massa, mattis eget metus et, cursus suscipit ante. Ut accumsan, risus sed ultrices faucibus, nulla orci cursus est, vel viverra lacus augue ac libero. Aliquam erat volutpat. Aliquam sit amet nisi commodo, tincidunt ex eu, tempor sapien. Maecenas eget nisl eget eros ullamcorper tristique. Aenean facilisis posuere mauris. Pellentesque quis feugiat massa, at elementum nulla. Mauris lobortis posuere libero sit amet gravida.
+
+        Integer interdum justo vitae elit laoreet, nec efficitur magna commodo. Nam semper vitae tortor id consectetur. Aenean ac rutrum leo. In mauris sem, aliquet et dapibus nec, ultrices ac libero. Integer sit amet orci eget est condimentum dapibus. Praesent vel ultrices quam. Nam consequat ligula eu molestie hendrerit. Duis tincidunt, nisi efficitur hendrerit facilisis, erat lacus ultricies ligula, et tempor elit nibh nec metus. Vivamus suscipit libero arcu, non iaculis nisi maximus vitae. Proin tincidunt vestibulum justo ac ultrices.",
+    ];
+
+    pub const ENGLISH_MESSAGES: [&str; 3] = [
+        "Give a man a fish and you feed him for a day.
+        Teach a man to fish and you feed him for a lifetime.",
+        "The enemy will attack the northern bridge at first light, so hold the line until reinforcements arrive.",
+        "It was a bright cold day in April, and the clocks were striking thirteen.",
+    ];
+
+    // long enough for a stochastic search to settle on the right key
+    pub const LONG_ENGLISH_MESSAGE: &str = "The workshop started late because the screen refused to talk to \
+        anybody's laptop, so the first hour was spent on paper. Everyone got a sheet with a short message on it, \
+        written in a simple substitution, and a pencil. Most of the groups began by counting letters, and soon \
+        found that the most common symbol was probably an E and that a three letter word that kept coming back \
+        was very likely to be THE. From there the rest of the alphabet fell into place one guess at a time, \
+        although a couple of rare letters stayed a mystery until the very end. By the time the screen finally \
+        worked, half of the room had already read the hidden message, which asked them to please bring back the \
+        pencils before going home for the evening.";
+
+    #[test]
+    fn test_scorers() {
+        for message in ENGLISH_MESSAGES {
+            test_scorer_raw(&NGramScorer::english_unigrams(), message);
+            test_scorer_raw(&NGramScorer::english_bigrams(), message);
+            test_scorer_raw(&NGramScorer::english_quadgrams(), message);
+            test_scorer_raw(&ChiSquaredScorer::english(), message);
+        }
+    }
+
+    #[test]
+    fn test_printable_ratio() {
+        assert_eq!(
+            PrintableRatio.score(String::from("Hello,\nworld!").serialise()),
+            1.0
+        );
+        assert_eq!(
+            PrintableRatio.score(vec![b'a', 0, b'b', 0xff].into_iter()),
+            0.5
+        );
+        assert_eq!(PrintableRatio.score(Vec::new().into_iter()), 0.0);
+
+        let garbage = String::from(ENGLISH_MESSAGES[0])
+            .serialise()
+            .xor_repeating(vec![0x80, 0x13].into_iter());
+        assert!(PrintableRatio.score(garbage) < 0.5);
+    }
+
+    #[test]
+    fn test_ngram_garbage() {
+        // a few letters among binary garbage must not look like English
+        let quadgrams = NGramScorer::english_quadgrams();
+        let english = quadgrams.score(String::from(ENGLISH_MESSAGES[1]).serialise());
+        let garbage = quadgrams.score(vec![0, 1, 2, b'T', b'H', b'E', b'N', 0xfe, 3].into_iter());
+        assert!(english > garbage);
+
+        // punctuation is skipped like whitespace, with the dense table or without it
+        for scorer in [
+            NGramScorer::english_unigrams(),
+            quadgrams,
+            NGramScorer::new(NGramTable::english(5)),
+        ] {
+            assert_eq!(
+                scorer.score(String::from("HELLO, WORLD.").serialise()),
+                scorer.score(String::from("HELLO WORLD").serialise())
+            );
+            // unless it is strict
+            let strict = scorer.strict();
+            assert!(
+                strict.score(String::from("HELLO, WORLD.").serialise())
+                    < strict.score(String::from("HELLO WORLD").serialise())
+            );
+        }
+    }
+
+    #[test]
+    fn test_chi_squared() {
+        let chi_squared = ChiSquaredScorer::english();
+        assert_eq!(
+            chi_squared.score(String::from("1234 !?").serialise()),
+            f64::NEG_INFINITY
+        );
+        assert!(
+            chi_squared.score(String::from("ETAOIN").serialise())
+                > chi_squared.score(String::from("ZQXJKV").serialise())
+        );
+
+        let mut frequencies = ENGLISH_LETTER_FREQUENCIES;
+        frequencies[25] = 0.0;
+        assert!(ChiSquaredScorer::new(frequencies).is_err());
+    }
+
+    #[test]
+    fn test_ngram_table() {
+        let table = NGramTable::parse(
+            "# a made up language where everything is an A or a B
+            AA 6
+            ab 2
+
+            BA 2",
+        )
+        .unwrap();
+        assert_eq!(table.n(), 2);
+        assert!((table.log_probability(b"AA") - 0.6f64.log10()).abs() < 1e-9);
+        assert!(table.log_probability(b"BB") < table.log_probability(b"AB"));
+        // anything that is not made of uppercase letters was never seen
+        for ngram in [&b"ab"[..], b"A!", b"\xff\x00", b"A"] {
+            assert_eq!(table.log_probability(ngram), table.log_probability(b"BB"));
+        }
+
+        let scorer = NGramScorer::new(table.clone());
+        assert!(
+            scorer.score(String::from("aaaa").serialise())
+                > scorer.score(String::from("bbbb").serialise())
+        );
+
+        let path = std::env::temp_dir().join(format!("ngrams-test-{}.txt", std::process::id()));
+        std::fs::write(&path, "AA 6\nAB 2\nBA 2\n").unwrap();
+        let from_file = NGramTable::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            from_file.unwrap(),
+            NGramTable::parse("aa 6\nAB 2\nBA 2").unwrap()
+        );
+
+        let counted = NGramTable::from_corpus(b"a-a, b a", 2).unwrap();
+        assert_eq!(counted, NGramTable::parse("AA 1\nAB 1\nBA 1").unwrap());
+
+        assert_eq!(NGramTable::english(3).n(), 3);
+        assert!(NGramTable::parse("").is_err());
+        assert!(NGramTable::parse("AB 1\nABC 2").is_err());
+        assert!(NGramTable::parse("A1 3").is_err());
+        assert!(NGramTable::parse("AB three").is_err());
+        assert!(NGramTable::parse("AB 1 2").is_err());
+        assert!(NGramTable::from_corpus(b"abc", 0).is_err());
+        assert!(NGramTable::from_file("/nonexistent/ngrams.txt").is_err());
+    }
+
+    #[test]
+    fn test_caesar_breaker() {
+        for message in TEST_MESSAGES {
+            test_brute_force_raw(message, 3, |m, k| m.caesar_shift(k).collect(), break_caesar);
+        }
+    }
+
+    #[test]
+    fn test_single_byte_xor_breaker() {
+        for message in TEST_MESSAGES {
+            test_brute_force_raw(
+                message,
+                0x5a,
+                |m, k| m.xor_repeating(vec![k].into_iter()).collect(),
+                break_single_byte_xor,
+            );
+        }
+
+        let scorer = NGramScorer::english_quadgrams();
+        let candidates = break_single_byte_xor(
+            String::from(ENGLISH_MESSAGES[1])
+                .serialise()
+                .xor_repeating(vec![0x17].into_iter()),
+            &scorer,
+            5,
+        );
+        assert_eq!(candidates.len(), 5);
+        assert!(candidates.windows(2).all(|w| w[0].score() >= w[1].score()));
+        assert_eq!(candidates[0].plaintext(), ENGLISH_MESSAGES[1].as_bytes());
+    }
+    #[test]
+    fn test_statistics() {
+        assert_eq!(hamming_distance(b"this is a test", b"wokka wokka!!!"), 37);
+        assert_eq!(index_of_coincidence(b"AABB"), 4.0 / 12.0);
+        assert_eq!(index_of_coincidence(b"A"), 0.0);
+        assert_eq!(periodic_index_of_coincidence(b"ABABAB", 2), 1.0);
+        assert_eq!(columns(b"ABCDE", 2), vec![b"ACE".to_vec(), b"BD".to_vec()]);
+        assert_eq!(repeat_distances(b"THEXXTHEYYYYTHE", 3), vec![5, 1, 7]);
+        assert_eq!(entropy(b"AAAA"), 0.0);
+        assert_eq!(entropy(b"ABCD"), 2.0);
+        assert_eq!(entropy(&(0..=255).collect::<Vec<u8>>()), 8.0);
+
+        // English letters are far more likely to coincide than random ones
+        let letters: Vec<u8> = ENGLISH_MESSAGES
+            .concat()
+            .bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|b| b.to_ascii_uppercase())
+            .collect();
+        assert!(index_of_coincidence(&letters) > 0.055);
+    }
+
+    #[test]
+    fn test_repeating_xor_breaker() {
+        // NOTE: the columns of shorter messages have too few symbols to solve on their own
+        for key in ["ICE", "whiteboard"] {
+            test_periodic_raw(
+                LONG_ENGLISH_MESSAGE,
+                key,
+                |m, k| m.xor_repeating(k).collect(),
+                break_repeating_xor,
+            );
+        }
+    }
+
+    #[test]
+    fn test_vigenere_breaker() {
+        for key in ["LEMON", "KRYPTOS"] {
+            test_periodic_raw(
+                LONG_ENGLISH_MESSAGE,
+                key,
+                |m, k| m.vigenere(k).letters_only().collect(),
+                break_vigenere,
+            );
+        }
+
+        let scorer = NGramScorer::english_unigrams();
+        let ciphertext = String::from(LONG_ENGLISH_MESSAGE)
+            .serialise()
+            .vigenere(String::from("LEMON").serialise())
+            .letters_only();
+        let result = break_vigenere(ciphertext, &scorer, 12, 3);
+        assert_eq!(result.key_lengths()[0].length(), 5);
+        assert!(result.key_lengths()[0].hamming().is_none());
+        assert!(result.candidates().len() <= 3);
+
+        let trace = result.inspect_state();
+        assert!(trace.contains("Kasiski"));
+        assert!(trace.contains("Key length 5"));
+        assert!(trace.contains("Best key \"LEMON\""));
+    }
+
+    #[test]
+    fn test_substitution_search() {
+        let key = SubstitutionKey::random(&Alphabet::letters(), 42);
+        let ciphertext = String::from(LONG_ENGLISH_MESSAGE)
+            .serialise()
+            .substitute(&key);
+        let space = SubstitutionSpace::new(ciphertext, &Alphabet::letters());
+
+        test_search_raw(
+            &space,
+            Search::new(Strategy::HillClimbing, 1)
+                .with_restarts(5)
+                .with_iterations(3000),
+        );
+        test_search_raw(
+            &space,
+            Search::new(Strategy::simulated_annealing(0.05, 0.0005).unwrap(), 2)
+                .with_restarts(3)
+                .with_iterations(4000),
+        );
+
+        // the neighbours stay valid keys
+        let mut rng = SplitMix64::new(7);
+        let start = space.random_key(&mut rng);
+        let neighbour = space.neighbour(&start, &mut rng);
+        assert_eq!(
+            start
+                .permutation()
+                .iter()
+                .zip(neighbour.permutation())
+                .filter(|(a, b)| a != b)
+                .count()
+                % 2,
+            0
+        );
+    }
+
+    #[test]
+    fn test_columnar_search() {
+        let key = ColumnarKey::from_keyword("ZEBRAS", Padding::Irregular).unwrap();
+        let ciphertext: Vec<u8> = String::from(ENGLISH_MESSAGES[1])
+            .serialise()
+            .transpose(key)
+            .collect();
+
+        let scorer = NGramScorer::english_quadgrams();
+        let search = Search::new(Strategy::HillClimbing, 3)
+            .with_iterations(300)
+            .with_restarts(3);
+        // the width is unknown too, so every width is searched and the best result kept
+        let best = (2..=8)
+            .map(|width| {
+                let space = ColumnarSpace::new(ciphertext.clone().into_iter(), width).unwrap();
+                search.run(&space, &scorer).swap_remove(0)
+            })
+            .max_by(|a, b| a.score().total_cmp(&b.score()))
+            .unwrap();
+        assert_eq!(best.plaintext(), ENGLISH_MESSAGES[1].as_bytes());
+        assert_eq!(best.key().columns(), &[4, 2, 1, 3, 5, 0]);
+
+        let space = ColumnarSpace::new(ciphertext.clone().into_iter(), 6).unwrap();
+        let key = ColumnarKey::from_keyword("ZEBRAS", Padding::Irregular).unwrap();
+        assert_eq!(space.decrypt(&key), ENGLISH_MESSAGES[1].as_bytes());
+        assert!(ColumnarSpace::new(ciphertext.into_iter(), 0).is_err());
+    }
+
+    #[test]
+    fn test_search_is_reproducible() {
+        let ciphertext = String::from(ENGLISH_MESSAGES[0]).serialise().substitute(
+            &SubstitutionKey::from_keyword(&Alphabet::letters(), "WHITEBOARD"),
+        );
+        let space = SubstitutionSpace::new(ciphertext, &Alphabet::letters());
+        let scorer = NGramScorer::english_quadgrams();
+        let search = Search::new(Strategy::HillClimbing, 99)
+            .with_iterations(200)
+            .with_restarts(3);
+
+        let first = search.run(&space, &scorer);
+        assert_eq!(first, search.run(&space, &scorer));
+        assert_eq!(first.len(), 3);
+        assert!(first.windows(2).all(|w| w[0].score() >= w[1].score()));
+        assert_eq!(
+            search.clone().with_restarts(0).run(&space, &scorer).len(),
+            1
+        );
+
+        // the temperatures of annealing have to be positive and finite
+        assert!(Strategy::simulated_annealing(0.0, 0.001).is_err());
+        assert!(Strategy::simulated_annealing(1.0, 0.0).is_err());
+        assert!(Strategy::simulated_annealing(-1.0, 0.001).is_err());
+        assert!(Strategy::simulated_annealing(f64::INFINITY, 0.001).is_err());
+        assert!(Strategy::simulated_annealing(f64::NAN, 0.001).is_err());
+        let strategy = Strategy::simulated_annealing(1.0, 0.001).unwrap();
+        let Strategy::SimulatedAnnealing(annealing) = strategy else {
+            panic!("The wrong strategy was built");
+        };
+        assert_eq!((annealing.start(), annealing.end()), (1.0, 0.001));
+    }
+    #[test]
+    fn test_identify() {
+        let message = || String::from(LONG_ENGLISH_MESSAGE).serialise();
+        let columnar = || ColumnarKey::from_keyword("ZEBRAS", Padding::Irregular).unwrap();
+
+        test_identify_raw(message(), Hypothesis::Plaintext);
+        test_identify_raw(message().transpose(columnar()), Hypothesis::Transposition);
+        test_identify_raw(
+            message().substitute(&SubstitutionKey::random(&Alphabet::letters(), 4)),
+            Hypothesis::Monoalphabetic,
+        );
+        test_identify_raw(
+            message()
+                .vigenere(String::from("LEMON").serialise())
+                .letters_only(),
+            Hypothesis::Periodic { period: 5 },
+        );
+        test_identify_raw(
+            message()
+                .vigenere(String::from("KRYPTOS").serialise())
+                .letters_only(),
+            Hypothesis::Periodic { period: 7 },
+        );
+        test_identify_raw(
+            message()
+                .autokey(String::from("QUEENLY").serialise())
+                .letters_only(),
+            Hypothesis::Aperiodic,
+        );
+        test_identify_raw(
+            message().encrypt_grams(Playfair::latin("PLAYFAIR EXAMPLE")),
+            Hypothesis::Digraphic,
+        );
+        test_identify_raw(
+            message().xor_repeating(String::from("whiteboard").serialise()),
+            Hypothesis::Binary,
+        );
+        test_identify_raw(
+            message().adfgvx(&Adfgvx::new(PolybiusSquare::adfgvx("PRIVACY"), columnar())),
+            Hypothesis::Fractionated,
+        );
+        test_identify_raw(
+            message().polybius(&PolybiusSquare::numeric("KEY")),
+            Hypothesis::Polybius,
+        );
+        test_identify_raw(
+            message().nihilist(&Nihilist::latin("ZEBRAS", "RUSSIAN").unwrap()),
+            Hypothesis::Nihilist,
+        );
+    }
+
+    #[test]
+    fn test_identify_report() {
+        let identification = identify(
+            String::from(LONG_ENGLISH_MESSAGE)
+                .serialise()
+                .vigenere(String::from("LEMON").serialise())
+                .letters_only(),
+        );
+        assert_eq!(identification.letters(), 576);
+        assert!(identification.even_length());
+        assert!(identification.index_of_coincidence() < 0.05);
+        assert!(identification.periodic_index_of_coincidence()[4] > 0.06);
+        assert!(identification.entropy() > 4.0);
+        assert!(identification.alphabet_size() > 26);
+
+        let report = identification.inspect_state();
+        assert!(report.contains("576 letters"));
+        assert!(report.contains("Vigenère period 5"));
+        assert_eq!(
+            Hypothesis::Periodic { period: 5 }.to_string(),
+            "Vigenère period 5"
+        );
+
+        let empty = identify(Vec::new().into_iter());
+        assert_eq!(empty.best(), None);
+        assert_eq!(empty.symbols(), 0);
+    }
+
+    #[test]
+    fn test_crib_dragging() {
+        let mut rng = SplitMix64::new(5);
+        let keystream: Vec<u8> = (0..128).map(|_| rng.next_u64() as u8).collect();
+        let encrypt = |message: &str| -> Vec<u8> {
+            String::from(message)
+                .serialise()
+                .xor_truncating(keystream.clone().into_iter())
+                .collect()
+        };
+        let (m1, m2) = (ENGLISH_MESSAGES[1], ENGLISH_MESSAGES[2]);
+        let (c1, c2) = (encrypt(m1), encrypt(m2));
+
+        let scorer = NGramScorer::english_bigrams().strict();
+        let xored = || {
+            c1.clone()
+                .into_iter()
+                .xor_truncating(c2.clone().into_iter())
+        };
+        for crib in ["northern bridge", "attack"] {
+            let offset = m1.find(crib).unwrap();
+            let candidates = drag_crib(xored(), crib.as_bytes(), &scorer, 3);
+            assert_eq!(*candidates[0].key(), offset);
+            assert_eq!(
+                candidates[0].plaintext(),
+                &m2.as_bytes()[offset..offset + crib.len()]
+            );
+        }
+        assert!(drag_crib(xored(), &[0; 200], &scorer, 3).is_empty());
+        assert!(drag_crib(xored(), b"", &scorer, 3).is_empty());
+    }
+
+    #[test]
+    fn test_crib_session() {
+        let mut rng = SplitMix64::new(6);
+        let keystream: Vec<u8> = (0..128).map(|_| rng.next_u64() as u8).collect();
+        let ciphertexts: Vec<Vec<u8>> = ENGLISH_MESSAGES[1..]
+            .iter()
+            .map(|m| {
+                String::from(*m)
+                    .serialise()
+                    .xor_truncating(keystream.clone().into_iter())
+                    .collect()
+            })
+            .collect();
+        let (m1, m2) = (ENGLISH_MESSAGES[1], ENGLISH_MESSAGES[2]);
+
+        assert!(CribSession::new(ciphertexts[..1].to_vec()).is_err());
+        let mut session = CribSession::new(ciphertexts.clone()).unwrap();
+        assert_eq!(session.known(), 0);
+        assert!(session.inspect_state().contains("2: ____"));
+
+        // a guess in the second message reveals the first one at the same place
+        let candidates = session
+            .drag(1, b"bright cold day", &NGramScorer::english_bigrams(), 1)
+            .unwrap();
+        let offset = *candidates[0].key();
+        assert_eq!(offset, m2.find("bright").unwrap());
+        session.place(1, offset, b"bright cold day").unwrap();
+        assert_eq!(session.known(), 15);
+        assert_eq!(
+            session.plaintext(0).unwrap()[offset..offset + 15]
+                .iter()
+                .map(|b| b.unwrap())
+                .collect::<Vec<u8>>(),
+            &m1.as_bytes()[offset..offset + 15]
+        );
+
+        // which is extended, one guess at a time
+        session.place(0, 0, b"The enemy will ").unwrap();
+        session.place(1, 0, m2.as_bytes()).unwrap();
+        session.place(0, 0, m1.as_bytes()).unwrap();
+        assert_eq!(session.known(), m1.len());
+        assert_eq!(
+            session.keystream()[..m1.len()]
+                .iter()
+                .map(|k| k.unwrap())
+                .collect::<Vec<u8>>(),
+            &keystream[..m1.len()]
+        );
+        assert_eq!(
+            session.inspect_state(),
+            format!(
+                "{} of {} keystream bytes known, 4 guesses\n1: {}\n2: {}",
+                m1.len(),
+                m1.len(),
+                m1,
+                m2
+            )
+        );
+
+        // a third message with the same keystream can be read too
+        let third: Vec<u8> = String::from("Attack at dawn")
+            .serialise()
+            .xor_truncating(keystream.clone().into_iter())
+            .collect();
+        let decrypted: Vec<u8> = session
+            .decrypt(third.into_iter())
+            .into_iter()
+            .map(|b| b.unwrap())
+            .collect();
+        assert_eq!(decrypted, b"Attack at dawn");
+
+        assert!(session.place(1, m2.len() - 2, b"abc").is_err());
+        assert!(session.place(2, 0, b"abc").is_err());
+        let scorer = NGramScorer::english_bigrams();
+        assert_eq!(
+            session.drag(2, b"abc", &scorer, 1).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            session.plaintext(2).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert!(session.undo());
+        assert!(session.undo());
+        assert_eq!(session.known(), 24);
+        assert!(session.undo());
+        assert!(session.undo());
+        assert_eq!(session.known(), 0);
+        assert!(!session.undo());
+    }
+
+    #[test]
+    fn test_ecb_detection() {
+        let cypher = Feistel::new(b"YELLOW SUBMARINE");
+        let mut message = b"YELLOW SUBMARINE".repeat(3);
+        // NOTE: the repeated blocks have to line up with the blocks of the cypher
+        message.extend_from_slice(
+            &LONG_ENGLISH_MESSAGE.as_bytes()[..LONG_ENGLISH_MESSAGE.len() / 16 * 16],
+        );
+        message.extend_from_slice(&b"YELLOW SUBMARINE".repeat(2));
+        let encrypt = |mode: BlockMode| -> Vec<u8> {
+            message
+                .clone()
+                .into_iter()
+                .encrypt_blocks(&cypher, mode)
+                .collect()
+        };
+
+        let detection = detect_ecb(encrypt(BlockMode::Ecb).into_iter(), 64);
+        assert!(detection.is_ecb());
+        let best = detection.best().unwrap();
+        assert_eq!(best.block_size(), 16);
+        assert_eq!(best.repeated(), 4);
+        assert_eq!(best.heatmap()[..4], [5, 5, 5, 1]);
+        let len = encrypt(BlockMode::Ecb).len();
+        assert!(detection
+            .guesses()
+            .iter()
+            .all(|g| g.block_size() >= 8 && len % g.block_size() == 0));
+
+        let report = detection.inspect_state();
+        assert!(report.contains("Block size 16: 4 of"));
+        assert!(report.contains("Heatmap for block size 16\n     0 AAA............."));
+
+        let detection = detect_ecb(encrypt(BlockMode::Cbc { iv: vec![0; 16] }).into_iter(), 64);
+        assert!(!detection.is_ecb());
+        assert!(!detection.inspect_state().contains("Heatmap"));
+
+        let repetition = BlockRepetition::new(b"ABABCDAB", 2);
+        assert_eq!(repetition.blocks(), 4);
+        assert_eq!(repetition.repeated(), 2);
+        assert_eq!(repetition.heatmap(), &[3, 3, 1, 3]);
+        assert_eq!(repetition.fraction(), 0.5);
+    }
+
+    #[test]
+    fn test_ecb_oracle_attack() {
+        let cypher = Feistel::new(b"an unknown key");
+        let secret = ENGLISH_MESSAGES[1].as_bytes();
+        let oracle = |input: &[u8]| -> Vec<u8> {
+            let mut message = input.to_vec();
+            message.extend_from_slice(secret);
+            message
+                .into_iter()
+                .encrypt_blocks(&cypher, BlockMode::Ecb)
+                .collect()
+        };
+
+        let attack = break_ecb_suffix(oracle).unwrap();
+        assert_eq!(attack.block_size(), 16);
+        assert_eq!(attack.secret(), secret);
+        assert!(attack.queries() < 256 * secret.len());
+        let report = attack.inspect_state();
+        assert!(report.starts_with("Block size 16, and a secret of 103 bytes"));
+        assert!(report.contains("Block 1: \"The enemy will a\""));
+        assert!(report.ends_with(&format!("with {} queries", attack.queries())));
+
+        // chaining hides the repeated blocks the attack needs
+        let oracle = |input: &[u8]| -> Vec<u8> {
+            let mut message = input.to_vec();
+            message.extend_from_slice(secret);
+            message
+                .into_iter()
+                .encrypt_blocks(&cypher, BlockMode::Cbc { iv: vec![0; 16] })
+                .collect()
+        };
+        assert!(break_ecb_suffix(oracle).is_err());
+        assert!(break_ecb_suffix(|input: &[u8]| input.to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_padding_oracle_decrypt() {
+        let oracle = PaddingOracle::new(Feistel::new(b"server key"));
+        for message in ENGLISH_MESSAGES.iter().chain(&["", "sixteen byte msg"]) {
+            let ciphertext = oracle
+                .encrypt(String::from(*message).serialise(), vec![9; 16])
+                .unwrap();
+            let attack = padding_oracle_decrypt(|c| oracle.check(c), &ciphertext, 16).unwrap();
+            assert_eq!(attack.output(), message.as_bytes());
+            assert_eq!(attack.block_size(), 16);
+            assert_eq!(attack.intermediates().len(), ciphertext.len() / 16 - 1);
+            // at most 256 guesses, and a check of the last byte, for every byte
+            assert!(attack.queries() <= 257 * (ciphertext.len() - 16));
+        }
+
+        let ciphertext = oracle
+            .encrypt(String::from("Attack at dawn").serialise(), vec![0; 16])
+            .unwrap();
+        let attack = padding_oracle_decrypt(|c| oracle.check(c), &ciphertext, 16).unwrap();
+        let report = attack.inspect_state();
+        assert!(report.starts_with("Decrypting 1 blocks of 16 bytes\nBlock 1: intermediate "));
+        assert!(report.contains("plaintext \"Attack at dawn\\u{2}\\u{2}\""));
+        assert!(report.ends_with(&format!(
+            "Decrypted 14 bytes with {} queries",
+            attack.queries()
+        )));
+
+        assert!(padding_oracle_decrypt(|c| oracle.check(c), &ciphertext[..20], 16).is_err());
+        assert!(padding_oracle_decrypt(|c| oracle.check(c), &ciphertext[..16], 16).is_err());
+        assert!(padding_oracle_decrypt(|_| false, &ciphertext, 16).is_err());
+        assert!(padding_oracle_decrypt(|_| true, &[0; 512], 256).is_err());
+    }
+
+    #[test]
+    fn test_padding_oracle_forge() {
+        let oracle = PaddingOracle::new(Feistel::new(b"server key"));
+        for message in ENGLISH_MESSAGES.iter().chain(&["", "sixteen byte msg"]) {
+            let attack = padding_oracle_forge(|c| oracle.check(c), message.as_bytes(), 16).unwrap();
+            assert_eq!(oracle.decrypt(attack.output()).unwrap(), message.as_bytes());
+            assert_eq!(
+                attack.output().len(),
+                16 * (attack.intermediates().len() + 1)
+            );
+            // the last block is chosen, and all zeroes
+            assert!(attack.output()[attack.output().len() - 16..]
+                .iter()
+                .all(|&b| b == 0));
+        }
+
+        let attack = padding_oracle_forge(|c| oracle.check(c), b"admin=true", 16).unwrap();
+        assert!(attack
+            .inspect_state()
+            .starts_with("Forging 1 blocks of 16 bytes\nBlock 1: intermediate "));
+        assert!(padding_oracle_forge(|c| oracle.check(c), b"", 0).is_err());
+
+        let oracle = PaddingOracle::new(Feistel::new(b"key"));
+        assert!(oracle
+            .encrypt(vec![1, 2, 3].into_iter(), vec![0; 4])
+            .is_err());
+        assert!(oracle.decrypt(&[0; 8]).is_err());
+        assert_eq!(oracle.check(&[0; 32]), oracle.decrypt(&[0; 32]).is_ok());
+    }
+
+    #[test]
+    fn test_length_extension() {
+        test_length_extension_raw::<Md5>();
+        test_length_extension_raw::<Sha1>();
+        test_length_extension_raw::<Sha256>();
+
+        let verifier = MacVerifier::<Sha1>::new(b"key", MacScheme::SecretPrefix);
+        let mac = verifier.sign(b"abc");
+        let forgery = extend_length::<Sha1>(&mac, 3, b"abc", b"d").unwrap();
+        // the glue is the padding of the 6 bytes that were hashed, so the forgery is 64 bytes long but for `d`
+        assert_eq!(forgery.glue().len(), 58);
+        assert_eq!(forgery.message().len(), 3 + 58 + 1);
+        assert_eq!(forgery.secret_len(), 3);
+        assert_eq!(
+            forgery.inspect_state(),
+            format!(
+                "Secret of 3 bytes\nGlue padding 80{}0000000000000030\nForged message {:?}\nForged MAC {}",
+                "00".repeat(49),
+                String::from_utf8_lossy(forgery.message()),
+                to_hex(forgery.mac())
+            )
+        );
+        assert!(extend_length::<Sha1>(&mac[..10], 3, b"abc", b"d").is_err());
+    }
+
+    #[test]
+    fn test_mt19937_untemper() {
+        let mut rng = SplitMix64::new(8);
+        for y in [0, 1, u32::MAX, 0x8000_0000, 0x1234_5678]
+            .into_iter()
+            .chain((0..1000).map(|_| rng.next_u64() as u32))
+        {
+            assert_eq!(untemper(Mt19937::temper(y)), y);
+        }
+    }
+
+    #[test]
+    fn test_mt19937_clone() {
+        let mut rng = Mt19937::new(0xC0FFEE);
+        let outputs: Vec<u32> = (0..700).map(|_| rng.next_u32()).collect();
+
+        // the copy carries on where the outputs stop, in step with the original
+        let mut clone = clone_mt19937(&outputs).unwrap();
+        for _ in 0..2000 {
+            assert_eq!(clone.next_u32(), rng.next_u32());
+        }
+        let mut clone = clone_mt19937(&outputs[..624]).unwrap();
+        assert_eq!(clone.next_u32(), outputs[624]);
+        // the outputs do not have to line up with a twist
+        let mut rng = Mt19937::new(0xC0FFEE);
+        let outputs: Vec<u32> = (0..724).map(|_| rng.next_u32()).collect();
+        let mut clone = clone_mt19937(&outputs[100..724]).unwrap();
+        for _ in 0..2000 {
+            assert_eq!(clone.next_u32(), rng.next_u32());
+        }
+
+        assert!(clone_mt19937(&outputs[..623]).is_err());
+        // the outputs after the first 624 are checked against the copy
+        let mut tampered = outputs.clone();
+        tampered[650] ^= 1;
+        assert!(clone_mt19937(&tampered).is_err());
+
+        // a keystream recovered from a known plaintext gives the generator away too
+        let plaintext = String::from(LONG_ENGLISH_MESSAGE).repeat(5);
+        let ciphertext: Vec<u8> = plaintext
+            .serialise()
+            .xor(Mt19937::new(42).keystream())
+            .collect();
+        let keystream: Vec<u8> = ciphertext
+            .iter()
+            .zip(plaintext.as_bytes())
+            .map(|(c, p)| c ^ p)
+            .collect();
+        let mut clone = clone_mt19937_keystream(&keystream).unwrap();
+        let mut original = Mt19937::new(42);
+        (0..keystream.len() / 4).for_each(|_| {
+            original.next_u32();
+        });
+        assert_eq!(clone.next_u32(), original.next_u32());
+        assert!(clone_mt19937_keystream(&keystream[..2495]).is_err());
+    }
+
+    #[test]
+    fn test_mt19937_seed_recovery() {
+        // a generator seeded with the time, some time in the last couple of hours
+        let now = 1_700_000_000;
+        let seed = now - 4321;
+        let mut rng = Mt19937::new(seed);
+        let outputs = [rng.next_u32(), rng.next_u32()];
+        assert_eq!(recover_seed(&outputs[..1], now - 7200..=now), Some(seed));
+        assert_eq!(recover_seed(&outputs, now - 7200..=now), Some(seed));
+        assert_eq!(recover_seed(&outputs, now - 4000..=now), None);
+        assert_eq!(recover_seed(&[], now - 7200..=now), None);
+
+        // a 16 bit seed behind a keystream
+        let keystream: Vec<u8> = Mt19937::new(31337).keystream().take(6).collect();
+        assert_eq!(
+            recover_keystream_seed(&keystream, 0..=u16::MAX as u32),
+            Some(31337)
+        );
+    }
+}
+
+fn test_scorer_raw<S: Scorer>(scorer: &S, message: &str) {
+    let english = scorer.score(String::from(message).serialise());
+    let shifted = scorer.score(String::from(message).serialise().caesar_shift(7));
+    let xored = scorer.score(
+        String::from(message)
+            .serialise()
+            .xor_repeating(vec![0x5a, 0x17, 0xc3].into_iter()),
+    );
+
+    assert!(
+        english > shifted,
+        "{} is not above {} for {:?}",
+        english,
+        shifted,
+        message
+    );
+    assert!(
+        english > xored,
+        "{} is not above {} for {:?}",
+        english,
+        xored,
+        message
+    );
+    // the trait is usable as an object too
+    let scorer: &dyn Scorer = scorer;
+    assert_eq!(scorer.score_bytes(message.as_bytes()), english);
+}
+
+fn test_brute_force_raw<
+    E: Fn(std::vec::IntoIter<u8>, u8) -> Vec<u8>,
+    B: Fn(std::vec::IntoIter<u8>, &NGramScorer, usize) -> Vec<Candidate<u8>>,
+>(
+    message: &str,
+    key: u8,
+    encrypt: E,
+    breaker: B,
+) {
+    let scorer = NGramScorer::english_quadgrams();
+    let ciphertext = encrypt(String::from(message).serialise(), key);
+    let candidates = breaker(Vec::from(ciphertext.as_slice()).into_iter(), &scorer, 3);
+
+    assert_eq!(candidates.len(), 3);
+    assert!(candidates
+        .windows(2)
+        .all(|c| c[0].score().total_cmp(&c[1].score()).is_ge()));
+
+    // NOTE: messages this short, like "AABAC" or "", do not have enough text to tell the keys apart, but the right
+    // key is still among all of them
+    if message.len() < 20 {
+        let all = breaker(ciphertext.into_iter(), &scorer, usize::MAX);
+        assert_eq!(all.len(), 256);
+        assert!(
+            all.iter()
+                .any(|c| *c.key() == key && c.plaintext() == message.as_bytes()),
+            "The right key is not among the candidates"
+        );
+        return;
+    }
+
+    assert_eq!(*candidates[0].key(), key, "The wrong key was found");
+    assert_eq!(candidates[0].plaintext(), message.as_bytes());
+}
+
+fn test_periodic_raw<
+    E: Fn(std::vec::IntoIter<u8>, std::vec::IntoIter<u8>) -> Vec<u8>,
+    B: Fn(std::vec::IntoIter<u8>, &NGramScorer, usize, usize) -> PeriodicBreak,
+>(
+    message: &str,
+    key: &str,
+    encrypt: E,
+    breaker: B,
+) {
+    let scorer = NGramScorer::english_unigrams().strict();
+    let ciphertext = encrypt(
+        String::from(message).serialise(),
+        String::from(key).serialise(),
+    );
+    let result = breaker(ciphertext.into_iter(), &scorer, 16, 3);
+
+    let best = result.best().expect("No key was found");
+    assert_eq!(
+        String::from_utf8_lossy(best.key()),
+        key,
+        "{}",
+        result.inspect_state()
+    );
+    assert_eq!(best.plaintext(), message.as_bytes());
+    assert!(best.confidence() > 0.0 && best.confidence() <= 1.0);
+    assert_eq!(result.key_lengths().len(), 16);
+}
+
+fn test_search_raw(space: &SubstitutionSpace, search: Search) {
+    let scorer = NGramScorer::english_quadgrams();
+    let results = search.run(space, &scorer);
+
+    let best = &results[0];
+    assert_eq!(
+        String::from_utf8_lossy(best.plaintext()),
+        tests::LONG_ENGLISH_MESSAGE,
+        "The search did not find the key"
+    );
+    assert_eq!(space.decrypt(best.key()), best.plaintext());
+}
+
+fn test_identify_raw<I: CryptographicIter>(ciphertext: I, expected: Hypothesis) {
+    let identification = identify(ciphertext);
+    assert_eq!(
+        identification.best(),
+        Some(expected),
+        "{}",
+        identification.inspect_state()
+    );
+
+    let scores: Vec<f64> = identification
+        .hypotheses()
+        .iter()
+        .map(|(_, s)| *s)
+        .collect();
+    assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+    assert!(scores.iter().all(|&s| s > 0.0 && s <= 1.0));
+}
+
+fn test_length_extension_raw<H: MerkleDamgard>() {
+    let message = b"comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+    let extension = b";admin=true";
+
+    let verifier = MacVerifier::<H>::new(b"YELLOW SUBMARINE", MacScheme::SecretPrefix);
+    let mac = verifier.sign(message);
+    assert!(verifier.verify(message, &mac));
+
+    let forgery = extend_length::<H>(&mac, 16, message, extension).unwrap();
+    assert!(forgery.message().starts_with(message));
+    assert!(forgery.message().ends_with(extension));
+    assert!(verifier.verify(forgery.message(), forgery.mac()));
+    // the wrong length of the secret gives the wrong glue
+    let wrong = extend_length::<H>(&mac, 15, message, extension).unwrap();
+    assert!(!verifier.verify(wrong.message(), wrong.mac()));
+
+    let guessed = extend_length_guessing::<H, _>(
+        |m, mac| verifier.verify(m, mac),
+        &mac,
+        message,
+        extension,
+        64,
+    )
+    .unwrap();
+    assert_eq!(guessed.secret_len(), 16);
+    assert_eq!(guessed, forgery);
+
+    // HMAC hashes the digest again under the key, so carrying on from it gets nowhere
+    let verifier = MacVerifier::<H>::new(b"YELLOW SUBMARINE", MacScheme::Hmac);
+    let mac = verifier.sign(message);
+    assert!(verifier.verify(message, &mac));
+    assert!(extend_length_guessing::<H, _>(
+        |m, mac| verifier.verify(m, mac),
+        &mac,
+        message,
+        extension,
+        64,
+    )
+    .is_none());
+}
@@ -1,6 +1,16 @@
+#![allow(clippy::items_after_test_module)]
+
+use cryptography_whiteboard::bitstream::BitVec;
+
 #[cfg(test)]
 mod tests {
-    use cryptography_whiteboard::bitstream::BitVec;
+    use cryptography_whiteboard::{
+        bits,
+        bitstream::{BitChunk, BitPadding, BitVec, SubByteValue, BIT_VEC_FORMAT_VERSION},
+        traits::{BitIter, CryptographicIter, Serialisable},
+    };
+
+    use crate::{random_bits, TestRng};
 
     #[test]
     fn test_bit_vec_from() {
@@ -27,4 +37,367 @@ mod tests {
 
         assert_eq!(vec, vec2, "The vector should have the expected value");
     }
+
+    #[test]
+    fn test_bytes_to_bits() {
+        let bits: BitVec = String::from("Hi").serialise().bits().collect();
+        let expected: BitVec = "0100100001101001".try_into().unwrap();
+
+        assert_eq!(bits, expected, "The bits should be in MSB-first order");
+    }
+
+    #[test]
+    fn test_bits_to_bytes_round_trip() {
+        let message = String::from("Give a man a fish and you feed him for a day.");
+
+        for padding in [BitPadding::Zeros, BitPadding::Ones, BitPadding::Drop] {
+            let round_trip: Vec<u8> = message.serialise().bits().bytes(padding).collect();
+            assert_eq!(
+                round_trip,
+                message.as_bytes(),
+                "Whole bytes should not be affected by the padding policy"
+            );
+        }
+    }
+
+    #[test]
+    fn test_bits_to_bytes_padding() {
+        let bits: BitVec = "1010101011".try_into().unwrap();
+
+        let zeros: Vec<u8> = bits.clone().into_iter().bytes(BitPadding::Zeros).collect();
+        assert_eq!(zeros, vec![0b10101010, 0b11000000]);
+
+        let ones: Vec<u8> = bits.clone().into_iter().bytes(BitPadding::Ones).collect();
+        assert_eq!(ones, vec![0b10101010, 0b11111111]);
+
+        let dropped: Vec<u8> = bits.into_iter().bytes(BitPadding::Drop).collect();
+        assert_eq!(dropped, vec![0b10101010]);
+    }
+
+    #[test]
+    fn test_serialise_round_trip() {
+        let mut rng = TestRng::new(0x5EED);
+        for _ in 0..500 {
+            let (bits, vec) = random_bits(&mut rng, 200);
+            let serialised: Vec<u8> = vec.serialise().collect();
+
+            assert_eq!(serialised[0], BIT_VEC_FORMAT_VERSION);
+            let deserialised = BitVec::deserialise(serialised.into_iter())
+                .expect("A serialised BitVec should be readable");
+
+            assert_eq!(
+                deserialised, vec,
+                "The round trip should preserve the value"
+            );
+            assert_eq!(deserialised.into_iter().collect::<Vec<_>>(), bits);
+        }
+    }
+
+    #[test]
+    fn test_serialise_does_not_overread() {
+        let vec: BitVec = "1011001110001".try_into().unwrap();
+        let mut stream = vec.serialise().chain([0xAA, 0xBB]);
+
+        assert_eq!(BitVec::deserialise(stream.by_ref()), Some(vec));
+        assert_eq!(
+            stream.collect::<Vec<_>>(),
+            vec![0xAA, 0xBB],
+            "Reading a BitVec should not consume the data following it"
+        );
+    }
+
+    #[test]
+    fn test_deserialise_legacy() {
+        let mut rng = TestRng::new(0x1E6AC7);
+        for _ in 0..500 {
+            let (_, vec) = random_bits(&mut rng, 200);
+
+            let mut legacy = vec![vec.last_sub_byte().len(), vec.last_sub_byte().raw_value()];
+            legacy.extend(vec.bytes());
+
+            assert_eq!(
+                BitVec::deserialise(legacy.clone().into_iter()),
+                Some(vec.clone())
+            );
+            assert_eq!(BitVec::deserialise_legacy(legacy.into_iter()), Some(vec));
+        }
+    }
+
+    #[test]
+    fn test_deserialise_rejects_inconsistent_headers() {
+        // a tail of 200 bits can not fit into the leftover byte
+        assert_eq!(BitVec::deserialise([200, 0, 1, 2].into_iter()), None);
+        // the padding bits of the legacy tail have to be empty
+        assert_eq!(BitVec::deserialise([3, 0b1111_1111].into_iter()), None);
+        // 17 bits need 3 bytes of data
+        assert_eq!(
+            BitVec::deserialise([BIT_VEC_FORMAT_VERSION, 17, 0xFF, 0xFF].into_iter()),
+            None
+        );
+        // the padding bits of the last byte have to be empty
+        assert_eq!(
+            BitVec::deserialise([BIT_VEC_FORMAT_VERSION, 4, 0b1111_1000].into_iter()),
+            None
+        );
+        // the varint length is truncated
+        assert_eq!(
+            BitVec::deserialise([BIT_VEC_FORMAT_VERSION, 0x80].into_iter()),
+            None
+        );
+        // the varint length has redundant zero groups
+        assert_eq!(
+            BitVec::deserialise([BIT_VEC_FORMAT_VERSION, 0x80, 0x00].into_iter()),
+            None
+        );
+        assert_eq!(BitVec::deserialise([].into_iter()), None);
+    }
+
+    #[test]
+    fn test_deserialise_random_input() {
+        let mut rng = TestRng::new(0xF022);
+        for _ in 0..2000 {
+            let len = rng.next_below(8);
+            let mut data: Vec<u8> = (0..len).map(|_| rng.next_u64() as u8).collect();
+            if rng.next_below(2) == 0 && !data.is_empty() {
+                data[0] = BIT_VEC_FORMAT_VERSION;
+            }
+
+            // anything that is accepted has to describe a consistent BitVec
+            if let Some(vec) = BitVec::deserialise(data.into_iter()) {
+                assert_eq!(vec.clone().into_iter().count(), vec.len());
+                assert_eq!(BitVec::deserialise(vec.serialise()), Some(vec));
+            }
+        }
+    }
+
+    #[test]
+    fn test_get() {
+        let mut rng = TestRng::new(0x6E7);
+        for _ in 0..200 {
+            let (bits, vec) = random_bits(&mut rng, 100);
+            for i in 0..bits.len() + 10 {
+                assert_eq!(vec.get(i), bits.get(i).copied(), "Bit {} differs", i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_iter_against_reference() {
+        let mut rng = TestRng::new(0x17E2);
+        for _ in 0..1000 {
+            let (bits, vec) = random_bits(&mut rng, 100);
+            let mut expected = bits.into_iter();
+            let mut actual = vec.into_iter();
+
+            loop {
+                assert_eq!(actual.len(), expected.len());
+                assert_eq!(actual.size_hint(), expected.size_hint());
+
+                let step = rng.next_below(10);
+                let (a, e) = match rng.next_below(4) {
+                    0 => (actual.next(), expected.next()),
+                    1 => (actual.next_back(), expected.next_back()),
+                    2 => (actual.nth(step), expected.nth(step)),
+                    _ => (actual.nth_back(step), expected.nth_back(step)),
+                };
+                assert_eq!(a, e, "The iterator should match a Vec<bool>");
+
+                if e.is_none() && expected.len() == 0 {
+                    break;
+                }
+            }
+
+            assert_eq!(actual.next(), None, "The iterator should be fused");
+            assert_eq!(actual.next_back(), None, "The iterator should be fused");
+        }
+    }
+
+    #[test]
+    fn test_iter_adapters_against_reference() {
+        let mut rng = TestRng::new(0xAD);
+        for _ in 0..300 {
+            let (bits, vec) = random_bits(&mut rng, 100);
+            let n = rng.next_below(bits.len() + 5);
+
+            assert_eq!(
+                vec.clone().into_iter().skip(n).collect::<Vec<_>>(),
+                bits.iter().copied().skip(n).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                vec.clone().into_iter().rev().collect::<Vec<_>>(),
+                bits.iter().copied().rev().collect::<Vec<_>>()
+            );
+            assert_eq!(vec.clone().into_iter().count(), bits.len());
+            assert_eq!(vec.into_iter().last(), bits.last().copied());
+        }
+    }
+
+    #[test]
+    fn test_iter_skip() {
+        // 2^24 bits, skipped over in large steps from both ends
+        let vec = BitVec::deserialise(
+            [BIT_VEC_FORMAT_VERSION]
+                .into_iter()
+                .chain([0x80, 0x80, 0x80, 0x08])
+                .chain(std::iter::repeat_n(0b0101_0101, 1 << 21)),
+        )
+        .unwrap();
+
+        let mut iter = vec.into_iter();
+        for _ in 0..4_000 {
+            assert_eq!(iter.nth(1001), Some(true));
+            assert_eq!(iter.nth_back(1001), Some(false));
+        }
+    }
+
+    #[test]
+    fn test_bits_macro() {
+        let expected: BitVec = "0110101001".try_into().unwrap();
+        assert_eq!(bits![0, 1, 1, 0, 1, 0, 1, 0, 0, 1], expected);
+        assert_eq!(bits![], BitVec::new());
+        assert_eq!(bits![1, 0, 1].to_string(), "101");
+    }
+
+    #[test]
+    fn test_bool_conversions() {
+        let mut rng = TestRng::new(0xB001);
+        for _ in 0..200 {
+            let (bits, vec) = random_bits(&mut rng, 100);
+            assert_eq!(BitVec::from(bits.clone()), vec);
+            assert_eq!(BitVec::from(&bits[..]), vec);
+            assert_eq!(Vec::<bool>::from(vec), bits);
+        }
+    }
+
+    #[test]
+    fn test_byte_conversions() {
+        let mut rng = TestRng::new(0xB17E);
+        for _ in 0..200 {
+            let (_, vec) = random_bits(&mut rng, 100);
+            let len = vec.len();
+
+            let (bytes, padding) = vec.clone().into_padded_bytes();
+            assert_eq!(bytes.len() * 8 - padding as usize, len);
+            assert_eq!(BitVec::try_from((&bytes[..], len)).unwrap(), vec);
+        }
+
+        // the padding bits are ignored
+        assert_eq!(
+            BitVec::from_bytes(&[0xFF, 0xFF], 12).unwrap(),
+            "111111111111".try_into().unwrap()
+        );
+        assert!(BitVec::from_bytes(&[0xFF], 12).is_err());
+        assert!(BitVec::from_bytes(&[0xFF, 0xFF], 8).is_err());
+    }
+
+    #[test]
+    fn test_uint_conversions() {
+        assert_eq!(BitVec::from(0b1010_0001u8), bits![1, 0, 1, 0, 0, 0, 0, 1]);
+        assert_eq!(BitVec::from(1u16).to_string(), "0000000000000001");
+        assert_eq!(BitVec::from(u32::MAX).len(), 32);
+        assert_eq!(BitVec::from(u64::MAX).len(), 64);
+        assert_eq!(BitVec::from(1u128 << 127).get(0), Some(true));
+
+        assert_eq!(BitVec::from_uint(5, 4).unwrap(), bits![0, 1, 0, 1]);
+        assert_eq!(BitVec::from_uint(0, 0).unwrap(), BitVec::new());
+        assert!(BitVec::from_uint(16, 4).is_err());
+        assert!(BitVec::from_uint(0, 129).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let raw_val = "0110101001";
+        let vec: BitVec = raw_val.try_into().unwrap();
+        assert_eq!(vec.to_string(), raw_val);
+        assert_eq!(BitVec::new().to_string(), "");
+    }
+
+    #[test]
+    fn test_bit_chunk() {
+        let chunk = BitChunk::from_value(0b1_0110_1001, 9);
+        assert_eq!(chunk.len(), 9);
+        assert_eq!(chunk.value(), 0b1_0110_1001);
+        assert_eq!(String::from(chunk), "101101001");
+        assert_eq!(BitChunk::try_from("101101001").unwrap(), chunk);
+
+        assert_eq!(BitChunk::checked_from_value(0b10, 1), None);
+        assert_eq!(BitChunk::checked_from_value(0, 65), None);
+        assert_eq!(BitChunk::from_value(u64::MAX, 64).value(), u64::MAX);
+        assert_eq!(BitChunk::from_value(0, 0), BitChunk::new());
+
+        let full = BitChunk::from_value(0, 64);
+        assert_eq!(full.checked_add(true), None);
+        assert!(BitChunk::try_from("0".repeat(65).as_str()).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bit_chunk_overflow() {
+        let mut chunk = BitChunk::from_value(0, 64);
+        chunk += true;
+    }
+
+    #[test]
+    fn test_bit_chunk_sub_byte_value() {
+        let sub_byte = SubByteValue::try_from("10110").unwrap();
+        let chunk = BitChunk::from(sub_byte.clone());
+        assert_eq!(chunk, BitChunk::try_from("10110").unwrap());
+        assert_eq!(SubByteValue::try_from(chunk).unwrap(), sub_byte);
+        assert!(SubByteValue::try_from(BitChunk::from_value(0, 9)).is_err());
+
+        let mut vec = BitVec::new();
+        vec += sub_byte.clone();
+        assert_eq!(vec, BitVec::new() + chunk);
+    }
+
+    #[test]
+    fn test_append_bit_chunk() {
+        let mut rng = TestRng::new(0xC4A2);
+        for _ in 0..500 {
+            let (mut bits, mut vec) = random_bits(&mut rng, 30);
+
+            let len = rng.next_below(65) as u8;
+            let value = if len == 0 {
+                0
+            } else {
+                rng.next_u64() >> (64 - len)
+            };
+            let chunk = BitChunk::from_value(value, len);
+
+            vec += chunk;
+            bits.extend((0..len).rev().map(|i| value >> i & 1 == 1));
+
+            assert_eq!(vec, BitVec::from(bits), "Appending {:?} failed", chunk);
+        }
+    }
+}
+
+// A small xorshift generator, so that the randomised tests are reproducible
+struct TestRng(u64);
+
+impl TestRng {
+    fn new(seed: u64) -> Self {
+        TestRng(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn next_below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+fn random_bits(rng: &mut TestRng, max_len: usize) -> (Vec<bool>, BitVec) {
+    let len = rng.next_below(max_len + 1);
+    let bits: Vec<bool> = (0..len).map(|_| rng.next_u64() & 1 == 1).collect();
+
+    let mut vec = BitVec::new();
+    bits.iter().for_each(|b| vec += *b);
+
+    (bits, vec)
 }
@@ -1,3 +1,5 @@
+#![allow(clippy::items_after_test_module)]
+
 use cryptography_whiteboard::{
     compression::huffman::HuffmanEncoding,
     traits::{InspectableState, Serialisable},
@@ -28,13 +30,13 @@ mod tests {
 }
 
 fn test_huffman_raw(original: String) {
-    let original_len = original.as_bytes().len() * 8;
+    let _original_len = original.len() * 8;
     // println!("Original: {},\n bit length: {}", original, original_len);
 
     let huffman = HuffmanEncoding::from_crypto_iter(original.serialise());
 
-    let dictionary_bit_length = &huffman.dictionary.serialise().count() * 8;
-    let message_bit_length = huffman.clone().data.into_iter().count();
+    let _dictionary_bit_length = &huffman.dictionary.serialise().count() * 8;
+    let _message_bit_length = huffman.clone().data.into_iter().count();
     // println!("Stream: {:?}", &huffman.data);
     // println!("Dictionary: {:?}", &huffman.dictionary);
     // println!("Dictionary bit length: {}", dictionary_bit_length);
@@ -1,10 +1,43 @@
-use std::{borrow::Borrow, ops::Deref};
+#![allow(clippy::items_after_test_module)]
 
-use cryptography_whiteboard::traits::{CryptographicIter, InspectableState, Serialisable};
+use std::ops::Deref;
+
+use cryptography_whiteboard::{
+    bitstream::BitPadding,
+    cyphers::{
+        bitwise::Lfsr,
+        block::{BlockCypher, BlockMode},
+        polygraphic::PolygraphicCypher,
+        transposition::Transposition,
+    },
+    traits::{BitIter, CryptographicIter, InspectableState, Serialisable},
+};
 
 #[cfg(test)]
 mod tests {
-    use crate::{test_caesar_raw, test_vernam_raw};
+    use crate::{
+        test_bit_permutation_raw, test_block_raw, test_caesar_raw, test_lfsr_xor_raw,
+        test_polyalphabetic_raw, test_polygraphic_raw, test_transposition_raw, test_vernam_raw,
+    };
+    use cryptography_whiteboard::{
+        cyphers::{
+            alphabet::{Alphabet, OutOfAlphabet},
+            bitwise::Lfsr,
+            block::{pkcs7_pad, pkcs7_unpad, BlockCypher, BlockMode, Feistel},
+            book::{Book, BookDecoder, BookToken, Occurrence, Referencing, TokenReader},
+            enigma::{Enigma, Reflector, Rotor},
+            fractionating::{Adfgvx, Bifid, Nihilist, Trifid},
+            one_time_pad::{OneTimePad, PadMessage},
+            polybius::PolybiusSquare,
+            polygraphic::{FourSquare, Hill, KeySquare, Playfair, PolygraphicCypher, TwoSquare},
+            substitution::SubstitutionKey,
+            transposition::{
+                ColumnarKey, DoubleColumnar, Padding, RailFence, Route, RoutePath, Scytale,
+            },
+        },
+        random::Mt19937,
+        traits::{CryptographicIter, InspectableState, Serialisable},
+    };
 
     pub const TEST_KEYS: [&str; 3] = [
         "a bc", "", "a
@@ -39,6 +72,1276 @@ c",
             }
         }
     }
+
+    #[test]
+    fn test_xor_modes() {
+        let message = || String::from("abcd").serialise();
+        let key = || vec![1, 2].into_iter();
+
+        assert!(message()
+            .xor_repeating(key())
+            .eq(vec![b'`', b'`', b'b', b'f']));
+        assert!(message().xor_truncating(key()).eq(vec![b'`', b'`']));
+
+        let mut strict = message().xor(key());
+        assert!(strict.by_ref().eq(vec![b'`', b'`']));
+        let error = strict.error().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert!(error.to_string().contains("after 2 bytes"));
+
+        let mut empty_key = message().xor_repeating(Vec::new().into_iter());
+        assert_eq!(empty_key.by_ref().count(), 0);
+        assert!(empty_key.error().is_some());
+
+        let mut long_key = message().xor(vec![0; 10].into_iter());
+        assert_eq!(long_key.by_ref().count(), 4);
+        assert!(long_key.error().is_none());
+    }
+
+    #[test]
+    fn test_one_time_pad() {
+        let dir = std::env::temp_dir().join(format!("otp-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pad_path = dir.join("pad.bin");
+        let pad: Vec<u8> = (0..64u8).map(|i| i.wrapping_mul(37) ^ 0x5A).collect();
+        std::fs::write(&pad_path, &pad).unwrap();
+        // the receiver has their own copy of the pad, with their own index
+        let receiver_path = dir.join("receiver.bin");
+        std::fs::write(&receiver_path, &pad).unwrap();
+
+        let mut sender = OneTimePad::open(&pad_path).unwrap();
+        let mut receiver = OneTimePad::open(&receiver_path).unwrap();
+        assert_eq!(sender.remaining(), 64);
+
+        let mut offsets = Vec::new();
+        for message in ["Attack at dawn", "Retreat at dusk"] {
+            let sent = sender.encrypt(message.to_owned().serialise()).unwrap();
+            offsets.push(sent.offset());
+
+            let received = PadMessage::deserialise(sent.serialise()).unwrap();
+            assert_eq!(received, sent);
+            let plaintext = receiver.decrypt(&received).unwrap();
+            assert_eq!(plaintext.into_iter().inspect_state(), message);
+        }
+        assert_eq!(offsets, vec![0, 14]);
+        assert_eq!(sender.used_ranges(), std::slice::from_ref(&(0..29)));
+        assert_eq!(receiver.used_ranges(), std::slice::from_ref(&(0..29)));
+
+        // the index survives reopening the pad, and used material is refused
+        let mut sender = OneTimePad::open(&pad_path).unwrap();
+        assert_eq!(sender.remaining(), 64 - 29);
+        let error = sender
+            .encrypt_at(10, String::from("again").serialise())
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+        assert!(sender
+            .encrypt_at(40, String::from("gap").serialise())
+            .is_ok());
+
+        // the first gap that fits the message is used, and nothing is left for a message that does not fit
+        let sent = sender.encrypt(vec![0; 11].into_iter()).unwrap();
+        assert_eq!(sent.offset(), 29);
+        assert_eq!(sender.used_ranges(), std::slice::from_ref(&(0..43)));
+        let error = sender.encrypt(vec![0; 30].into_iter()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(sender.remaining(), 21);
+
+        // two handles on the same pad see what the other one has used, even though both were opened before either
+        // used it
+        let shared_path = dir.join("shared.bin");
+        std::fs::write(&shared_path, &pad).unwrap();
+        let mut first = OneTimePad::open(&shared_path).unwrap();
+        let mut second = OneTimePad::open(&shared_path).unwrap();
+        let sent = first.encrypt(String::from("first").serialise()).unwrap();
+        assert_eq!(sent.offset(), 0);
+        let error = second
+            .encrypt_at(2, String::from("reused").serialise())
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+        let sent = second.encrypt(String::from("second").serialise()).unwrap();
+        assert_eq!(sent.offset(), 5);
+        assert_eq!(first.encrypt(vec![0; 3].into_iter()).unwrap().offset(), 11);
+        assert_eq!(first.used_ranges(), std::slice::from_ref(&(0..14)));
+
+        // a corrupted index is not silently ignored
+        let mut index_path = pad_path.clone().into_os_string();
+        index_path.push(".used");
+        std::fs::write(&index_path, [1, 5, 100]).unwrap();
+        assert!(OneTimePad::open(&pad_path).is_err());
+
+        assert!(PadMessage::deserialise(vec![0, 5, 1, 2].into_iter()).is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_lfsr_xor() {
+        for message in TEST_MESSAGES {
+            test_lfsr_xor_raw(message.to_owned());
+        }
+    }
+
+    #[test]
+    fn test_bit_permutation() {
+        for message in TEST_MESSAGES {
+            test_bit_permutation_raw(message.to_owned());
+        }
+    }
+
+    #[test]
+    fn test_lfsr_period() {
+        // x^4 + x^3 + 1 is primitive, so the register should go through all 15 non-zero states
+        let mut lfsr = Lfsr::new(4, 0b0011, 0b0001);
+        let start = lfsr.state();
+
+        let mut period = 0;
+        loop {
+            lfsr.next();
+            period += 1;
+            if lfsr.state() == start || period > 16 {
+                break;
+            }
+        }
+        assert_eq!(
+            period, 15,
+            "A maximal 4-bit LFSR should have a period of 15"
+        );
+    }
+
+    #[test]
+    fn test_polyalphabetic() {
+        for message in TEST_MESSAGES {
+            for key in TEST_KEYS {
+                test_polyalphabetic_raw(message.to_owned(), key.to_owned());
+            }
+        }
+    }
+
+    #[test]
+    fn test_vigenere_known_answer() {
+        let key = String::from("LEMON");
+        let ciphertext = String::from("ATTACKATDAWN")
+            .serialise()
+            .vigenere(key.serialise())
+            .letters_only();
+        assert_eq!(ciphertext.inspect_state(), "LXFOPVEFRNHR");
+
+        let ciphertext = String::from("Attack at dawn!")
+            .serialise()
+            .vigenere(key.serialise())
+            .letters_only();
+        assert_eq!(ciphertext.inspect_state(), "Lxfopv ef rnhr!");
+        assert_eq!(
+            ciphertext
+                .vigenere_decrypt(String::from("lemon").serialise())
+                .letters_only()
+                .inspect_state(),
+            "Attack at dawn!"
+        );
+    }
+
+    #[test]
+    fn test_beaufort_known_answer() {
+        let ciphertext = String::from("DEFENDTHEEASTWALLOFTHECASTLE")
+            .serialise()
+            .beaufort(String::from("FORTIFICATION").serialise())
+            .letters_only();
+        assert_eq!(ciphertext.inspect_state(), "CKMPVCPVWPIWUJOGIUAPVWRIWUUK");
+    }
+
+    #[test]
+    fn test_autokey_known_answer() {
+        let key = String::from("QUEENLY");
+        let ciphertext = String::from("ATTACKATDAWN")
+            .serialise()
+            .autokey(key.serialise())
+            .letters_only();
+        assert_eq!(ciphertext.inspect_state(), "QNXEPVYTWTWP");
+        assert_eq!(
+            ciphertext
+                .autokey_decrypt(key.serialise())
+                .letters_only()
+                .inspect_state(),
+            "ATTACKATDAWN"
+        );
+    }
+
+    #[test]
+    fn test_caesar_in_alphabet() {
+        let letters = Alphabet::letters();
+        let ciphertext = String::from("HELLO")
+            .serialise()
+            .caesar_shift_in(&letters, 3);
+        assert_eq!(ciphertext.inspect_state(), "KHOOR");
+
+        let ciphertext = String::from("Hello, World! xyz")
+            .serialise()
+            .caesar_shift_in(&letters, 3);
+        assert_eq!(ciphertext.inspect_state(), "Khoor, Zruog! abc");
+        assert_eq!(
+            ciphertext.caesar_unshift_in(&letters, 3).inspect_state(),
+            "Hello, World! xyz"
+        );
+
+        let uppercase = Alphabet::uppercase();
+        assert_eq!(
+            String::from("Hello")
+                .serialise()
+                .caesar_shift_in(&uppercase, 29)
+                .inspect_state(),
+            "Kello"
+        );
+    }
+
+    #[test]
+    fn test_caesar_round_trip_in_alphabets() {
+        let alphabets = [
+            Alphabet::bytes(),
+            Alphabet::letters(),
+            Alphabet::uppercase(),
+            Alphabet::lowercase(),
+            Alphabet::custom("aeiou").unwrap().with_case_folding(),
+        ];
+        for message in TEST_MESSAGES {
+            for alphabet in &alphabets {
+                for shift in [0, 1, 3, 25, 300] {
+                    let decrypted = message
+                        .to_owned()
+                        .serialise()
+                        .caesar_shift_in(alphabet, shift)
+                        .caesar_unshift_in(alphabet, shift);
+                    assert_eq!(decrypted.inspect_state(), message);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_out_of_alphabet_policy() {
+        let message = String::from("Hello, World!");
+
+        let dropped = Alphabet::letters().with_policy(OutOfAlphabet::Drop);
+        assert_eq!(
+            message
+                .serialise()
+                .caesar_shift_in(&dropped, 3)
+                .inspect_state(),
+            "KhoorZruog"
+        );
+
+        let strict = Alphabet::letters().with_policy(OutOfAlphabet::Error);
+        let mut ciphertext = message.serialise().caesar_shift_in(&strict, 3);
+        let output: Vec<u8> = ciphertext.by_ref().collect();
+        assert_eq!(output, b"Khoor");
+        assert!(
+            ciphertext.error().is_some(),
+            "The ',' should have been reported"
+        );
+
+        let mut ciphertext = String::from("Hello")
+            .serialise()
+            .caesar_shift_in(&strict, 3);
+        assert_eq!(ciphertext.by_ref().count(), 5);
+        assert!(ciphertext.error().is_none());
+    }
+
+    #[test]
+    fn test_unicode_alphabets() {
+        let greek = Alphabet::custom("ΑΒΓΔΕ").unwrap().with_case_folding();
+        let ciphertext = String::from("Αβγ - δε")
+            .serialise()
+            .caesar_shift_in(&greek, 1);
+        assert_eq!(ciphertext.inspect_state(), "Βγδ - εα");
+
+        // "é" written with a combining accent is a single symbol
+        let graphemes = Alphabet::graphemes(&["e", "e\u{301}", "a"]).unwrap();
+        let ciphertext = String::from("e\u{301}ae")
+            .serialise()
+            .caesar_shift_in(&graphemes, 1);
+        assert_eq!(ciphertext.inspect_state(), "aee\u{301}");
+
+        assert!(Alphabet::custom("abca").is_err());
+        assert!(Alphabet::custom("").is_err());
+    }
+
+    #[test]
+    fn test_vigenere_in_alphabet() {
+        let alphabet = Alphabet::alphanumeric();
+        let key = String::from("K3Y");
+        // NOTE: the digits have no lowercase form, so a lowercase letter could come back in uppercase
+        let ciphertext = String::from("MEET AT 10PM")
+            .serialise()
+            .vigenere(key.serialise())
+            .in_alphabet(&alphabet);
+        assert_eq!(
+            ciphertext
+                .vigenere_decrypt(key.serialise())
+                .in_alphabet(&alphabet)
+                .inspect_state(),
+            "MEET AT 10PM"
+        );
+    }
+
+    #[test]
+    fn test_substitution() {
+        let alphabets = [
+            Alphabet::letters(),
+            Alphabet::bytes(),
+            Alphabet::custom("ΑΒΓΔΕ").unwrap().with_case_folding(),
+        ];
+        for alphabet in &alphabets {
+            let keys = [
+                SubstitutionKey::from_keyword(alphabet, "ZEBRAS"),
+                SubstitutionKey::random(alphabet, 42),
+                SubstitutionKey::atbash(alphabet),
+                SubstitutionKey::affine(alphabet, 3, 8).unwrap(),
+            ];
+            for key in &keys {
+                for message in TEST_MESSAGES {
+                    let decrypted = message
+                        .to_owned()
+                        .serialise()
+                        .substitute(key)
+                        .unsubstitute(key);
+                    assert_eq!(decrypted.inspect_state(), message);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_substitution_known_answer() {
+        let letters = Alphabet::letters();
+
+        let key = SubstitutionKey::from_keyword(&letters, "zebras");
+        assert_eq!(key.cypher_alphabet(), "ZEBRASCDFGHIJKLMNOPQTUVWXY");
+        assert_eq!(
+            String::from("flee at once. we are discovered!")
+                .serialise()
+                .substitute(&key)
+                .inspect_state(),
+            "siaa zq lkba. va zoa rfpbluaoar!"
+        );
+
+        let key = SubstitutionKey::atbash(&letters);
+        assert_eq!(
+            String::from("Hello")
+                .serialise()
+                .substitute(&key)
+                .inspect_state(),
+            "Svool"
+        );
+
+        let key = SubstitutionKey::affine(&letters, 5, 8).unwrap();
+        assert_eq!(
+            String::from("AFFINE CIPHER")
+                .serialise()
+                .substitute(&key)
+                .inspect_state(),
+            "IHHWVC SWFRCP"
+        );
+
+        let key =
+            SubstitutionKey::from_cypher_alphabet(&letters, "QWERTYUIOPASDFGHJKLZXCVBNM").unwrap();
+        assert_eq!(
+            String::from("abc")
+                .serialise()
+                .substitute(&key)
+                .inspect_state(),
+            "qwe"
+        );
+    }
+
+    #[test]
+    fn test_substitution_invalid_keys() {
+        let letters = Alphabet::letters();
+        assert!(SubstitutionKey::affine(&letters, 13, 1).is_err());
+        assert!(SubstitutionKey::affine(&letters, 2, 1).is_err());
+        assert!(SubstitutionKey::affine(&letters, 26, 1).is_err());
+        assert!(SubstitutionKey::affine(&Alphabet::bytes(), 2, 1).is_err());
+        assert!(SubstitutionKey::affine(&Alphabet::bytes(), 3, 1).is_ok());
+
+        // large coefficients are the same as their remainders
+        assert_eq!(
+            SubstitutionKey::affine(&letters, 5, usize::MAX).unwrap(),
+            SubstitutionKey::affine(&letters, 5, usize::MAX % 26).unwrap()
+        );
+        assert_eq!(
+            SubstitutionKey::affine(&letters, usize::MAX, usize::MAX).unwrap(),
+            SubstitutionKey::affine(&letters, usize::MAX % 26, usize::MAX % 26).unwrap()
+        );
+
+        assert!(SubstitutionKey::from_cypher_alphabet(&letters, "ABC").is_err());
+        assert!(SubstitutionKey::new(&letters, (0..25).chain([0]).collect()).is_err());
+    }
+
+    #[test]
+    fn test_substitution_key_serialisation() {
+        let alphabets = [
+            Alphabet::letters(),
+            Alphabet::uppercase().with_policy(OutOfAlphabet::Drop),
+            Alphabet::graphemes(&["e", "e\u{301}", "a"]).unwrap(),
+        ];
+        for alphabet in &alphabets {
+            let key = SubstitutionKey::random(alphabet, 7);
+            let restored = SubstitutionKey::deserialise(key.serialise()).unwrap();
+            assert_eq!(restored, key);
+        }
+
+        let mut corrupted: Vec<u8> = SubstitutionKey::atbash(&Alphabet::letters())
+            .serialise()
+            .collect();
+        *corrupted.last_mut().unwrap() = 1;
+        assert_eq!(SubstitutionKey::deserialise(corrupted.into_iter()), None);
+    }
+
+    #[test]
+    fn test_polygraphic() {
+        for message in TEST_MESSAGES {
+            for keyword in ["PLAYFAIR EXAMPLE", "", "JUMBLE"] {
+                test_polygraphic_raw(message, Playfair::latin(keyword));
+                test_polygraphic_raw(message, TwoSquare::latin(keyword, "KEYWORD"));
+                test_polygraphic_raw(message, FourSquare::latin(keyword, "KEYWORD"));
+            }
+            for keyword in ["HILL", "GYBNQKURP"] {
+                test_polygraphic_raw(
+                    message,
+                    Hill::from_keyword(&Alphabet::letters(), keyword, "X").unwrap(),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_polygraphic_known_answer() {
+        fn encrypt<C: PolygraphicCypher + Clone>(message: &str, cypher: C) -> String {
+            message
+                .to_owned()
+                .serialise()
+                .encrypt_grams(cypher)
+                .with_policy(OutOfAlphabet::Drop)
+                .inspect_state()
+        }
+
+        assert_eq!(
+            encrypt(
+                "HIDE THE GOLD IN THE TREE STUMP",
+                Playfair::latin("PLAYFAIR EXAMPLE")
+            ),
+            "BMODZBXDNABEKUDMUIXMMOUVIF"
+        );
+
+        // these examples leave out `Q` rather than merging `J` into `I`
+        let no_q = Alphabet::custom("ABCDEFGHIJKLMNOPRSTUVWXYZ")
+            .unwrap()
+            .with_case_folding();
+        let square = |keyword| KeySquare::new(&no_q, 5, 5, keyword, &[]).unwrap();
+        assert_eq!(
+            encrypt(
+                "HELP ME OBI WAN KENOBI",
+                TwoSquare::new(square("EXAMPLE"), square("KEYWORD"), "X").unwrap()
+            ),
+            "HEDLXWSDJYANHOTKDG"
+        );
+        assert_eq!(
+            encrypt(
+                "HELP ME OBI WAN KENOBI",
+                FourSquare::new(square(""), square("EXAMPLE"), square("KEYWORD"), "X").unwrap()
+            ),
+            "FYGMKYHOBXMFKKKIMD"
+        );
+
+        let hill = Hill::from_keyword(&Alphabet::letters(), "GYBNQKURP", "X").unwrap();
+        assert_eq!(encrypt("ACT", hill.clone()), "POH");
+        assert_eq!(
+            String::from("POH")
+                .serialise()
+                .decrypt_grams(hill)
+                .inspect_state(),
+            "ACT"
+        );
+    }
+
+    #[test]
+    fn test_polygraphic_filler() {
+        let playfair = Playfair::latin("");
+        // doubled letters are split up, and a doubled filler is split up with the alternative one
+        let encrypted = String::from("BALLOON XXL")
+            .serialise()
+            .encrypt_grams(playfair.clone())
+            .with_policy(OutOfAlphabet::Drop);
+        assert_eq!(
+            encrypted
+                .clone()
+                .decrypt_grams(playfair.clone())
+                .inspect_state(),
+            "BALXLOONXQXL"
+        );
+
+        // `J` is merged into `I`, and punctuation keeps its place between the pairs
+        let decrypted = String::from("Jam, jelly!")
+            .serialise()
+            .encrypt_grams(playfair.clone())
+            .decrypt_grams(playfair);
+        assert_eq!(decrypted.inspect_state(), "Iam, ielly!");
+    }
+
+    #[test]
+    fn test_polygraphic_invalid_keys() {
+        let letters = Alphabet::letters();
+        // the determinant is even, so it is not invertible modulo 26
+        assert!(Hill::from_keyword(&letters, "ABCD", "X").is_err());
+        assert!(Hill::from_keyword(&letters, "ABC", "X").is_err());
+        assert!(Hill::from_keyword(&letters, "", "X").is_err());
+        assert!(Hill::from_keyword(&letters, "HILL", "?").is_err());
+        // a custom modulus: 3x + 0y, 0x + 5y over the 10 digits is not invertible, but 3, 7 is
+        let digits = Alphabet::custom("0123456789").unwrap();
+        assert!(Hill::new(&digits, vec![3, 0, 0, 5], "0").is_err());
+        let hill = Hill::new(&digits, vec![3, 0, 0, 7], "0").unwrap();
+        assert_eq!(hill.inverse(), &[7, 0, 0, 3]);
+
+        assert!(KeySquare::new(&letters, 5, 5, "", &[]).is_err());
+        assert!(KeySquare::new(&letters, 6, 6, "", &[]).is_err());
+        assert!(KeySquare::new(&Alphabet::alphanumeric(), 6, 6, "", &[]).is_ok());
+        assert!(Playfair::new(KeySquare::latin(""), "I", "J").is_err());
+    }
+
+    #[test]
+    fn test_chunks() {
+        let chunks: Vec<Vec<u8>> = vec![1, 2, 3, 4, 5].into_iter().chunks(2).collect();
+        assert_eq!(chunks, vec![vec![1, 2], vec![3, 4], vec![5]]);
+        assert_eq!(Vec::new().into_iter().chunks(3).count(), 0);
+    }
+
+    #[test]
+    fn test_book_cypher() {
+        let book = Book::new(TEST_MESSAGES[3].as_bytes().to_vec(), 3).unwrap();
+        let message = String::from("Meet me at the old mill.");
+
+        for referencing in [Referencing::Offset, Referencing::Ottendorf] {
+            for occurrence in [Occurrence::First, Occurrence::Cycle, Occurrence::Random(7)] {
+                let tokens: Vec<BookToken> = message
+                    .clone()
+                    .serialise()
+                    .book_encode(&book, referencing, occurrence)
+                    .collect();
+                assert_eq!(tokens.len(), message.len());
+
+                // the tokens go through their serialised form
+                let bytes: Vec<u8> = tokens.iter().flat_map(|t| t.serialise()).collect();
+                let decoded = BookDecoder::new(TokenReader::new(bytes.into_iter()), &book);
+                assert_eq!(decoded.inspect_state(), message);
+
+                // repeated letters only get the same reference every time when picking the first occurrence
+                let e: Vec<&BookToken> = tokens
+                    .iter()
+                    .zip(message.bytes())
+                    .filter(|(_, b)| *b == b'e')
+                    .map(|(t, _)| t)
+                    .collect();
+                assert_eq!(
+                    e.iter().all(|t| *t == e[0]),
+                    occurrence == Occurrence::First
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_book_cypher_references() {
+        let book = Book::new(b"the quick brown\nfox jumps\nover the lazy dog".to_vec(), 2).unwrap();
+        let tokens: Vec<String> = String::from("fox")
+            .serialise()
+            .book_encode(&book, Referencing::Ottendorf, Occurrence::First)
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(tokens, vec!["1.2.1.1", "1.1.3.3", "1.2.1.3"]);
+        assert_eq!(
+            book.lookup(BookToken::Ottendorf {
+                page: 2,
+                line: 1,
+                word: 3,
+                letter: 2
+            }),
+            Some(b'a')
+        );
+        assert_eq!(book.lookup(BookToken::Offset(4)), Some(b'q'));
+
+        let mut encoder = String::from("fox!").serialise().book_encode(
+            &book,
+            Referencing::Offset,
+            Occurrence::Cycle,
+        );
+        assert_eq!(encoder.by_ref().count(), 3);
+        assert!(encoder.error().is_some());
+
+        for invalid in [
+            BookToken::Offset(100),
+            BookToken::Ottendorf {
+                page: 1,
+                line: 3,
+                word: 1,
+                letter: 1,
+            },
+            BookToken::Ottendorf {
+                page: 0,
+                line: 1,
+                word: 1,
+                letter: 1,
+            },
+            BookToken::Ottendorf {
+                page: 1,
+                line: 1,
+                word: 1,
+                letter: 4,
+            },
+        ] {
+            let mut decoder = BookDecoder::new(vec![invalid].into_iter(), &book);
+            assert_eq!(decoder.by_ref().count(), 0);
+            assert!(decoder.error().is_some());
+        }
+
+        // whitespace can only be a word boundary in Ottendorf references, and comes back as a space
+        let tokens: Vec<BookToken> = String::from("a\tdog")
+            .serialise()
+            .book_encode(&book, Referencing::Ottendorf, Occurrence::First)
+            .collect();
+        assert_eq!(tokens[1], BookToken::WordBoundary);
+        assert_eq!(tokens[1].to_string(), "/");
+        assert_eq!(
+            BookDecoder::new(tokens.into_iter(), &book).inspect_state(),
+            "a dog"
+        );
+        assert_eq!(
+            BookToken::deserialise(BookToken::WordBoundary.serialise()),
+            Some(BookToken::WordBoundary)
+        );
+
+        assert!(Book::new(Vec::new(), 0).is_err());
+        assert_eq!(BookToken::deserialise(vec![3, 0].into_iter()), None);
+    }
+
+    #[test]
+    fn test_enigma() {
+        let m3 = Enigma::m3(
+            Reflector::B,
+            [Rotor::I, Rotor::II, Rotor::III],
+            "AAA",
+            "AAA",
+            "",
+        )
+        .unwrap();
+        assert_eq!(
+            String::from("AAAAA")
+                .serialise()
+                .enigma(&m3)
+                .inspect_state(),
+            "BDZGO"
+        );
+
+        // the thin B reflector with Beta at A is wired like the B reflector
+        let m4 = Enigma::m4(
+            Reflector::BThin,
+            Rotor::Beta,
+            [Rotor::I, Rotor::II, Rotor::III],
+            "AAAA",
+            "AAAA",
+            "",
+        )
+        .unwrap();
+        assert_eq!(
+            String::from("AAAAA")
+                .serialise()
+                .enigma(&m4)
+                .inspect_state(),
+            "BDZGO"
+        );
+
+        // the middle rotor steps twice in a row
+        let mut machine = Enigma::m3(
+            Reflector::B,
+            [Rotor::I, Rotor::II, Rotor::III],
+            "AAA",
+            "ADU",
+            "",
+        )
+        .unwrap();
+        let mut positions = Vec::new();
+        for _ in 0..4 {
+            machine.step();
+            positions.push(machine.positions());
+        }
+        assert_eq!(positions, vec!["ADV", "AEW", "BFX", "BFY"]);
+
+        for message in TEST_MESSAGES {
+            let machine = Enigma::m3(
+                Reflector::C,
+                [Rotor::VIII, Rotor::V, Rotor::VI],
+                "QRS",
+                "MZY",
+                "AB CD EF GH",
+            )
+            .unwrap();
+            let ciphertext = message.to_owned().serialise().enigma(&machine);
+            // no letter is ever encrypted to itself
+            assert!(ciphertext
+                .clone()
+                .zip(message.bytes())
+                .all(|(c, p)| !p.is_ascii_alphabetic() || c != p));
+            assert_eq!(ciphertext.enigma(&machine).inspect_state(), message);
+        }
+    }
+
+    #[test]
+    fn test_enigma_state() {
+        let mut ciphertext = String::from("HELLO").serialise().enigma(
+            &Enigma::m3(
+                Reflector::B,
+                [Rotor::II, Rotor::IV, Rotor::V],
+                "BUL",
+                "BLA",
+                "AV BS",
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            ciphertext.cypher().inspect_state(),
+            "Reflector B, rotors II IV V, rings BUL, positions BLA, plugboard AV BS"
+        );
+        ciphertext.by_ref().take(2).count();
+        assert_eq!(ciphertext.cypher().positions(), "BLC");
+    }
+
+    #[test]
+    fn test_enigma_historical_messages() {
+        // Operation Barbarossa, 1941
+        let machine = Enigma::m3(
+            Reflector::B,
+            [Rotor::II, Rotor::IV, Rotor::V],
+            "BUL",
+            "BLA",
+            "AV BS CG DL FU HZ IN KM OW RX",
+        )
+        .unwrap();
+        let plaintext = String::from(
+            "EDPUD NRGYS ZRCXN UYTPO MRMBO FKTBZ REZKM LXLVE FGUEY SIOZV EQMIK UBPMM YLKLT TDEIS \
+             MDICA GYKUA CTCDO MOHWX MUUIA UBSTS LRNBZ SZWNR FXWFY SSXJZ VIJHI DISHP RKLKA YUPAD \
+             TXQSP INQMA TLPIF SVKDA SCTAC DPBOP VHJK",
+        )
+        .serialise()
+        .enigma(&machine)
+        .inspect_state()
+        .replace(' ', "");
+        assert_eq!(
+            plaintext,
+            "AUFKLXABTEILUNGXVONXKURTINOWAXKURTINOWAXNORDWESTLXSEBEZXSEBEZXUAFFLIEGERSTRASZERIQTUNG\
+             XDUBROWKIXDUBROWKIXOPOTSCHKAXOPOTSCHKAXUMXEINSAQTDREINULLXUHRANGETRETENXANGRIFFXINFXRGTX"
+        );
+
+        // a message from the M4 of U-264, 1942
+        let machine = Enigma::m4(
+            Reflector::BThin,
+            Rotor::Beta,
+            [Rotor::II, Rotor::IV, Rotor::I],
+            "AAAV",
+            "VJNA",
+            "AT BL DF GJ HM NW OP QY RZ VX",
+        )
+        .unwrap();
+        let plaintext = String::from(
+            "NCZW VUSX PNYM INHZ XMQX SFWX WLKJ AHSH NMCO CCAK UQPM KCSM HKSE INJU SBLK IOSX CKUB \
+             HMLL XCSJ USRR DVKO HULX WCCB GVLI YXEO AHXR HKKF VDRE WEZL XOBA FGYU JQUK GRTV UKAM \
+             EURB VEKS UHHV OYHA BCJW MAKL FKLM YFVN RIZR VVRT KOFD ANJM OLBG FFLE OPRG TFLV RHOW \
+             OPBE KVWM UQFM PWPA RMFH AGKX IIBG",
+        )
+        .serialise()
+        .enigma(&machine)
+        .inspect_state()
+        .replace(' ', "");
+        assert_eq!(
+            plaintext,
+            "VONVONJLOOKSJHFFTTTEINSEINSDREIZWOYYQNNSNEUNINHALTXXBEIANGRIFFUNTERWASSERGEDRUECKTYW\
+             ABOSXLETZTERGEGNERSTANDNULACHTDREINULUHRMARQUANTONJOTANEUNACHTSEYHSDREIYZWOZWONULGRA\
+             DYACHTSMYSTOSSENACHXEKNSVIERMBFAELLTYNNNNNNOOOVIERYSICHTEINSNULL"
+        );
+    }
+
+    #[test]
+    fn test_enigma_invalid_settings() {
+        let rotors = [Rotor::I, Rotor::II, Rotor::III];
+        assert!(Enigma::m3(Reflector::BThin, rotors, "AAA", "AAA", "").is_err());
+        assert!(Enigma::m3(
+            Reflector::B,
+            [Rotor::I, Rotor::I, Rotor::III],
+            "AAA",
+            "AAA",
+            ""
+        )
+        .is_err());
+        assert!(Enigma::m3(
+            Reflector::B,
+            [Rotor::Beta, Rotor::I, Rotor::III],
+            "AAA",
+            "AAA",
+            ""
+        )
+        .is_err());
+        assert!(Enigma::m3(Reflector::B, rotors, "AA", "AAA", "").is_err());
+        assert!(Enigma::m3(Reflector::B, rotors, "AAA", "A1A", "").is_err());
+        assert!(Enigma::m3(Reflector::B, rotors, "AAA", "AAA", "AB BC").is_err());
+        assert!(Enigma::m3(Reflector::B, rotors, "AAA", "AAA", "AA").is_err());
+        assert!(Enigma::m3(Reflector::B, rotors, "AAA", "AAA", "ABC").is_err());
+        assert!(Enigma::m4(Reflector::B, Rotor::Beta, rotors, "AAAA", "AAAA", "").is_err());
+        assert!(Enigma::m4(Reflector::BThin, Rotor::IV, rotors, "AAAA", "AAAA", "").is_err());
+        assert!(Enigma::m4(Reflector::CThin, Rotor::Gamma, rotors, "AAA", "AAAA", "").is_err());
+    }
+
+    #[test]
+    fn test_fractionating() {
+        let adfgvx = Adfgvx::new(
+            PolybiusSquare::adfgvx("NA1C3H8TB2OME5WRPD4F6G7I9J0KLQSUVXYZ"),
+            ColumnarKey::from_keyword("PRIVACY", Padding::Irregular).unwrap(),
+        );
+        let nihilist = Nihilist::latin("ZEBRAS", "RUSSIAN").unwrap();
+        let letters = |s: &str| {
+            s.to_uppercase()
+                .replace('J', "I")
+                .chars()
+                .filter(char::is_ascii_uppercase)
+                .collect::<String>()
+        };
+
+        for message in TEST_MESSAGES {
+            for period in [None, Some(1), Some(5), Some(7)] {
+                test_polygraphic_raw(message, Bifid::latin("KEYWORD", period).unwrap());
+                test_polygraphic_raw(message, Trifid::latin("KEYWORD", period).unwrap());
+            }
+
+            let decrypted = message
+                .to_owned()
+                .serialise()
+                .adfgvx(&adfgvx)
+                .adfgvx_decrypt(&adfgvx)
+                .inspect_state();
+            let expected: String = message
+                .chars()
+                .filter(char::is_ascii_alphanumeric)
+                .collect();
+            assert_eq!(decrypted, expected);
+
+            let decrypted = message
+                .to_owned()
+                .serialise()
+                .nihilist(&nihilist)
+                .nihilist_decrypt(&nihilist)
+                .inspect_state();
+            assert_eq!(decrypted, letters(message));
+
+            let square = PolybiusSquare::numeric("KEYWORD");
+            let decrypted = message
+                .to_owned()
+                .serialise()
+                .polybius(&square)
+                .unpolybius(&square)
+                .inspect_state();
+            // NOTE: digits do not keep the case of the letters
+            assert_eq!(decrypted, message.to_uppercase().replace('J', "I"));
+        }
+    }
+
+    #[test]
+    fn test_fractionating_known_answer() {
+        assert_eq!(
+            String::from("HELLO, WORLD")
+                .serialise()
+                .polybius(&PolybiusSquare::numeric(""))
+                .inspect_state(),
+            "2315313134, 5234423114"
+        );
+
+        let adfgvx = Adfgvx::new(
+            PolybiusSquare::adfgvx("NA1C3H8TB2OME5WRPD4F6G7I9J0KLQSUVXYZ"),
+            ColumnarKey::from_keyword("PRIVACY", Padding::Irregular).unwrap(),
+        );
+        assert_eq!(
+            String::from("ATTACK AT 1200AM")
+                .serialise()
+                .adfgvx(&adfgvx)
+                .inspect_state(),
+            "DGDDDAGDDGAFADDFDADVDVFAADVX"
+        );
+        assert_eq!(
+            String::from("DGDD DAGD DGAF ADDF DADV DVFA ADVX")
+                .serialise()
+                .adfgvx_decrypt(&adfgvx)
+                .inspect_state(),
+            "ATTACKAT1200AM"
+        );
+
+        let square = KeySquare::new(
+            &Alphabet::letters(),
+            5,
+            5,
+            "BGWKZQPNDSIOAXEFCLUMTHYVR",
+            &[("J", "I")],
+        )
+        .unwrap();
+        let bifid = Bifid::new(square, None).unwrap();
+        assert_eq!(
+            String::from("FLEE AT ONCE")
+                .serialise()
+                .encrypt_grams(bifid.clone())
+                .inspect_state(),
+            "UAEO LW RINS"
+        );
+
+        let trifid = Trifid::latin("FELIX MARIE DELASTELLE", Some(5)).unwrap();
+        let ciphertext = String::from("aide-toi, le ciel t'aidera")
+            .serialise()
+            .encrypt_grams(trifid.clone())
+            .with_policy(OutOfAlphabet::Drop)
+            .inspect_state();
+        assert_eq!(ciphertext, "fmjfvoissuftfpufeqqc");
+        assert_eq!(
+            ciphertext.serialise().decrypt_grams(trifid).inspect_state(),
+            "aidetoilecieltaidera"
+        );
+
+        let nihilist = Nihilist::latin("ZEBRAS", "RUSSIAN").unwrap();
+        let ciphertext = String::from("DYNAMITE WINTER PALACE")
+            .serialise()
+            .nihilist(&nihilist)
+            .inspect_state();
+        assert_eq!(
+            ciphertext,
+            "37 106 62 36 67 47 86 26 104 53 62 77 27 55 57 66 55 36 54 27"
+        );
+        assert_eq!(
+            ciphertext
+                .serialise()
+                .nihilist_decrypt(&nihilist)
+                .inspect_state(),
+            "DYNAMITEWINTERPALACE"
+        );
+    }
+
+    #[test]
+    fn test_fractionating_invalid() {
+        assert!(Bifid::latin("", Some(0)).is_err());
+        assert!(Trifid::new(&Alphabet::letters(), "", None).is_err());
+        assert!(Nihilist::latin("ZEBRAS", "123").is_err());
+        assert!(
+            PolybiusSquare::new(KeySquare::latin(""), &Alphabet::custom("123").unwrap()).is_err()
+        );
+
+        let nihilist = Nihilist::latin("ZEBRAS", "RUSSIAN").unwrap();
+        for invalid in ["37 10a", "37 5", "37 999"] {
+            let mut decrypted = String::from(invalid)
+                .serialise()
+                .nihilist_decrypt(&nihilist);
+            assert_eq!(decrypted.by_ref().count(), 1);
+            assert!(decrypted.error().is_some());
+        }
+    }
+
+    #[test]
+    fn test_transposition() {
+        let filler = || Padding::Filler(String::from("X"));
+        for message in TEST_MESSAGES {
+            for rails in 1..6 {
+                for offset in 0..4 {
+                    test_transposition_raw(message, RailFence::new(rails, offset).unwrap());
+                }
+            }
+            for keyword in ["ZEBRAS", "A", "LONGERKEYWORD"] {
+                for padding in [Padding::Irregular, filler()] {
+                    let key = ColumnarKey::from_keyword(keyword, padding).unwrap();
+                    test_transposition_raw(message, key.clone());
+                    test_transposition_raw(
+                        message,
+                        DoubleColumnar::new(
+                            key,
+                            ColumnarKey::from_keyword("KEY", Padding::Irregular).unwrap(),
+                        ),
+                    );
+                }
+            }
+            for path in [
+                RoutePath::Spiral,
+                RoutePath::AnticlockwiseSpiral,
+                RoutePath::Boustrophedon,
+            ] {
+                for width in [1, 2, 5, 100] {
+                    test_transposition_raw(
+                        message,
+                        Route::new(width, path, Padding::Irregular).unwrap(),
+                    );
+                    test_transposition_raw(message, Route::new(width, path, filler()).unwrap());
+                }
+            }
+            for faces in [1, 3, 4, 100] {
+                test_transposition_raw(message, Scytale::new(faces, Padding::Irregular).unwrap());
+                test_transposition_raw(message, Scytale::new(faces, filler()).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_transposition_known_answer() {
+        let message = String::from("WE ARE DISCOVERED. FLEE AT ONCE");
+        let letters = Alphabet::uppercase().with_policy(OutOfAlphabet::Drop);
+
+        let rail_fence = RailFence::new(3, 0).unwrap();
+        assert_eq!(
+            message
+                .serialise()
+                .transpose(rail_fence.clone())
+                .in_alphabet(&letters)
+                .inspect_state(),
+            "WECRLTEERDSOEEFEAOCAIVDEN"
+        );
+
+        let columnar = ColumnarKey::from_keyword("ZEBRAS", Padding::Irregular).unwrap();
+        assert_eq!(
+            message
+                .serialise()
+                .transpose(columnar)
+                .in_alphabet(&letters)
+                .inspect_state(),
+            "EVLNACDTESEAROFODEECWIREE"
+        );
+
+        let scytale = Scytale::new(4, Padding::Irregular).unwrap();
+        assert_eq!(
+            String::from("IAMHURTVERYBADLYHELP")
+                .serialise()
+                .transpose(scytale)
+                .inspect_state(),
+            "IRYYATBHMVAEHEDLURLP"
+        );
+
+        let spiral = Route::new(3, RoutePath::Spiral, Padding::Irregular).unwrap();
+        assert_eq!(
+            String::from("ABCDEFGHI")
+                .serialise()
+                .transpose(spiral)
+                .inspect_state(),
+            "ABCFIHGDE"
+        );
+        let spiral = Route::new(3, RoutePath::AnticlockwiseSpiral, Padding::Irregular).unwrap();
+        assert_eq!(
+            String::from("ABCDEFGHI")
+                .serialise()
+                .transpose(spiral)
+                .inspect_state(),
+            "ADGHIFCBE"
+        );
+    }
+
+    #[test]
+    fn test_transposition_padding() {
+        // a message shorter than the key
+        let padded = ColumnarKey::from_keyword("CAB", Padding::Filler(String::from("X"))).unwrap();
+        assert_eq!(
+            String::from("HI")
+                .serialise()
+                .transpose(padded.clone())
+                .inspect_state(),
+            "IXH"
+        );
+        assert_eq!(
+            String::from("IXH")
+                .serialise()
+                .untranspose(padded)
+                .inspect_state(),
+            "HIX"
+        );
+
+        let irregular = ColumnarKey::from_keyword("CAB", Padding::Irregular).unwrap();
+        assert_eq!(
+            String::from("HI")
+                .serialise()
+                .transpose(irregular)
+                .inspect_state(),
+            "IH"
+        );
+
+        // the filler has to be in the alphabet
+        let padded = ColumnarKey::from_keyword("CAB", Padding::Filler(String::from("x"))).unwrap();
+        let mut ciphertext = String::from("HI")
+            .serialise()
+            .transpose(padded)
+            .in_alphabet(&Alphabet::uppercase());
+        assert_eq!(ciphertext.by_ref().count(), 0);
+        assert!(ciphertext.error().is_some());
+    }
+
+    #[test]
+    fn test_transposition_keeps_punctuation_in_place() {
+        let key = ColumnarKey::from_keyword("KEY", Padding::Irregular).unwrap();
+        let ciphertext = String::from("Hello, World!")
+            .serialise()
+            .transpose(key.clone())
+            .letters_only();
+        let ciphertext = ciphertext.inspect_state();
+        assert_eq!(&ciphertext[5..7], ", ");
+        assert_eq!(&ciphertext[12..], "!");
+        assert_eq!(
+            ciphertext
+                .serialise()
+                .untranspose(key)
+                .letters_only()
+                .inspect_state(),
+            "Hello, World!"
+        );
+    }
+
+    #[test]
+    fn test_block_modes() {
+        let cypher = Feistel::new(b"YELLOW SUBMARINE");
+        for message in TEST_MESSAGES {
+            test_block_raw(message, &cypher, BlockMode::Ecb);
+            test_block_raw(message, &cypher, BlockMode::Cbc { iv: vec![7; 16] });
+        }
+
+        // equal blocks of plaintext only stay equal without chaining
+        let message = vec![b'A'; 64];
+        let ecb: Vec<u8> = message
+            .clone()
+            .into_iter()
+            .encrypt_blocks(&cypher, BlockMode::Ecb)
+            .collect();
+        assert_eq!(ecb.len(), 80);
+        assert_eq!(ecb[..16], ecb[16..32]);
+        assert_ne!(ecb[48..64], ecb[64..]);
+        let cbc: Vec<u8> = message
+            .into_iter()
+            .encrypt_blocks(&cypher, BlockMode::Cbc { iv: vec![0; 16] })
+            .collect();
+        assert_ne!(cbc[..16], cbc[16..32]);
+        // the first block of CBC with a zero IV is the same as ECB
+        assert_eq!(ecb[..16], cbc[..16]);
+    }
+
+    #[test]
+    fn test_feistel() {
+        let cypher = Feistel::new(b"key");
+        assert_eq!(cypher.block_size(), 16);
+
+        let mut block = *b"sixteen byte blk";
+        cypher.encrypt_block(&mut block);
+        assert_ne!(&block, b"sixteen byte blk");
+        let mut other = *b"sixteen byte blk";
+        Feistel::new(b"kez").encrypt_block(&mut other);
+        assert_ne!(block, other);
+        cypher.decrypt_block(&mut block);
+        assert_eq!(&block, b"sixteen byte blk");
+    }
+
+    #[test]
+    fn test_pkcs7() {
+        assert_eq!(
+            pkcs7_pad(b"YELLOW SUBMARINE", 20),
+            b"YELLOW SUBMARINE\x04\x04\x04\x04"
+        );
+        assert_eq!(pkcs7_pad(b"", 4), [4; 4]);
+        assert_eq!(pkcs7_pad(b"ABCD", 4), b"ABCD\x04\x04\x04\x04");
+        assert_eq!(
+            pkcs7_unpad(b"ICE ICE BABY\x04\x04\x04\x04", 16).unwrap(),
+            b"ICE ICE BABY"
+        );
+        assert!(pkcs7_unpad(b"ICE ICE BABY\x05\x05\x05\x05", 16).is_err());
+        assert!(pkcs7_unpad(b"ICE ICE BABY\x01\x02\x03\x04", 16).is_err());
+        assert!(pkcs7_unpad(b"ICE ICE BABY\x00", 13).is_err());
+        assert!(pkcs7_unpad(b"ICE\x01", 16).is_err());
+        assert!(pkcs7_unpad(b"", 16).is_err());
+    }
+
+    #[test]
+    fn test_block_errors() {
+        let cypher = Feistel::new(b"key");
+
+        let mut transform = vec![0u8; 20]
+            .into_iter()
+            .decrypt_blocks(&cypher, BlockMode::Ecb);
+        assert_eq!(transform.by_ref().count(), 0);
+        assert!(transform.error().is_some());
+
+        let mut transform = Vec::new()
+            .into_iter()
+            .decrypt_blocks(&cypher, BlockMode::Ecb);
+        assert_eq!(transform.by_ref().count(), 0);
+        assert!(transform.error().is_some());
+
+        let mut transform = vec![1u8; 5]
+            .into_iter()
+            .encrypt_blocks(&cypher, BlockMode::Cbc { iv: vec![0; 8] });
+        assert_eq!(transform.by_ref().count(), 0);
+        assert_eq!(
+            transform.error().unwrap().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        // a flipped bit in the last block breaks the padding
+        let mut ciphertext: Vec<u8> = String::from("Attack at dawn")
+            .serialise()
+            .encrypt_blocks(&cypher, BlockMode::Ecb)
+            .collect();
+        ciphertext[15] ^= 1;
+        let mut transform = ciphertext
+            .into_iter()
+            .decrypt_blocks(&cypher, BlockMode::Ecb);
+        assert_eq!(transform.by_ref().count(), 0);
+        assert!(transform.error().is_some());
+
+        // the padding can not count more than 255 bytes
+        struct Wide;
+        impl BlockCypher for Wide {
+            fn block_size(&self) -> usize {
+                256
+            }
+            fn encrypt_block(&self, _block: &mut [u8]) {}
+            fn decrypt_block(&self, _block: &mut [u8]) {}
+        }
+        let mut transform = vec![1u8; 5]
+            .into_iter()
+            .encrypt_blocks(Wide, BlockMode::Ecb);
+        assert_eq!(transform.by_ref().count(), 0);
+        assert_eq!(
+            transform.error().unwrap().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn test_mt19937() {
+        // the outputs of the reference implementation for its default seed
+        let mut rng = Mt19937::new(5489);
+        assert_eq!(rng.next_u32(), 3499211612);
+        assert_eq!(rng.next_u32(), 581869302);
+        (2..9999).for_each(|_| {
+            rng.next_u32();
+        });
+        assert_eq!(rng.next_u32(), 4123659995);
+
+        let keystream: Vec<u8> = Mt19937::new(1).keystream().take(8).collect();
+        let mut rng = Mt19937::new(1);
+        let words = [rng.next_u32(), rng.next_u32()];
+        assert_eq!(keystream[..4], words[0].to_le_bytes());
+        assert_eq!(keystream[4..], words[1].to_le_bytes());
+
+        for message in TEST_MESSAGES {
+            let ciphertext: Vec<u8> = String::from(message)
+                .serialise()
+                .xor(Mt19937::new(1234).keystream())
+                .collect();
+            assert_eq!(ciphertext.len(), message.len());
+            let decrypted = ciphertext
+                .into_iter()
+                .xor(Mt19937::new(1234).keystream())
+                .inspect_state();
+            assert_eq!(decrypted, message);
+        }
+    }
 }
 
 fn test_caesar_raw(plaintext: String) {
@@ -56,14 +1359,233 @@ fn test_vernam_raw(plaintext: String, key: String) {
     let key = key.serialise();
 
     let expected = plaintext.inspect_state();
-    // NOTE: it is truncated to the length of the shortest of the streams
-    let expected = &expected.as_bytes()[..std::cmp::min(key.len(), expected.len())];
-    let expected = String::from_utf8_lossy(expected);
-    let expected = expected.deref();
+    let shortest = std::cmp::min(key.len(), expected.len());
+
+    // NOTE: the truncating mode cuts the message to the length of the key
+    let truncated = String::from_utf8_lossy(&expected.as_bytes()[..shortest]);
+    assert_eq!(
+        truncated.deref(),
+        plaintext
+            .clone()
+            .xor_truncating(key.clone())
+            .xor_truncating(key.clone())
+            .inspect_state(),
+        "The decrypted text does not match the truncated original"
+    );
+
+    // a one-time pad that is too short is reported rather than silently cutting the message
+    let mut ciphertext = plaintext.clone().xor(key.clone());
+    let encrypted: Vec<u8> = ciphertext.by_ref().collect();
+    assert_eq!(encrypted.len(), shortest);
+    assert_eq!(ciphertext.error().is_some(), key.len() < expected.len());
+    if ciphertext.error().is_none() {
+        assert_eq!(
+            expected,
+            encrypted.into_iter().xor(key.clone()).inspect_state(),
+            "The decrypted text does not match the original"
+        );
+    }
+
+    // NOTE: there is nothing to repeat in an empty key
+    if key.len() > 0 {
+        assert_eq!(
+            expected,
+            plaintext
+                .xor_repeating(key.clone())
+                .xor_repeating(key)
+                .inspect_state(),
+            "The decrypted text does not match the original"
+        );
+    }
+}
+
+fn test_lfsr_xor_raw(plaintext: String) {
+    let keystream = Lfsr::new(16, 0b1011_0100_0000_0000, 0xACE1);
+
+    let ciphertext = plaintext
+        .serialise()
+        .bits()
+        .xor_bits(keystream.clone())
+        .bytes(BitPadding::Drop);
 
+    assert_eq!(
+        plaintext,
+        ciphertext
+            .bits()
+            .xor_bits(keystream)
+            .bytes(BitPadding::Drop)
+            .inspect_state(),
+        "The decrypted text does not match the original"
+    );
+}
+
+fn test_bit_permutation_raw(plaintext: String) {
+    // NOTE: a block of 12 bits does not line up with the bytes, so the last partial block gets passed through
+    let permutation = vec![3, 7, 11, 0, 4, 8, 1, 5, 9, 2, 6, 10];
+
+    let ciphertext = plaintext
+        .serialise()
+        .bits()
+        .permute_bits(permutation.clone())
+        .bytes(BitPadding::Drop);
+
+    assert_eq!(
+        plaintext,
+        ciphertext
+            .bits()
+            .unpermute_bits(permutation)
+            .bytes(BitPadding::Drop)
+            .inspect_state(),
+        "The decrypted text does not match the original"
+    );
+}
+
+fn test_polyalphabetic_raw(plaintext: String, key: String) {
+    let key = key.serialise();
+    // NOTE: there is nothing to encrypt with if the key is empty, or has no letters for a letters-only cypher
+    if key.len() == 0 && !plaintext.is_empty() {
+        let mut transforms = [
+            plaintext.clone().serialise().vigenere(key.clone()),
+            plaintext.clone().serialise().autokey(key.clone()),
+            plaintext
+                .clone()
+                .serialise()
+                .vigenere(String::from("1 2").serialise())
+                .letters_only(),
+        ];
+        for transform in transforms.iter_mut() {
+            assert_eq!(transform.by_ref().count(), 0);
+            assert_eq!(
+                transform.error().map(|e| e.kind()),
+                Some(std::io::ErrorKind::InvalidInput),
+                "A key without any symbols should be reported"
+            );
+        }
+        return;
+    }
+    let expected = plaintext.clone();
+
+    let round_trips = [
+        plaintext
+            .serialise()
+            .vigenere(key.clone())
+            .vigenere_decrypt(key.clone())
+            .inspect_state(),
+        plaintext
+            .serialise()
+            .beaufort(key.clone())
+            .beaufort_decrypt(key.clone())
+            .inspect_state(),
+        plaintext
+            .serialise()
+            .variant_beaufort(key.clone())
+            .variant_beaufort_decrypt(key.clone())
+            .inspect_state(),
+        plaintext
+            .serialise()
+            .autokey(key.clone())
+            .autokey_decrypt(key.clone())
+            .inspect_state(),
+    ];
+    for decrypted in round_trips {
+        assert_eq!(
+            expected, decrypted,
+            "The decrypted text does not match the original"
+        );
+    }
+
+    // a variant Beaufort encryption is a Vigenère decryption
+    assert!(plaintext
+        .serialise()
+        .variant_beaufort(key.clone())
+        .eq(plaintext.serialise().vigenere_decrypt(key.clone())));
+
+    let decrypted = plaintext
+        .serialise()
+        .vigenere(key.clone())
+        .letters_only()
+        .vigenere_decrypt(key)
+        .letters_only();
     assert_eq!(
         expected,
-        plaintext.xor(key.clone()).xor(key).inspect_state(),
+        decrypted.inspect_state(),
+        "The letters-only round trip should keep the rest of the text"
+    );
+}
+
+fn test_transposition_raw<T: Transposition + Clone>(plaintext: &str, transposition: T) {
+    let ciphertext = plaintext
+        .to_owned()
+        .serialise()
+        .transpose(transposition.clone());
+    let decrypted = ciphertext.untranspose(transposition).inspect_state();
+
+    // NOTE: the padding is left at the end of the plaintext
+    assert_eq!(
+        decrypted.trim_end_matches('X'),
+        plaintext.trim_end_matches('X'),
+        "The decrypted text does not match the original"
+    );
+}
+
+fn test_polygraphic_raw<C: PolygraphicCypher + Clone>(plaintext: &str, cypher: C) {
+    let alphabet = cypher.alphabet().clone();
+    let symbol = |index: usize| alphabet.symbol(index).unwrap()[0].to_ascii_uppercase();
+    let fillers: Vec<u8> = std::iter::once(None)
+        .chain((0..alphabet.len()).map(Some))
+        .filter_map(|previous| cypher.filler(previous))
+        .map(symbol)
+        .collect();
+
+    let decrypted = plaintext
+        .to_owned()
+        .serialise()
+        .encrypt_grams(cypher.clone())
+        .decrypt_grams(cypher)
+        .inspect_state();
+
+    // NOTE: the case is not kept within a fractionated gram, and `J` comes back as `I` on 5x5 squares
+    let same = |decrypted: u8, original: u8| {
+        let (decrypted, original) = (
+            decrypted.to_ascii_uppercase(),
+            original.to_ascii_uppercase(),
+        );
+        decrypted == original || (original == b'J' && decrypted == b'I')
+    };
+
+    // the fillers stay in the plaintext, so every byte that does not match the original has to be one
+    let mut original = plaintext.bytes().peekable();
+    for b in decrypted.bytes() {
+        if original.next_if(|&o| same(b, o)).is_none() {
+            assert!(
+                fillers.contains(&b.to_ascii_uppercase()),
+                "{decrypted:?} does not match {plaintext:?}: {:?} is not a filler",
+                b as char
+            );
+        }
+    }
+    assert!(
+        original.peek().is_none(),
+        "The decrypted text {decrypted:?} does not match the original {plaintext:?}"
+    );
+}
+
+fn test_block_raw<C: BlockCypher + Clone>(plaintext: &str, cypher: C, mode: BlockMode) {
+    let ciphertext: Vec<u8> = plaintext
+        .to_owned()
+        .serialise()
+        .encrypt_blocks(cypher.clone(), mode.clone())
+        .collect();
+    assert_eq!(
+        ciphertext.len(),
+        (plaintext.len() / cypher.block_size() + 1) * cypher.block_size()
+    );
+
+    let mut decrypted = ciphertext.into_iter().decrypt_blocks(cypher, mode);
+    assert_eq!(
+        decrypted.by_ref().collect::<Vec<u8>>(),
+        plaintext.as_bytes(),
         "The decrypted text does not match the original"
     );
+    assert!(decrypted.error().is_none());
 }
@@ -0,0 +1,140 @@
+#![allow(clippy::items_after_test_module)]
+
+use cryptography_whiteboard::{
+    common::to_hex,
+    hashing::{hash, Hasher, MerkleDamgard},
+    traits::Serialisable,
+};
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_hash_raw, test_incremental_raw};
+    use cryptography_whiteboard::{
+        common::to_hex,
+        hashing::{hmac, md5::Md5, md_padding, sha1::Sha1, sha256::Sha256, Hasher, BLOCK_SIZE},
+        traits::Serialisable,
+    };
+
+    pub const TEST_MESSAGES: [&str; 4] = [
+        "",
+        "abc",
+        "The quick brown fox jumps over the lazy dog",
+        "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+    ];
+
+    #[test]
+    fn test_md5() {
+        let digests = [
+            "d41d8cd98f00b204e9800998ecf8427e",
+            "900150983cd24fb0d6963f7d28e17f72",
+            "9e107d9d372bb6826bd81d3542a419d6",
+            "8215ef0796a20bcaaae116d3876c664a",
+        ];
+        for (message, digest) in TEST_MESSAGES.iter().zip(digests) {
+            test_hash_raw::<Md5>(message, digest);
+        }
+    }
+
+    #[test]
+    fn test_sha1() {
+        let digests = [
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+            "a9993e364706816aba3e25717850c26c9cd0d89d",
+            "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12",
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+        ];
+        for (message, digest) in TEST_MESSAGES.iter().zip(digests) {
+            test_hash_raw::<Sha1>(message, digest);
+        }
+    }
+
+    #[test]
+    fn test_sha256() {
+        let digests = [
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            "d7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592",
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ];
+        for (message, digest) in TEST_MESSAGES.iter().zip(digests) {
+            test_hash_raw::<Sha256>(message, digest);
+        }
+    }
+
+    #[test]
+    fn test_incremental_hashing() {
+        let message: Vec<u8> = (0..300u32).map(|i| (i * 7) as u8).collect();
+        for split in [0, 1, 55, 56, 63, 64, 65, 128, 299, 300] {
+            test_incremental_raw::<Md5>(&message, split);
+            test_incremental_raw::<Sha1>(&message, split);
+            test_incremental_raw::<Sha256>(&message, split);
+        }
+    }
+
+    #[test]
+    fn test_md_padding() {
+        for len in 0..200u64 {
+            let padding = md_padding(len, true);
+            assert_eq!((len as usize + padding.len()) % BLOCK_SIZE, 0);
+            assert!((9..=BLOCK_SIZE + 8).contains(&padding.len()));
+            assert_eq!(padding[0], 0x80);
+            assert_eq!(padding[padding.len() - 8..], (len * 8).to_be_bytes());
+        }
+        assert_eq!(md_padding(3, false)[53..], 24u64.to_le_bytes());
+
+        let mut hasher = Hasher::<Sha256>::new();
+        hasher.update(b"abc");
+        assert_eq!(hasher.len(), 3);
+        assert!(Hasher::<Sha256>::new().is_empty());
+        assert!(Hasher::<Sha256>::resume(&[0; 32], 65).is_err());
+        assert!(Hasher::<Sha256>::resume(&[0; 20], 64).is_err());
+        assert!(Hasher::<Sha1>::resume(&[0; 20], 64).is_ok());
+    }
+
+    #[test]
+    fn test_hmac() {
+        // RFC 2202 and RFC 4231
+        let message = || String::from("what do ya want for nothing?").serialise();
+        assert_eq!(
+            to_hex(&hmac::<Md5, _>(b"Jefe", message())),
+            "750c783e6ab0b503eaa86e310a5db738"
+        );
+        assert_eq!(
+            to_hex(&hmac::<Sha1, _>(b"Jefe", message())),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+        assert_eq!(
+            to_hex(&hmac::<Sha256, _>(b"Jefe", message())),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        // a key longer than a block is hashed first
+        let message =
+            String::from("Test Using Larger Than Block-Size Key - Hash Key First").serialise();
+        assert_eq!(
+            to_hex(&hmac::<Sha256, _>(&[0xaa; 131], message)),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+}
+
+fn test_hash_raw<H: MerkleDamgard>(message: &str, digest: &str) {
+    let hashed = hash::<H, _>(String::from(message).serialise());
+    assert_eq!(hashed.len(), H::DIGEST_LEN);
+    assert_eq!(
+        to_hex(&hashed),
+        digest,
+        "The digest of {:?} is wrong",
+        message
+    );
+}
+
+fn test_incremental_raw<H: MerkleDamgard>(message: &[u8], split: usize) {
+    let mut hasher = Hasher::<H>::new();
+    hasher.update(&message[..split]);
+    hasher.update(&message[split..]);
+    assert_eq!(
+        hasher.finalise(),
+        hash::<H, _>(Vec::from(message).into_iter())
+    );
+}